@@ -8,7 +8,7 @@ use std::{
     time::Duration,
 };
 use tokio::sync::mpsc::{self, Receiver};
-use uds_client::{ResponseSlot, UdsClient, UdsSocket, UdsSocketRx, UdsSocketTx};
+use uds_client::{ResponseSlot, UdsClient, UdsRuntime, UdsSocket, UdsSocketRx, UdsSocketTx};
 use ui::UiEventTx;
 
 mod services;
@@ -38,21 +38,26 @@ async fn main() {
     });
 
     // Create UDS client task
-    uds_client_task(tx_socket, uds_rx).await.ok();
-    response_task(rx_socket).await.ok();
+    let mut runtime = UdsRuntime::new();
+    uds_client_task(&mut runtime, tx_socket, uds_rx);
+    response_task(&mut runtime, rx_socket);
 
     // start UI
     let _ = ui.run();
+
+    // The window is closed: stop the tasks, closing the CAN socket
+    runtime.shutdown().await;
 }
 
 /// The UDS client task: receive and process the event from UI
-pub async fn uds_client_task(
+pub fn uds_client_task(
+    runtime: &mut UdsRuntime,
     tx_socket: UdsSocketTx,
     mut uds_rx: Receiver<UiEventTx>,
-) -> Result<(), ()> {
-    tokio::spawn(async move {
+) {
+    runtime.spawn(|cancel| async move {
         let mut uds_client = UdsClient::new(tx_socket, 0x784, &RESPONSE_SLOT);
-        while let Some(event) = uds_rx.recv().await {
+        while let Some(event) = cancel.run_until_cancelled(uds_rx.recv()).await.flatten() {
             match event {
                 UiEventTx::EcuReset => uds_client.run_service(UdsCommand::ECUReset).await,
                 UiEventTx::CommunicationControl => uds_client.run_service(UdsCommand::CommunicationControl).await,
@@ -60,22 +65,20 @@ pub async fn uds_client_task(
             }
         }
     });
-
-    Ok(())
 }
 
 /// The response task: handle Rx UDS socket and update to RESPONSE_SLOT
-pub async fn response_task(mut rx_socket: UdsSocketRx) -> Result<(), ()> {
-    tokio::spawn(async move {
-        loop {
-            if let Ok(frame) = rx_socket.receive_with_timeout(Duration::from_millis(10)) {
+pub fn response_task(runtime: &mut UdsRuntime, mut rx_socket: UdsSocketRx) {
+    runtime.spawn(|cancel| async move {
+        while !cancel.is_cancelled() {
+            // Drain everything already received, only sleep once the socket is empty
+            while let Some(frame) = rx_socket.try_receive() {
                 info!("Received frame: {:?}", frame);
                 if let Err(e) = RESPONSE_SLOT.update_response(frame.data().to_vec()).await {
                     error!("UDS: Failed to update response from UDS server: {:?}", e);
                 }
             }
-            tokio::time::sleep(Duration::from_millis(10)).await;
+            tokio::time::sleep(Duration::from_millis(1)).await;
         }
     });
-    Ok(())
 }
@@ -0,0 +1,277 @@
+//! # UDS command line client
+//!
+//! A headless client for scripted diagnostics (CI, bench testing) built on the public `uds-client` API.
+//!
+//! ```sh
+//! uds-cli --iface can0 --req 0x7E0 --resp 0x7E8 read-did 0xF190
+//! uds-cli --req 0x7E0 reset hard
+//! uds-cli --req 0x7E0 --resp 0x7E8 session extended
+//! uds-cli --req 0x7E0 --resp 0x7E8 read-dtc 0xFF
+//! ```
+//!
+//! Each command runs through `UdsClient::run_command` and its decoded response is printed to
+//! stdout, see `CommandOutput`. A request ID up to 0x7FF is sent as a standard (11-bit) CAN ID,
+//! a larger one as an extended (29-bit) CAN ID. The process exits with code 1 on a `DiagError`
+//! and with code 2 on invalid arguments.
+
+use std::{
+    process::ExitCode,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use automotive_diag::uds::UdsCommand;
+#[cfg(target_os = "linux")]
+use embedded_can::Frame;
+use embedded_can::{ExtendedId, Id, StandardId};
+use tokio::task::JoinHandle;
+use uds_client::{
+    DiagError, Heartbeat, ResponseSlot, RxWatchdog, UdsClient, UdsRuntime, UdsSocket, UdsSocketRx,
+    default_response_id,
+};
+
+static RESPONSE_SLOT: LazyLock<Arc<ResponseSlot>> =
+    LazyLock::new(|| Arc::new(ResponseSlot::new(None)));
+
+const USAGE: &str = "\
+Usage: uds-cli [--iface <name>] --req <id> [--resp <id>] <command> [args]
+
+Options:
+    --iface <name>   CAN interface (Linux only, default: can0)
+    --req <id>       Request CAN ID, e.g. 0x7E0: standard up to 0x7FF, extended
+                     up to 0x1FFFFFFF
+    --resp <id>      Response CAN ID (default: request ID + 8, or the swapped
+                     addresses of a 29-bit normal fixed addressing ID)
+
+Commands:
+    read-did <did>                     ReadDataByIdentifier (0x22)
+    reset <hard|key-off|soft>          ECUReset (0x11)
+    session <default|programming|extended>
+                                       DiagnosticSessionControl (0x10)
+    read-dtc [mask]                    ReadDTCInformation by status mask (0x19 0x02), default mask 0xFF";
+
+/// A parsed diagnostic request: service and its parameters.
+struct Request {
+    cmd: UdsCommand,
+    args: Vec<u8>,
+}
+
+/// Parsed command line options.
+struct Options {
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    iface: String,
+    req_id: Id,
+    resp_id: Option<u32>,
+    request: Request,
+}
+
+/// Parse a number written as decimal or as hexadecimal with a `0x` prefix.
+fn parse_number(value: &str) -> Result<u32, String> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number: '{value}'"))
+}
+
+/// Parse a request CAN ID: standard (11-bit) up to 0x7FF, extended (29-bit) up to 0x1FFFFFFF.
+fn parse_can_id(value: &str) -> Result<Id, String> {
+    let id = parse_number(value)?;
+    match u16::try_from(id).ok().and_then(StandardId::new) {
+        Some(id) => Ok(Id::Standard(id)),
+        None => ExtendedId::new(id)
+            .map(Id::Extended)
+            .ok_or(format!("CAN ID out of range: '{value}'")),
+    }
+}
+
+fn parse_request(command: &str, params: &[String]) -> Result<Request, String> {
+    let param = |idx: usize| {
+        params
+            .get(idx)
+            .map(String::as_str)
+            .ok_or(format!("missing argument for '{command}'"))
+    };
+
+    match command {
+        "read-did" => {
+            let did = parse_number(param(0)?)?;
+            let did = u16::try_from(did).map_err(|_| format!("DID out of range: 0x{did:X}"))?;
+            Ok(Request {
+                cmd: UdsCommand::ReadDataByIdentifier,
+                args: did.to_be_bytes().to_vec(),
+            })
+        }
+        "reset" => {
+            let kind = match param(0)? {
+                "hard" => 0x01,
+                "key-off" => 0x02,
+                "soft" => 0x03,
+                other => return Err(format!("unknown reset type: '{other}'")),
+            };
+            Ok(Request {
+                cmd: UdsCommand::ECUReset,
+                args: vec![kind],
+            })
+        }
+        "session" => {
+            let session = match param(0)? {
+                "default" => 0x01,
+                "programming" => 0x02,
+                "extended" => 0x03,
+                other => return Err(format!("unknown session: '{other}'")),
+            };
+            Ok(Request {
+                cmd: UdsCommand::DiagnosticSessionControl,
+                args: vec![session],
+            })
+        }
+        "read-dtc" => {
+            let mask = match params.first() {
+                Some(mask) => parse_number(mask)?,
+                None => 0xFF,
+            };
+            let mask = u8::try_from(mask).map_err(|_| format!("mask out of range: 0x{mask:X}"))?;
+            Ok(Request {
+                cmd: UdsCommand::ReadDTCInformation,
+                args: vec![0x02, mask],
+            })
+        }
+        other => Err(format!("unknown command: '{other}'")),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut iface = String::from("can0");
+    let mut req_id = None;
+    let mut resp_id = None;
+    let mut idx = 0;
+
+    while let Some(arg) = args.get(idx) {
+        let mut value = || {
+            idx += 1;
+            args.get(idx).ok_or(format!("missing value for '{arg}'"))
+        };
+        match arg.as_str() {
+            "--iface" => iface = value()?.clone(),
+            "--req" => req_id = Some(parse_can_id(value()?)?),
+            "--resp" => resp_id = Some(parse_number(value()?)?),
+            command => {
+                return Ok(Options {
+                    iface,
+                    req_id: req_id.ok_or("missing '--req'")?,
+                    resp_id,
+                    request: parse_request(command, &args[idx + 1..])?,
+                });
+            }
+        }
+        idx += 1;
+    }
+
+    Err(String::from("missing command"))
+}
+
+/// Returns the raw value of a CAN ID.
+fn raw_id(id: Id) -> u32 {
+    match id {
+        Id::Standard(id) => id.as_raw().into(),
+        Id::Extended(id) => id.as_raw(),
+    }
+}
+
+/// Restart the RX task when it doesn't beat within this period.
+const RX_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Feed every received CAN frame into the response slot, beating `heartbeat` on each loop.
+/// On Linux, the bus errors are reported to the slot as well: a transmit timeout fails the
+/// request with `DiagError::TxTimeout` instead of waiting for the ECU.
+fn spawn_response_task(mut rx_socket: UdsSocketRx, heartbeat: Heartbeat) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        #[cfg(target_os = "linux")]
+        let mut bus_errors = rx_socket.error_monitor();
+        loop {
+            // Drain everything already received, only sleep once the socket is empty
+            while let Some(frame) = rx_socket.try_receive() {
+                #[cfg(target_os = "linux")]
+                RESPONSE_SLOT
+                    .update_response_from(raw_id(frame.id()), frame.data().to_vec())
+                    .await;
+                #[cfg(target_os = "windows")]
+                RESPONSE_SLOT.update_response(frame.data().to_vec()).await;
+                heartbeat.beat();
+            }
+            #[cfg(target_os = "linux")]
+            while let Ok(error) = bus_errors.try_recv() {
+                RESPONSE_SLOT.update_bus_error(error).await;
+            }
+            heartbeat.beat();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    })
+}
+
+async fn run(options: Options) -> Result<(), DiagError> {
+    let resp_id = options
+        .resp_id
+        .unwrap_or_else(|| default_response_id(raw_id(options.req_id)));
+    #[cfg(target_os = "linux")]
+    let (tx_socket, rx_socket) = UdsSocket::new(&options.iface, resp_id).split();
+    #[cfg(target_os = "windows")]
+    let (tx_socket, rx_socket) = UdsSocket::new(resp_id).split();
+    // The first RX task uses the socket opened with the TX one, a restart opens a new socket
+    let mut rx_socket = Some(rx_socket);
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    let iface = options.iface.clone();
+    let mut runtime = UdsRuntime::new();
+    runtime.track(RxWatchdog::new(RX_GRACE_PERIOD).spawn(move |heartbeat| {
+        let rx_socket = rx_socket.take().unwrap_or_else(|| {
+            #[cfg(target_os = "linux")]
+            let (_, rx_socket) = UdsSocket::new(&iface, resp_id).split();
+            #[cfg(target_os = "windows")]
+            let (_, rx_socket) = UdsSocket::new(resp_id).split();
+            rx_socket
+        });
+        spawn_response_task(rx_socket, heartbeat)
+    }));
+
+    let mut client = UdsClient::with_id_and_response_id(
+        tx_socket,
+        options.req_id,
+        Some(resp_id),
+        &RESPONSE_SLOT,
+    );
+    let Request { cmd, args } = options.request;
+    let output = client.run_command(cmd, &args).await;
+    runtime.shutdown().await;
+
+    println!("{}", output?);
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(options).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -7,6 +7,9 @@
 //! - Support for UDS over CAN (ISO 14229).
 //! - Asynchronous API using `tokio`.
 //! - Works with both Linux (`socketcan`) and Windows (`UsbCanSocket`).
+//! - Optional `serde` feature to serialize UDS frames (payloads as hex strings), e.g. for JSON logs and test fixtures, and to load `EcuProfile`s from files.
+//! - `UdsRuntime` owning the background tasks, stopped together on shutdown.
+//! - Optional `defmt` feature: frames and errors implement `defmt::Format` and the trace messages go through `defmt`.
 //!
 //! ## Running an Example
 //!
@@ -31,14 +34,19 @@
 //! ```
 //!
 //! Example usage:
-//! ```rust
-//! use uds_client_rs::UdsClient;
+//! ```rust,no_run
+//! use std::sync::{Arc, LazyLock};
+//! use uds_client::{ResponseSlot, UdsClient, UdsSocket};
+//!
+//! static RESPONSE_SLOT: LazyLock<Arc<ResponseSlot>> =
+//!     LazyLock::new(|| Arc::new(ResponseSlot::new(None)));
 //!
 //! #[tokio::main]
 //! async fn main() {
-//!     let mut client = UdsClient::new("can0", 0x784);
-//!     if let Err(e) = client.uds_reset_118().await {
-//!         eprintln!("Failed to request session: {:?}", e);
+//!     let (tx_socket, _rx_socket) = UdsSocket::new("can0", 0x7F0).split();
+//!     let mut client = UdsClient::with_response_id(tx_socket, 0x784, Some(0x7F0), &RESPONSE_SLOT);
+//!     if let Err(e) = client.uds_reset_ecu().await {
+//!         eprintln!("Failed to reset ECU: {:?}", e);
 //!     }
 //! }
 //! ```
@@ -46,8 +54,11 @@
 //! ## License
 //! This project is licensed under the MIT License.
 
+mod logging;
+mod runtime;
 mod socket_can;
 mod uds_client;
 
+pub use runtime::UdsRuntime;
 pub use socket_can::*;
 pub use uds_client::*;
@@ -0,0 +1,25 @@
+//! Internal logging macros.
+//!
+//! The crate's trace messages go through `log` by default. With the `defmt` feature they are
+//! routed through `defmt` instead, so they show up in a `defmt` pipeline. The messages keep the
+//! `std::fmt` syntax: they are formatted first and logged as a string.
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "defmt")]
+        defmt::debug!("{=str}", format!($($arg)*).as_str());
+        #[cfg(not(feature = "defmt"))]
+        log::debug!($($arg)*);
+    }};
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "defmt")]
+        defmt::warn!("{=str}", format!($($arg)*).as_str());
+        #[cfg(not(feature = "defmt"))]
+        log::warn!($($arg)*);
+    }};
+}
+
+pub(crate) use {log_debug, log_warn};
@@ -0,0 +1,80 @@
+//! Ownership and shutdown of the background tasks of an application (the UDS client task,
+//! the RX task feeding the response slot...).
+
+use std::{future::Future, time::Duration};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::logging::{log_debug, log_warn};
+
+/// Default time given to the tasks to stop after the cancellation, before they are aborted.
+const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+
+/// A handle owning the background tasks, to stop them all when the application closes.
+///
+/// The tasks spawned with `spawn` receive a `CancellationToken` and should return once it is
+/// cancelled, dropping their socket halves: the CAN socket is closed with its last half. Tasks
+/// that don't (or that were spawned elsewhere and given to `track`) are aborted. Dropping the
+/// runtime cancels and aborts all the tasks without waiting for them.
+#[derive(Debug, Default)]
+pub struct UdsRuntime {
+    cancel: CancellationToken,  // Cancelled on shutdown
+    tasks: Vec<JoinHandle<()>>, // The owned tasks
+}
+
+impl UdsRuntime {
+    /// Create a runtime owning no task yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn the task built by `task` from the cancellation token of the runtime.
+    pub fn spawn<F, Fut>(&mut self, task: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handle = tokio::spawn(task(self.cancel.child_token()));
+        self.tasks.push(handle);
+    }
+
+    /// Take ownership of a task spawned elsewhere (e.g. by `RxWatchdog::spawn`), aborted on
+    /// shutdown if it is still running after the grace period.
+    pub fn track(&mut self, handle: JoinHandle<()>) {
+        self.tasks.push(handle);
+    }
+
+    /// Returns a token cancelled on shutdown, e.g. for a task spawned elsewhere.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.child_token()
+    }
+
+    /// Stop the tasks: see `shutdown_timeout`, with a 500ms grace period.
+    pub async fn shutdown(self) {
+        self.shutdown_timeout(DEFAULT_SHUTDOWN_GRACE).await
+    }
+
+    /// Cancel the tasks and wait at most `grace` for each of them to return; the tasks still
+    /// running are aborted.
+    pub async fn shutdown_timeout(mut self, grace: Duration) {
+        self.cancel.cancel();
+        let tasks = std::mem::take(&mut self.tasks);
+        log_debug!("UDS: shutting down {} tasks", tasks.len());
+        for mut task in tasks {
+            if tokio::time::timeout(grace, &mut task).await.is_err() {
+                log_warn!("UDS: a task didn't stop within {:?}, aborting it", grace);
+                task.abort();
+                let _ = task.await;
+            }
+        }
+    }
+}
+
+impl Drop for UdsRuntime {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! CAN bus errors reported by the controller, decoded from SocketCAN error frames.
+
+#[cfg(target_os = "linux")]
+use socketcan::{CanErrorFrame, EmbeddedFrame};
+
+/// A CAN bus error reported by the controller.
+///
+/// On Linux, the controller reports them as error frames, which the RX socket forwards to its
+/// error monitor (see `UdsSocketRx::error_monitor`) instead of handing them out as data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CanBusError {
+    /// A frame could not be transmitted in time (by the netdevice driver)
+    #[error("CAN transmit timeout")]
+    TransmitTimeout,
+    /// Arbitration was lost after the given bit (0 if unspecified)
+    #[error("CAN arbitration lost after bit {0}")]
+    ArbitrationLost(u8),
+    /// The receive or transmit buffer of the controller overflowed
+    #[error("CAN controller overrun")]
+    ControllerOverrun,
+    /// Other controller problem, e.g. the error warning or error passive state
+    #[error("CAN controller problem")]
+    ControllerProblem,
+    /// A protocol violation (bit, form, stuff... error) was detected
+    #[error("CAN protocol violation")]
+    ProtocolViolation,
+    /// The transceiver reported an error, e.g. a wiring fault
+    #[error("CAN transceiver error")]
+    TransceiverError,
+    /// No node acknowledged the transmitted frame
+    #[error("CAN frame not acknowledged")]
+    NoAck,
+    /// The controller went bus-off after too many errors
+    #[error("CAN controller is bus-off")]
+    BusOff,
+    /// A bus error was detected
+    #[error("CAN bus error")]
+    BusError,
+    /// The controller was restarted after a bus-off
+    #[error("CAN controller restarted")]
+    Restarted,
+    /// Unknown error class
+    #[error("Unknown CAN bus error: 0x{0:X}")]
+    Unknown(u32),
+}
+
+#[cfg(target_os = "linux")]
+impl From<&CanErrorFrame> for CanBusError {
+    /// Decode an error frame. A frame may flag several error classes, the most severe is kept.
+    fn from(frame: &CanErrorFrame) -> Self {
+        let bits = frame.error_bits();
+        let data = frame.data();
+        if bits & 0x0040 != 0 {
+            CanBusError::BusOff
+        } else if bits & 0x0001 != 0 {
+            CanBusError::TransmitTimeout
+        } else if bits & 0x0004 != 0 {
+            // data[1]: 0x01 RX buffer overflow, 0x02 TX buffer overflow
+            if data[1] & 0x03 != 0 {
+                CanBusError::ControllerOverrun
+            } else {
+                CanBusError::ControllerProblem
+            }
+        } else if bits & 0x0002 != 0 {
+            CanBusError::ArbitrationLost(data[0])
+        } else if bits & 0x0010 != 0 {
+            CanBusError::TransceiverError
+        } else if bits & 0x0020 != 0 {
+            CanBusError::NoAck
+        } else if bits & 0x0008 != 0 {
+            CanBusError::ProtocolViolation
+        } else if bits & 0x0080 != 0 {
+            CanBusError::BusError
+        } else if bits & 0x0100 != 0 {
+            CanBusError::Restarted
+        } else {
+            CanBusError::Unknown(bits)
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! CAN FD bit timing of the PCAN-USB FD adapters.
+
+/// Clock of the PCAN-USB FD adapters, used for the bit timing.
+const PCAN_FD_CLOCK_MHZ: u32 = 80;
+/// Sample point of the computed bit timings, in percent of the bit time.
+const SAMPLE_POINT_PERCENT: u32 = 80;
+
+/// The limits of a PCAN-Basic bit timing: maximum prescaler, TSEG1 and TSEG2.
+struct TimingLimits {
+    brp: u32,   // Maximum bitrate prescaler
+    tseg1: u32, // Maximum time segment before the sample point
+    tseg2: u32, // Maximum time segment after the sample point
+}
+
+/// Limits of the nominal (arbitration phase) bit timing.
+const NOMINAL_LIMITS: TimingLimits = TimingLimits {
+    brp: 1024,
+    tseg1: 256,
+    tseg2: 128,
+};
+/// Limits of the data phase bit timing.
+const DATA_LIMITS: TimingLimits = TimingLimits {
+    brp: 1024,
+    tseg1: 32,
+    tseg2: 16,
+};
+
+/// The bitrates of a CAN FD channel: the nominal bitrate of the arbitration phase and the
+/// bitrate of the data phase (bit rate switch).
+///
+/// On Windows, open a PCAN-USB FD channel with `UdsSocket::new_fd` or `UdsSocket::with_bus_fd`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanFdConfig {
+    pub nominal_bitrate: u32, // Bitrate of the arbitration phase, e.g. 500_000
+    pub data_bitrate: u32,    // Bitrate of the data phase, e.g. 2_000_000
+}
+
+impl CanFdConfig {
+    /// The common automotive configuration: 500 kbit/s nominal, 2 Mbit/s data.
+    pub const BITRATE_500K_2M: CanFdConfig = CanFdConfig::new(500_000, 2_000_000);
+
+    /// Create a CAN FD configuration from its nominal and data bitrates, in bit/s.
+    pub const fn new(nominal_bitrate: u32, data_bitrate: u32) -> Self {
+        Self {
+            nominal_bitrate,
+            data_bitrate,
+        }
+    }
+
+    /// Returns the PCAN-Basic bitrate string (`CAN_InitializeFD`) of the configuration, for
+    /// the 80 MHz clock of the PCAN-USB FD and a sample point at 80%, e.g. for
+    /// `BITRATE_500K_2M`: `f_clock_mhz=80, nom_brp=1, nom_tseg1=127, nom_tseg2=32, nom_sjw=32,
+    /// data_brp=1, data_tseg1=31, data_tseg2=8, data_sjw=8`.
+    ///
+    /// `None` if a bitrate can't be derived exactly from the clock.
+    pub fn pcan_bitrate(&self) -> Option<String> {
+        let (nom_brp, nom_tseg1, nom_tseg2) = bit_timing(self.nominal_bitrate, &NOMINAL_LIMITS)?;
+        let (data_brp, data_tseg1, data_tseg2) = bit_timing(self.data_bitrate, &DATA_LIMITS)?;
+        Some(format!(
+            "f_clock_mhz={PCAN_FD_CLOCK_MHZ}, \
+             nom_brp={nom_brp}, nom_tseg1={nom_tseg1}, nom_tseg2={nom_tseg2}, nom_sjw={nom_tseg2}, \
+             data_brp={data_brp}, data_tseg1={data_tseg1}, data_tseg2={data_tseg2}, data_sjw={data_tseg2}"
+        ))
+    }
+}
+
+/// Compute the prescaler, TSEG1 and TSEG2 of `bitrate` with the smallest prescaler within
+/// `limits`. The synchronization segment is 1 time quantum, the SJW is TSEG2.
+fn bit_timing(bitrate: u32, limits: &TimingLimits) -> Option<(u32, u32, u32)> {
+    let clock = PCAN_FD_CLOCK_MHZ * 1_000_000;
+    (1..=limits.brp).find_map(|brp| {
+        let divider = brp.checked_mul(bitrate).filter(|divider| *divider != 0)?;
+        if !clock.is_multiple_of(divider) {
+            return None;
+        }
+        // Time quanta per bit, split at the sample point
+        let quanta = clock / divider;
+        let sample = quanta * SAMPLE_POINT_PERCENT / 100;
+        let (tseg1, tseg2) = (sample.checked_sub(1)?, quanta - sample);
+        ((1..=limits.tseg1).contains(&tseg1) && (1..=limits.tseg2).contains(&tseg2))
+            .then_some((brp, tseg1, tseg2))
+    })
+}
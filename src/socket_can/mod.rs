@@ -10,13 +10,28 @@
 //! - Includes `UdsSocketTx` and `UdsSocketRx` types for managing transmission and reception sockets separately.
 //! - Supports raw data transmission and receiving UDS frames with a response.
 //! - Wraps error handling for both platforms (Linux and Windows) with appropriate error types.
+//! - Forwards the CAN bus errors (SocketCAN error frames) to an error monitor channel.
+//! - Restarts a dead or stalled RX task with the `RxWatchdog`.
+//! - Opens the PCAN-USB FD adapters in CAN FD mode on Windows (see `CanFdConfig`).
+//! - Re-opens an unplugged PCAN adapter on Windows, publishing its `AdapterState`.
+//! - Echoes the transmitted frames for local testing: a SocketCAN socket receiving its own
+//!   frames (`UdsSocket::new_loopback`), or the in-memory `LoopbackSocket` on any platform.
 //!
 //! The module is designed to facilitate diagnostic communication over CAN, such as in automotive or embedded systems.
 
+mod bus_error;
+mod fd_config;
+mod loopback;
+mod reconnect;
+mod watchdog;
+
+pub use bus_error::CanBusError;
 #[cfg(target_os = "windows")]
-use embedded_can::ExtendedId;
+use embedded_can::{ExtendedId, StandardId};
 use embedded_can::{Frame, nb::Can};
 use embedded_io_async::ErrorType;
+pub use fd_config::CanFdConfig;
+pub use loopback::{LoopbackError, LoopbackFrame, LoopbackRx, LoopbackSocket, LoopbackTx};
 #[cfg(target_os = "windows")]
 use peak_can::{
     bus::UsbBus,
@@ -26,12 +41,22 @@ use peak_can::{
     socket::{Baudrate, RecvCan, SendCan},
     socket::{CanFrame, MessageType},
 };
+pub use reconnect::{AdapterState, DEFAULT_RECONNECT_DELAY, MAX_RECONNECT_DELAY, ReconnectBackoff};
 #[cfg(target_os = "linux")]
 use socketcan::{CanFrame, CanSocket, Socket};
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsFd, AsRawFd, RawFd};
 use std::{
     sync::{Arc, Mutex},
     time::Duration,
 };
+#[cfg(target_os = "linux")]
+use tokio::io::{Interest, unix::AsyncFd};
+#[cfg(target_os = "linux")]
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+#[cfg(target_os = "windows")]
+use tokio::sync::watch;
+pub use watchdog::{Heartbeat, RxWatchdog};
 
 #[cfg(target_os = "windows")]
 #[derive(Default, Clone, Copy)]
@@ -40,6 +65,110 @@ pub struct WrappedCanFrame(pub CanFrame);
 #[derive(Debug)]
 pub struct WrappedPcanError(pub CanError);
 
+/// A PCAN channel and its settings, to re-open it after the adapter was unplugged.
+#[cfg(target_os = "windows")]
+struct PcanAdapter {
+    bus: UsbBus,                        // The PCAN USB channel
+    server_id: u32,                     // The CAN ID accepted by the acceptance filters
+    fd: Option<CanFdConfig>,            // The CAN FD bitrates, None for classic CAN at 500K
+    backoff: Mutex<ReconnectBackoff>,   // Delay between the re-open attempts
+    state: watch::Sender<AdapterState>, // Connection state published to `adapter_state`
+}
+
+#[cfg(target_os = "windows")]
+impl PcanAdapter {
+    fn new(bus: UsbBus, server_id: u32, fd: Option<CanFdConfig>) -> Self {
+        Self {
+            bus,
+            server_id,
+            fd,
+            backoff: Mutex::new(ReconnectBackoff::default()),
+            state: watch::Sender::new(AdapterState::Connected),
+        }
+    }
+
+    /// Open the channel with its bitrates and acceptance filters.
+    fn open(&self) -> Result<UsbCanSocket, CanError> {
+        let can_socket = match self.fd {
+            Some(config) => {
+                let bitrate = config
+                    .pcan_bitrate()
+                    .expect("CAN FD bitrates not reachable with the PCAN-USB FD clock");
+                UsbCanSocket::open_fd(self.bus, &bitrate)?
+            }
+            None => UsbCanSocket::open(self.bus, Baudrate::Baud500K)?,
+        };
+        self.set_acceptance_filters(&can_socket)?;
+        Ok(can_socket)
+    }
+
+    /// Accept the frames of `server_id` on `socket`: the 29-bit filter, and the 11-bit filter
+    /// too when the ID fits in 11 bits, as the response of a standard ID request.
+    fn set_acceptance_filters(&self, socket: &UsbCanSocket) -> Result<(), CanError> {
+        use peak_can::df::{SetAcceptanceFilter11Bit, SetAcceptanceFilter29Bit};
+
+        if self.server_id <= u32::from(StandardId::MAX.as_raw()) {
+            socket.set_acceptance_filter_11bit(&[self.server_id])?;
+        }
+        socket.set_acceptance_filter_29bit(&[self.server_id])
+    }
+
+    /// Handle the PCAN error `error` of `socket`: when it tells that the adapter was
+    /// unplugged, re-open the channel in place if an attempt is due.
+    ///
+    /// Returns whether the channel was re-opened, the failed operation can then be repeated.
+    fn recover(&self, socket: &mut UsbCanSocket, error: &CanError) -> bool {
+        if !is_disconnect(error) {
+            return false;
+        }
+        self.state.send_replace(AdapterState::Disconnected);
+        let mut backoff = self.backoff.lock().unwrap();
+        let now = std::time::Instant::now();
+        if !backoff.is_due(now) {
+            return false;
+        }
+        match self.open() {
+            Ok(can_socket) => {
+                crate::logging::log_warn!("CAN: PCAN adapter re-opened");
+                *socket = can_socket;
+                backoff.reset();
+                self.state.send_replace(AdapterState::Connected);
+                true
+            }
+            Err(e) => {
+                backoff.failed(now);
+                crate::logging::log_debug!(
+                    "CAN: PCAN re-open failed {:?}, next attempt in {:?}",
+                    e,
+                    backoff.delay()
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Whether a PCAN error means the adapter handle is dead, e.g. the dongle was unplugged.
+#[cfg(target_os = "windows")]
+fn is_disconnect(error: &CanError) -> bool {
+    matches!(
+        error,
+        CanError::IllHw | CanError::IllOperation | CanError::Initialize | CanError::NoDriver
+    )
+}
+
+/// Raw descriptor of the RX socket, registered with the tokio reactor.
+/// The socket itself stays owned by the `UdsSocketRx`.
+#[cfg(target_os = "linux")]
+struct SocketFd(RawFd);
+
+#[cfg(target_os = "linux")]
+impl AsRawFd for SocketFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
 pub trait CanSocketTx {
     /// Associated frame type.
     type Frame: Frame;
@@ -47,11 +176,29 @@ pub trait CanSocketTx {
     /// Associated error type.
     type Error: embedded_can::Error;
 
-    // The transmit function
+    /// Queue `frame` for transmission, with the `embedded_can::nb::Can::transmit` semantics:
+    /// - `Ok(None)`: the frame was queued.
+    /// - `Ok(Some(pending))`: the frame was queued in place of a lower priority `pending` frame,
+    ///   bumped out of the TX buffer. `UdsClient` sends `pending` again.
+    /// - `Err(nb::Error::WouldBlock)`: the TX buffer is full, the frame wasn't queued.
+    ///
+    /// `UdsSocketTx` always returns `Ok(None)` on success: SocketCAN and PCAN queue the frames
+    /// without displacing the pending ones.
     fn transmit(
         &mut self,
         frame: &Self::Frame,
     ) -> impl std::future::Future<Output = nb::Result<Option<Self::Frame>, Self::Error>> + Send;
+
+    // Whether a transmit error means the CAN controller is bus-off
+    fn is_bus_off(error: &Self::Error) -> bool {
+        let _ = error;
+        false
+    }
+
+    // Restart the CAN controller after a bus-off, returns whether it was restarted
+    fn restart(&mut self) -> bool {
+        false
+    }
 }
 
 #[allow(dead_code)]
@@ -66,64 +213,197 @@ pub trait CanSocketRx {
     fn receive(
         &mut self,
     ) -> impl std::future::Future<Output = nb::Result<Self::Frame, Self::Error>>;
+
+    // Return a frame if one is already available, without blocking
+    fn receive_nonblocking(&mut self) -> Option<Self::Frame>;
 }
 
 pub struct UdsSocket {
     #[cfg(target_os = "linux")]
     can_socket: CanSocket,
+    #[cfg(target_os = "linux")]
+    iface: String,
     #[cfg(target_os = "windows")]
     can_socket: UsbCanSocket,
+    #[cfg(target_os = "windows")]
+    adapter: Arc<PcanAdapter>,
 }
 
+/// The transmit half of a `UdsSocket`.
+///
+/// Clones share the same socket: several tasks, each with its own `UdsClient` and
+/// `ResponseSlot`, can talk to different ECUs of the same bus without locking a shared client.
+#[derive(Clone)]
 pub struct UdsSocketTx {
     #[cfg(target_os = "linux")]
     tx: Arc<Mutex<CanSocket>>,
+    #[cfg(target_os = "linux")]
+    iface: String,
     #[cfg(target_os = "windows")]
     tx: Arc<Mutex<UsbCanSocket>>,
+    #[cfg(target_os = "windows")]
+    adapter: Arc<PcanAdapter>,
 }
 
 pub struct UdsSocketRx {
+    // Declared before `rx` so it is deregistered before the socket can be closed
+    #[cfg(target_os = "linux")]
+    async_fd: Option<AsyncFd<SocketFd>>,
     #[cfg(target_os = "linux")]
-    rx: Arc<Mutex<CanSocket>>,
+    rx: CanSocket,
+    #[cfg(target_os = "linux")]
+    errors: Option<UnboundedSender<CanBusError>>,
     #[cfg(target_os = "windows")]
     rx: Arc<Mutex<UsbCanSocket>>,
+    #[cfg(target_os = "windows")]
+    adapter: Arc<PcanAdapter>,
 }
 
 impl UdsSocket {
+    /// Open the CAN interface `socket` (e.g. `can0`), receiving only the frames of `server_id`
+    /// and the error frames.
+    ///
+    /// Each call opens an independent socket: open one per interface and give each its own
+    /// `ResponseSlot` to run several clients on several buses in the same process.
     #[cfg(target_os = "linux")]
     pub fn new(socket: &str, server_id: u32) -> Self {
+        Self::with_server_ids(socket, &[server_id])
+    }
+
+    /// Open the CAN interface `socket` (e.g. `can0`), receiving only the frames of the
+    /// `server_ids` and the error frames.
+    ///
+    /// Used to talk to several ECUs of the same bus: clone the TX half for each client, and let
+    /// the RX task hand every frame to the slot of each client with
+    /// `ResponseSlot::update_response_from`, which keeps only the frames of its ECU.
+    #[cfg(target_os = "linux")]
+    pub fn with_server_ids(socket: &str, server_ids: &[u32]) -> Self {
         use socketcan::{CanFilter, SocketOptions};
 
         let can_socket = CanSocket::open(socket).unwrap();
-        let filter = CanFilter::new(server_id, 0x1FFFFFFF);
-        let _ = can_socket.set_filters(&[filter]);
-        Self { can_socket }
+        let filters: Vec<CanFilter> = server_ids
+            .iter()
+            .map(|id| CanFilter::new(*id, 0x1FFFFFFF))
+            .collect();
+        let _ = can_socket.set_filters(&filters);
+        let _ = can_socket.set_error_filter_accept_all();
+        Self {
+            can_socket,
+            iface: socket.to_string(),
+        }
     }
 
+    /// Open the CAN interface `socket` (e.g. `vcan0`) in loopback mode: the frames transmitted
+    /// by this socket are received back, along with the frames of the `ids` and the error frames.
+    /// Include the request ID in `ids` to see the echo of the requests.
+    ///
+    /// Used to check the framing and transport path without an ECU. See `LoopbackSocket` for an
+    /// in-memory loopback without any CAN interface.
+    #[cfg(target_os = "linux")]
+    pub fn new_loopback(socket: &str, ids: &[u32]) -> Self {
+        use socketcan::SocketOptions;
+
+        let uds_socket = Self::with_server_ids(socket, ids);
+        let _ = uds_socket.can_socket.set_loopback(true);
+        let _ = uds_socket.can_socket.set_recv_own_msgs(true);
+        uds_socket
+    }
+
+    /// Open the first PCAN USB channel (`UsbBus::USB1`), receiving only the frames of `server_id`.
     #[cfg(target_os = "windows")]
     pub fn new(server_id: u32) -> Self {
-        use peak_can::df::SetAcceptanceFilter29Bit;
+        Self::with_bus(UsbBus::USB1, server_id)
+    }
 
-        let can_socket = match UsbCanSocket::open(UsbBus::USB1, Baudrate::Baud500K) {
+    /// Open the first PCAN-USB FD channel (`UsbBus::USB1`) in CAN FD mode with the bitrates of
+    /// `config`, receiving only the frames of `server_id`.
+    #[cfg(target_os = "windows")]
+    pub fn new_fd(server_id: u32, config: CanFdConfig) -> Self {
+        Self::with_bus_fd(UsbBus::USB1, server_id, config)
+    }
+
+    /// Open the PCAN USB channel `bus`, receiving only the frames of `server_id`.
+    ///
+    /// Open one socket per channel and give each its own `ResponseSlot` to run several clients
+    /// on several buses in the same process.
+    #[cfg(target_os = "windows")]
+    pub fn with_bus(bus: UsbBus, server_id: u32) -> Self {
+        Self::open_adapter(PcanAdapter::new(bus, server_id, None))
+    }
+
+    /// Open the PCAN-USB FD channel `bus` in CAN FD mode with the bitrates of `config`,
+    /// receiving only the frames of `server_id`.
+    ///
+    /// Panics if the bitrates of `config` can't be derived from the adapter clock (see
+    /// `CanFdConfig::pcan_bitrate`). Set the TX_DL of the client accordingly, see
+    /// `UdsClient::set_tx_data_length`.
+    #[cfg(target_os = "windows")]
+    pub fn with_bus_fd(bus: UsbBus, server_id: u32, config: CanFdConfig) -> Self {
+        Self::open_adapter(PcanAdapter::new(bus, server_id, Some(config)))
+    }
+
+    /// Open the channel of `adapter`. When the initialization fails, the channel is opened
+    /// without it, as already initialized by another application.
+    #[cfg(target_os = "windows")]
+    fn open_adapter(adapter: PcanAdapter) -> Self {
+        let can_socket = match adapter.open() {
             Ok(socket) => socket,
             Err(e) => {
-                log::warn!("The PCAN initialize failed {:?}, just open", e);
-                UsbCanSocket::open_with_usb_bus(UsbBus::USB1)
+                crate::logging::log_warn!("The PCAN initialize failed {:?}, just open", e);
+                let socket = UsbCanSocket::open_with_usb_bus(adapter.bus);
+                adapter.set_acceptance_filters(&socket).unwrap();
+                socket
             }
         };
-        can_socket
-            .set_acceptance_filter_29bit(&[server_id])
-            .unwrap();
-        Self { can_socket }
+        Self {
+            can_socket,
+            adapter: Arc::new(adapter),
+        }
+    }
+
+    /// Returns a receiver of the connection state of the PCAN adapter, updated when it is
+    /// unplugged and when it is re-opened.
+    ///
+    /// The socket halves detect the disconnect from the PCAN error of a send or receive, and
+    /// re-open the channel on the next ones, the attempts being spaced by a `ReconnectBackoff`.
+    /// The operation is repeated once the channel is re-opened.
+    #[cfg(target_os = "windows")]
+    pub fn adapter_state(&self) -> watch::Receiver<AdapterState> {
+        self.adapter.state.subscribe()
     }
 
+    /// Split the socket into its transmit and receive halves.
+    ///
+    /// On Linux the RX half owns a duplicate of the socket descriptor (same socket, filters and
+    /// queues), so a receive blocked on it never holds up a transmit. The PCAN channel can't be
+    /// duplicated: on Windows both halves share it, the PCAN calls don't block.
     pub fn split(self) -> (UdsSocketTx, UdsSocketRx) {
+        #[cfg(target_os = "linux")]
+        let rx = self
+            .can_socket
+            .as_fd()
+            .try_clone_to_owned()
+            .map(CanSocket::from)
+            .expect("failed to duplicate the CAN socket");
         let shared_socket = Arc::new(Mutex::new(self.can_socket));
         let rx_socket = UdsSocketRx {
+            #[cfg(target_os = "linux")]
+            async_fd: None,
+            #[cfg(target_os = "linux")]
+            rx,
+            #[cfg(target_os = "windows")]
             rx: shared_socket.clone(),
+            #[cfg(target_os = "linux")]
+            errors: None,
+            #[cfg(target_os = "windows")]
+            adapter: self.adapter.clone(),
         };
         let tx_socket = UdsSocketTx {
-            tx: shared_socket.clone(),
+            tx: shared_socket,
+            #[cfg(target_os = "linux")]
+            iface: self.iface,
+            #[cfg(target_os = "windows")]
+            adapter: self.adapter,
         };
         (tx_socket, rx_socket)
     }
@@ -160,6 +440,26 @@ impl CanSocketTx for UdsSocketTx {
     ) -> nb::Result<Option<Self::Frame>, Self::Error> {
         self.tx.lock().unwrap().transmit(frame)
     }
+
+    /// A bus-off is reported by an error frame, or by the interface going down (ENETDOWN).
+    fn is_bus_off(error: &Self::Error) -> bool {
+        match error {
+            socketcan::Error::Can(error) => matches!(error, socketcan::CanError::BusOff),
+            socketcan::Error::Io(error) => error.kind() == std::io::ErrorKind::NetworkDown,
+        }
+    }
+
+    /// Restart the interface through netlink (`ip link set <iface> type can restart`), which
+    /// requires the `CAP_NET_ADMIN` capability. The socket itself stays open.
+    fn restart(&mut self) -> bool {
+        let restarted = socketcan::CanInterface::open(&self.iface)
+            .map_err(|e| e.to_string())
+            .and_then(|iface| iface.restart().map_err(|e| e.to_string()));
+        if let Err(e) = &restarted {
+            crate::logging::log_warn!("CAN: failed to restart {}: {}", self.iface, e);
+        }
+        restarted.is_ok()
+    }
 }
 
 #[cfg(target_os = "linux")]
@@ -168,22 +468,127 @@ impl CanSocketRx for UdsSocketRx {
     type Error = socketcan::Error;
 
     async fn receive(&mut self) -> nb::Result<CanFrame, socketcan::Error> {
-        self.rx.lock().unwrap().receive()
+        self.rx.receive()
+    }
+
+    fn receive_nonblocking(&mut self) -> Option<CanFrame> {
+        self.try_receive()
     }
 }
 
 #[cfg(target_os = "linux")]
 impl UdsSocketRx {
+    /// Returns a channel receiving the CAN bus errors (error frames) seen by this socket.
+    ///
+    /// The error frames are never handed out as data frames: they are sent to the channel of
+    /// the last call, or logged when there is none.
+    pub fn error_monitor(&mut self) -> UnboundedReceiver<CanBusError> {
+        let (tx, rx) = unbounded_channel();
+        self.errors = Some(tx);
+        rx
+    }
+
+    /// Send a bus error to the error monitor, or log it when there is none.
+    fn report(&self, error: CanBusError) {
+        match &self.errors {
+            Some(errors) => {
+                let _ = errors.send(error);
+            }
+            None => crate::logging::log_warn!("CAN: bus error: {}", error),
+        }
+    }
+
+    /// Forward an error frame to the error monitor, returns the data frames unchanged.
+    fn data_frame(&self, frame: CanFrame) -> Option<CanFrame> {
+        match &frame {
+            CanFrame::Error(error_frame) => {
+                self.report(CanBusError::from(error_frame));
+                None
+            }
+            _ => Some(frame),
+        }
+    }
+
+    /// Wait at most `timeout` for the next received frame. An error frame is forwarded to the
+    /// error monitor and returned as an `io::Error` wrapping the `CanBusError`.
     pub fn receive_with_timeout(&mut self, timeout: Duration) -> socketcan::IoResult<CanFrame> {
-        self.rx.lock().unwrap().read_frame_timeout(timeout)
+        let frame = self.rx.read_frame_timeout(timeout)?;
+        if let CanFrame::Error(error_frame) = &frame {
+            let error = CanBusError::from(error_frame);
+            self.report(error);
+            return Err(std::io::Error::other(error));
+        }
+        Ok(frame)
+    }
+
+    /// Return the next received frame, or `None` right away if the socket is empty.
+    /// Error frames are skipped and forwarded to the error monitor.
+    pub fn try_receive(&mut self) -> Option<CanFrame> {
+        loop {
+            let frame = self.rx.read_frame_timeout(Duration::ZERO).ok()?;
+            if let Some(frame) = self.data_frame(frame) {
+                return Some(frame);
+            }
+        }
+    }
+
+    /// Wait for the next received frame without blocking the executor.
+    /// Error frames are skipped and forwarded to the error monitor.
+    ///
+    /// The socket is registered with the tokio reactor on the first call, so this must be
+    /// called from within a tokio runtime.
+    pub async fn recv(&mut self) -> std::io::Result<CanFrame> {
+        if self.async_fd.is_none() {
+            let fd = SocketFd(self.rx.as_raw_fd());
+            self.async_fd = Some(AsyncFd::with_interest(fd, Interest::READABLE)?);
+        }
+        let async_fd = self.async_fd.as_ref().unwrap();
+        let rx = &self.rx;
+
+        loop {
+            let mut guard = async_fd.readable().await?;
+            // The descriptor shares the blocking mode of the TX half, so only read when poll says so
+            let result = guard.try_io(|_| {
+                rx.read_frame_timeout(Duration::ZERO)
+                    .map_err(|e| match e.kind() {
+                        std::io::ErrorKind::TimedOut => std::io::ErrorKind::WouldBlock.into(),
+                        _ => e,
+                    })
+            });
+            match result {
+                Ok(Ok(frame)) => {
+                    if let Some(frame) = self.data_frame(frame) {
+                        return Ok(frame);
+                    }
+                }
+                Ok(Err(e)) => return Err(e),
+                Err(_would_block) => {}
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl WrappedPcanError {
+    /// Returns the raw PCAN error, e.g. to tell a bus-off from a full transmit queue.
+    pub fn can_error(&self) -> &CanError {
+        &self.0
+    }
+
+    /// Consume the wrapper and return the raw PCAN error.
+    pub fn into_inner(self) -> CanError {
+        self.0
     }
 }
 
 #[cfg(target_os = "windows")]
 impl embedded_can::Error for WrappedPcanError {
     fn kind(&self) -> embedded_can::ErrorKind {
+        // Full or overrun queues are transient, report them as `Overrun` so they can be retried
         match self.0 {
-            CanError::Overrun => embedded_can::ErrorKind::Overrun,
+            CanError::Overrun | CanError::QOverrun | CanError::XmtFull | CanError::QxmtFull => {
+                embedded_can::ErrorKind::Overrun
+            }
             _ => embedded_can::ErrorKind::Other,
         }
     }
@@ -193,18 +598,26 @@ impl embedded_can::Error for WrappedPcanError {
 impl embedded_can::Frame for WrappedCanFrame {
     fn new(id: impl Into<embedded_can::Id>, data: &[u8]) -> Option<Self> {
         let can_id: embedded_can::Id = id.into();
-        let raw_id = match can_id {
-            embedded_can::Id::Standard(standard_id) => standard_id.as_raw() as u32,
-            embedded_can::Id::Extended(extended_id) => extended_id.as_raw(),
+        let (raw_id, message_type) = match can_id {
+            embedded_can::Id::Standard(standard_id) => {
+                (standard_id.as_raw() as u32, MessageType::Standard)
+            }
+            embedded_can::Id::Extended(extended_id) => {
+                (extended_id.as_raw(), MessageType::Extended)
+            }
         };
-        match CanFrame::new(raw_id, MessageType::Extended, data) {
+        match CanFrame::new(raw_id, message_type, data) {
             Ok(frame) => Some(WrappedCanFrame(frame)),
             Err(_) => None,
         }
     }
 
     fn id(&self) -> embedded_can::Id {
-        embedded_can::Id::Extended(ExtendedId::new(self.0.can_id()).unwrap())
+        if self.0.is_extended_frame() {
+            embedded_can::Id::Extended(ExtendedId::new(self.0.can_id()).unwrap())
+        } else {
+            embedded_can::Id::Standard(StandardId::new(self.0.can_id() as u16).unwrap())
+        }
     }
 
     fn data(&self) -> &[u8] {
@@ -245,8 +658,9 @@ impl Can for UdsSocket {
     type Error = WrappedPcanError;
 
     fn transmit(&mut self, frame: &Self::Frame) -> nb::Result<Option<Self::Frame>, Self::Error> {
+        // PCAN queues the frame without displacing a pending one
         match self.can_socket.send(frame.0) {
-            Ok(_) => Ok(Some(Self::Frame::default())),
+            Ok(_) => Ok(None),
             Err(e) => Err(nb::Error::Other(WrappedPcanError(e))),
         }
     }
@@ -269,11 +683,22 @@ impl CanSocketTx for UdsSocketTx {
         &mut self,
         frame: &Self::Frame,
     ) -> nb::Result<Option<Self::Frame>, Self::Error> {
-        match self.tx.lock().unwrap().send(frame.0) {
-            Ok(_) => Ok(Some(Self::Frame::default())),
+        let mut socket = self.tx.lock().unwrap();
+        let result = match socket.send(frame.0) {
+            Err(e) if self.adapter.recover(&mut socket, &e) => socket.send(frame.0),
+            result => result,
+        };
+        match result {
+            Ok(_) => Ok(None),
             Err(e) => Err(nb::Error::Other(WrappedPcanError(e))),
         }
     }
+
+    /// PCAN reports the bus-off status as a send error. The driver restarts the controller
+    /// itself when its bus-off auto-reset is enabled, so `restart` is not implemented.
+    fn is_bus_off(error: &Self::Error) -> bool {
+        matches!(error.0, CanError::BusOff)
+    }
 }
 
 #[cfg(target_os = "windows")]
@@ -283,18 +708,37 @@ impl CanSocketRx for UdsSocketRx {
     type Error = WrappedPcanError;
 
     async fn receive(&mut self) -> nb::Result<Self::Frame, Self::Error> {
-        match self.rx.lock().unwrap().recv() {
+        let mut socket = self.rx.lock().unwrap();
+        let result = match socket.recv() {
+            Err(e) if self.adapter.recover(&mut socket, &e) => socket.recv(),
+            result => result,
+        };
+        match result {
             Ok(f) => Ok(WrappedCanFrame(f.0)),
             Err(e) => Err(nb::Error::Other(WrappedPcanError(e))),
         }
     }
+
+    fn receive_nonblocking(&mut self) -> Option<Self::Frame> {
+        self.try_receive().map(WrappedCanFrame)
+    }
 }
 
 #[cfg(target_os = "windows")]
 impl UdsSocketRx {
+    /// Wait at most `timeout` for the next received frame. An unplugged adapter is re-opened
+    /// as for `receive` (see `UdsSocket::adapter_state`).
     pub fn receive_with_timeout(&mut self, timeout: Duration) -> Result<CanFrame, CanError> {
         let start = chrono::Local::now();
-        while !self.rx.lock().unwrap().is_receiving()? {
+        loop {
+            let mut socket = self.rx.lock().unwrap();
+            match socket.is_receiving() {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(e) if self.adapter.recover(&mut socket, &e) => {}
+                Err(e) => return Err(e),
+            }
+            drop(socket);
             if chrono::Local::now() > start + timeout {
                 return Err(CanError::Unknown);
             }
@@ -302,4 +746,14 @@ impl UdsSocketRx {
 
         self.rx.lock().unwrap().recv_frame()
     }
+
+    /// Return the next received frame, or `None` right away if the receive queue is empty.
+    pub fn try_receive(&mut self) -> Option<CanFrame> {
+        let rx = self.rx.lock().unwrap();
+        if rx.is_receiving().ok()? {
+            rx.recv_frame().ok()
+        } else {
+            None
+        }
+    }
 }
@@ -0,0 +1,76 @@
+//! Reconnection of a USB-CAN adapter after an unplug.
+//!
+//! On Windows, the PCAN handle of an unplugged adapter fails every send and receive. The socket
+//! halves detect the disconnect from the PCAN error, re-open the channel with a backoff between
+//! the attempts, and publish the `AdapterState` (see `UdsSocket::adapter_state`).
+
+use std::time::{Duration, Instant};
+
+/// Delay before the second re-open attempt.
+pub const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+/// Maximum delay between two re-open attempts.
+pub const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Connection state of the CAN adapter, e.g. to show "adapter disconnected" in a UI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AdapterState {
+    /// The adapter is open and working
+    #[default]
+    Connected,
+    /// The adapter was unplugged: re-open attempts are made on the next send or receive
+    Disconnected,
+}
+
+/// Exponential backoff between the re-open attempts of a disconnected adapter.
+///
+/// The first attempt is due right away, each failure doubles the delay before the next one
+/// (from `initial` up to `max`), and a success resets it.
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    initial: Duration,             // Delay after the first failed attempt
+    max: Duration,                 // Maximum delay between two attempts
+    delay: Duration,               // Delay after the next failed attempt
+    next_attempt: Option<Instant>, // When the next attempt is due, None for right away
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECONNECT_DELAY, MAX_RECONNECT_DELAY)
+    }
+}
+
+impl ReconnectBackoff {
+    /// Create a backoff doubling from `initial` up to `max`.
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            delay: initial,
+            next_attempt: None,
+        }
+    }
+
+    /// Returns whether an attempt is due at `now`.
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.next_attempt.is_none_or(|next| now >= next)
+    }
+
+    /// Record a failed attempt at `now`: the next one is due after the current delay, which
+    /// doubles.
+    pub fn failed(&mut self, now: Instant) {
+        self.next_attempt = Some(now + self.delay);
+        self.delay = (self.delay * 2).min(self.max);
+    }
+
+    /// Record a successful attempt: the next disconnect is retried right away.
+    pub fn reset(&mut self) {
+        self.delay = self.initial;
+        self.next_attempt = None;
+    }
+
+    /// Returns the delay the next failed attempt will wait.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+}
@@ -0,0 +1,88 @@
+//! Supervision of the RX task feeding the response slot.
+//!
+//! A dead or wedged RX task is silent: every request then fails with a timeout although the
+//! ECU answers. The `RxWatchdog` restarts the task when it stops signalling its liveness.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+use crate::logging::log_warn;
+
+/// Liveness signal of a supervised RX task, given to the task by `RxWatchdog::spawn`.
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeat(Arc<AtomicU64>);
+
+impl Heartbeat {
+    /// Signal that the RX task is alive. Call it on every loop iteration: for each received
+    /// frame and each receive timeout.
+    pub fn beat(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of beats so far.
+    fn count(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Aborts the supervised task when the supervisor ends (e.g. aborted on shutdown).
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Restarts the RX task when it ends (e.g. a panic on a poisoned mutex) or when it doesn't
+/// beat its `Heartbeat` within the grace period (e.g. a receive blocking forever).
+#[derive(Debug, Clone, Copy)]
+pub struct RxWatchdog {
+    grace: Duration, // Maximum time between two beats of the RX task
+}
+
+impl RxWatchdog {
+    /// Create a watchdog restarting the RX task after `grace` without a beat.
+    pub fn new(grace: Duration) -> Self {
+        Self { grace }
+    }
+
+    /// Spawn the supervisor of the RX task started by `start`.
+    ///
+    /// `start` is called once right away, and again on each restart with a fresh `Heartbeat`:
+    /// it must spawn the RX task (opening its socket again if needed) and return its handle.
+    /// The stalled task is aborted before the restart; a task blocked outside of an `.await`
+    /// can't be aborted though and is left behind. Abort the returned handle to stop the
+    /// supervision and the RX task.
+    pub fn spawn<F>(self, mut start: F) -> JoinHandle<()>
+    where
+        F: FnMut(Heartbeat) -> JoinHandle<()> + Send + 'static,
+    {
+        tokio::spawn(async move {
+            loop {
+                let heartbeat = Heartbeat::default();
+                let task = AbortOnDrop(start(heartbeat.clone()));
+                let mut last = heartbeat.count();
+                loop {
+                    tokio::time::sleep(self.grace).await;
+                    if task.0.is_finished() {
+                        log_warn!("CAN: RX task ended, restarting it");
+                        break;
+                    }
+                    let count = heartbeat.count();
+                    if count == last {
+                        log_warn!("CAN: RX task stalled for {:?}, restarting it", self.grace);
+                        break;
+                    }
+                    last = count;
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,26 @@
+//! Derivation of the response CAN ID from the request CAN ID (ISO 15765-2 addressing).
+
+/// PDU format of a physically addressed ISO 15765-2 normal fixed addressing frame.
+const NORMAL_FIXED_PHYSICAL: u32 = 0xDA;
+
+/// Returns the response ID of a request sent with the 29-bit normal fixed addressing
+/// (e.g. `0x18DA10F1`: priority 6, PDU format 0xDA, target address 0x10, source address 0xF1).
+///
+/// The ECU responds with the target and source addresses swapped (`0x18DAF110`). Returns
+/// `None` for an ID using another addressing, and for a functional request (PDU format 0xDB)
+/// as each ECU responds with its own address.
+pub fn normal_fixed_response_id(request_id: u32) -> Option<u32> {
+    if request_id > 0x1FFF_FFFF || (request_id >> 16) & 0xFF != NORMAL_FIXED_PHYSICAL {
+        return None;
+    }
+    let target = (request_id >> 8) & 0xFF;
+    let source = request_id & 0xFF;
+    Some((request_id & 0x1FFF_0000) | (source << 8) | target)
+}
+
+/// Returns the CAN ID an ECU responds with when none is configured: the normal fixed
+/// addressing response ID (see `normal_fixed_response_id`), otherwise the request ID + 8
+/// (the OBD convention of the normal addressing, e.g. 0x7E0 -> 0x7E8).
+pub fn default_response_id(request_id: u32) -> u32 {
+    normal_fixed_response_id(request_id).unwrap_or(request_id + 8)
+}
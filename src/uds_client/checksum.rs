@@ -0,0 +1,123 @@
+//! Checksums of the transferred data.
+//!
+//! The CRC variants commonly used by the checkMemory routines of the ECUs, to compute the
+//! checksum of an image before a download, or to verify an image read back with
+//! `request_upload`.
+
+/// The parameters of a CRC algorithm (Rocksoft model).
+#[derive(Debug, Clone, Copy)]
+struct CrcParams {
+    width: u32,    // Width of the CRC in bits (16 or 32)
+    poly: u32,     // Generator polynomial, without the top bit
+    init: u32,     // Initial value of the register
+    reflect: bool, // Bytes and result are reflected (LSB first)
+    xor_out: u32,  // Value XORed with the final register
+}
+
+/// CRC algorithm, see `Crc::checksum`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crc {
+    /// CRC-16/CCITT-FALSE (CRC-16/IBM-3740): poly 0x1021, init 0xFFFF
+    Crc16CcittFalse,
+    /// CRC-16/XMODEM: poly 0x1021, init 0x0000
+    Crc16Xmodem,
+    /// CRC-32 (ISO-HDLC, Ethernet, zip): poly 0x04C11DB7 reflected, init and xorout 0xFFFFFFFF
+    Crc32,
+    /// CRC-32/MPEG-2: poly 0x04C11DB7 not reflected, init 0xFFFFFFFF
+    Crc32Mpeg2,
+    /// CRC-32/AUTOSAR (CRC-32P4): poly 0xF4ACFB13 reflected, init and xorout 0xFFFFFFFF
+    Crc32Autosar,
+}
+
+impl Crc {
+    fn params(self) -> CrcParams {
+        match self {
+            Crc::Crc16CcittFalse => CrcParams {
+                width: 16,
+                poly: 0x1021,
+                init: 0xFFFF,
+                reflect: false,
+                xor_out: 0x0000,
+            },
+            Crc::Crc16Xmodem => CrcParams {
+                width: 16,
+                poly: 0x1021,
+                init: 0x0000,
+                reflect: false,
+                xor_out: 0x0000,
+            },
+            Crc::Crc32 => CrcParams {
+                width: 32,
+                poly: 0x04C1_1DB7,
+                init: 0xFFFF_FFFF,
+                reflect: true,
+                xor_out: 0xFFFF_FFFF,
+            },
+            Crc::Crc32Mpeg2 => CrcParams {
+                width: 32,
+                poly: 0x04C1_1DB7,
+                init: 0xFFFF_FFFF,
+                reflect: false,
+                xor_out: 0x0000_0000,
+            },
+            Crc::Crc32Autosar => CrcParams {
+                width: 32,
+                poly: 0xF4AC_FB13,
+                init: 0xFFFF_FFFF,
+                reflect: true,
+                xor_out: 0xFFFF_FFFF,
+            },
+        }
+    }
+
+    /// Returns the width of the checksum in bytes: 2 for the CRC-16, 4 for the CRC-32.
+    pub fn size(self) -> usize {
+        (self.params().width / 8) as usize
+    }
+
+    /// Compute the checksum of `data`, the CRC-16 in the low 16 bits.
+    pub fn checksum(self, data: &[u8]) -> u32 {
+        let params = self.params();
+        let mask = u32::MAX >> (32 - params.width);
+        let top = 1 << (params.width - 1);
+        let mut crc = params.init;
+        if params.reflect {
+            // Reflected algorithm: shift right with the reflected polynomial
+            let poly = params.poly.reverse_bits() >> (32 - params.width);
+            for byte in data {
+                crc ^= *byte as u32;
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 {
+                        (crc >> 1) ^ poly
+                    } else {
+                        crc >> 1
+                    };
+                }
+            }
+        } else {
+            for byte in data {
+                crc ^= (*byte as u32) << (params.width - 8);
+                for _ in 0..8 {
+                    crc = if crc & top != 0 {
+                        (crc << 1) ^ params.poly
+                    } else {
+                        crc << 1
+                    };
+                }
+                crc &= mask;
+            }
+        }
+        (crc ^ params.xor_out) & mask
+    }
+
+    /// Returns the checksum of `data` as sent on the bus: `size()` bytes, big endian.
+    pub fn checksum_bytes(self, data: &[u8]) -> Vec<u8> {
+        self.checksum(data).to_be_bytes()[4 - self.size()..].to_vec()
+    }
+
+    /// Check `data` against the big endian checksum `expected`, e.g. the record of a
+    /// RequestTransferExit or checkMemory response. A record of another length never matches.
+    pub fn verify(self, data: &[u8], expected: &[u8]) -> bool {
+        expected == self.checksum_bytes(data)
+    }
+}
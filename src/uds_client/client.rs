@@ -12,20 +12,21 @@
 //! - Uses an **async-friendly design** to integrate with Rust's asynchronous runtime.
 //!
 //! ## Usage Example
-//! ```rust
-//! use embedded_can::{nb::Can, Frame, Id};
-//! use uds_client::{UdsClient, DiagError, ResponseSlot};
-//! use std::sync::Arc;
+//! ```rust,no_run
+//! use uds_client::{CanSocketTx, DiagError, Response, ResponseSlot, UdsClient};
+//! use std::sync::{Arc, LazyLock};
 //!
-//! async fn example_usage<T: Can>(channel: &mut T, resp_slot: &Arc<ResponseSlot>) -> Result<(), DiagError> {
-//!     let id = Id::Extended(ExtendedId::new(0x7DF).unwrap());
-//!     let mut client = UdsClient::new(channel, id, resp_slot);
+//! static RESPONSE_SLOT: LazyLock<Arc<ResponseSlot>> =
+//!     LazyLock::new(|| Arc::new(ResponseSlot::new(None)));
+//!
+//! async fn example_usage<T: CanSocketTx>(channel: T) -> Result<(), DiagError> {
+//!     let mut client = UdsClient::new(channel, 0x7DF, &RESPONSE_SLOT);
 //!
 //!     // Example: Sending a diagnostic request
-//!     client.send_command(0x10, &[0x01, 0x02, 0x03]).await?;
+//!     client.send_command(0x02, 0x10, &[0x01]).await?;
 //!
 //!     // Example: Receiving a response
-//!     if let Ok(response) = client.receive().await {
+//!     if let Response::Ok(response, _) = client.receive().await {
 //!         println!("Received response: {:?}", response);
 //!     }
 //!
@@ -43,16 +44,68 @@
 //! - [`UdsClient`] - The main client struct for handling UDS communication.
 
 use crate::socket_can::CanSocketTx;
+use automotive_diag::uds::{UdsCommand, UdsError};
+
+use super::{
+    DiagError, DidRegistry, EcuProfile, IsoTpSegmenter, LatencyStats, PaddingPolicy, PciByte,
+    PciType, Response, ResponseSlot, UdsResponseParser, default_response_id,
+    frame::{FlowStatus, FrameError, UdsFirstFrame, UdsFlowControlFrame, UdsFrame, hex_dump},
+};
+use crate::logging::{log_debug, log_warn};
+use embedded_can::{Error as _, ExtendedId, Frame, Id};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio_util::sync::CancellationToken;
 
-use super::{DiagError, Response, ResponseSlot, frame::UdsFrame};
-use embedded_can::{ExtendedId, Frame, Id};
-use log::debug;
-use std::sync::{Arc, LazyLock};
+/// Default block size advertised in our Flow Control frames (0 = no further FC required).
+pub(crate) const DEFAULT_FC_BLOCK_SIZE: u8 = 0x00;
+/// Default separation time advertised in our Flow Control frames (10ms).
+pub(crate) const DEFAULT_FC_SEPARATION_TIME: u8 = 0x0A;
+/// Maximum number of consecutive Flow Control "Wait" frames accepted before giving up (N_WFTmax).
+const MAX_FC_WAIT: usize = 10;
+/// Default N_Bs: time until the reception of the next Flow Control frame.
+pub(crate) const DEFAULT_N_BS: Duration = Duration::from_millis(1000);
+/// Default N_Cr: time until the reception of the next Consecutive Frame.
+pub(crate) const DEFAULT_N_CR: Duration = Duration::from_millis(1000);
+/// Default delay before repeating a request answered with busyRepeatRequest (NRC 0x21).
+pub(crate) const DEFAULT_BUSY_RETRY_DELAY: Duration = Duration::from_millis(100);
+/// Default ISO-TP TX_DL: the data length of a classic CAN frame.
+pub(crate) const DEFAULT_TX_DL: usize = 8;
+/// Data lengths of a CAN FD frame above 8 bytes (the valid TX_DL and the DLC padding steps).
+pub(crate) const CAN_FD_DATA_LENGTHS: [usize; 7] = [12, 16, 20, 24, 32, 48, 64];
+/// Maximum time to queue a frame while the TX buffer is full, before `DiagError::TxTimeout`.
+const TX_TIMEOUT: Duration = Duration::from_millis(100);
+/// Delay before trying again to queue a frame in a full TX buffer.
+const TX_RETRY_DELAY: Duration = Duration::from_millis(1);
+/// Maximum number of displaced frames sent again after a transmit (see `CanSocketTx::transmit`).
+const MAX_DISPLACED_FRAMES: usize = 8;
+/// Padding byte of the CAN FD frames (the ISO 15765-2 recommended value).
+const CAN_FD_PADDING: u8 = 0xCC;
 
 pub struct UdsClient<'a, T: CanSocketTx> {
-    channel: T,                  // The CAN socket channel to transmit data
-    id: Id,                      // The identifier used for the CAN message
-    resp: &'a Arc<ResponseSlot>, // A reference to the response slot for handling responses
+    channel: T,                         // The CAN socket channel to transmit data
+    id: Id,                             // The identifier used for the CAN message
+    response_id: u32,                   // The CAN ID the ECU responds with
+    resp: &'a Arc<ResponseSlot>,        // A reference to the response slot for handling responses
+    fc_block_size: u8,                  // Block size advertised in our Flow Control frames
+    fc_separation_time: u8,             // Separation time advertised in our Flow Control frames
+    periodic_cancel: CancellationToken, // Token used to interrupt an in-progress periodic read
+    transfer_cancel: CancellationToken, // Token used to abort an in-progress multi-frame transfer
+    suppress_positive_response: bool,   // Set the suppressPosRspMsgIndicationBit on sub-functions
+    did_registry: DidRegistry,          // Known DID data lengths used to split responses
+    n_bs: Duration,                     // ISO-TP N_Bs: maximum wait for a Flow Control frame
+    n_cr: Duration,                     // ISO-TP N_Cr: maximum wait for a Consecutive Frame
+    busy_retries: u8,                   // Repeats on busyRepeatRequest (0 = disabled)
+    busy_retry_delay: Duration,         // Delay before repeating a busy request
+    latency: LatencyStats,              // Request/response latency measurements
+    tx_dl: usize,                       // ISO-TP TX_DL: 8 on CAN, up to 64 on CAN FD
+    bus_off_recovery: bool,             // Restart the controller after a bus-off
+    padding: PaddingPolicy,             // Padding of the transmitted frames
+    min_tx_gap: Duration,               // Minimum gap between two transmitted frames
+    last_tx: Option<Instant>,           // End of the last transmit, for the gap
+    rx_active: Option<Instant>,         // Last frame of the multi-frame response being received
 }
 
 #[allow(dead_code)]
@@ -62,21 +115,366 @@ impl<'a, T: CanSocketTx> UdsClient<'a, T> {
     /// Takes a CAN socket channel `channel`, a 32-bit identifier `id`, and a reference
     /// to a `ResponseSlot` wrapped in `Arc`. The `Id::Extended` is used to create a unique
     /// identifier for the CAN frame.
-    pub fn new(channel: T, id: u32, resp: &'a LazyLock<Arc<ResponseSlot>>) -> Self {
-        let id = Id::Extended(ExtendedId::new(id).unwrap());
-        Self { channel, id, resp }
+    ///
+    /// The slot doesn't need to be a `static`: give each client (e.g. one per CAN interface)
+    /// its own slot, fed by the RX task of the same interface.
+    ///
+    /// The ECU is expected to respond with the CAN ID `default_response_id(id)`: `id + 8`, or
+    /// the swapped addresses of a 29-bit normal fixed addressing ID. See `with_response_id`.
+    pub fn new(channel: T, id: u32, resp: &'a Arc<ResponseSlot>) -> Self {
+        Self::with_response_id(channel, id, None, resp)
+    }
+
+    /// Create a new UdsClient instance whose ECU responds with the CAN ID `response_id`,
+    /// `None` meaning `default_response_id(id)`.
+    ///
+    /// The response ID is set on the slot: the frames given to
+    /// `ResponseSlot::update_response_from` with another CAN ID are dropped. Use the same ID
+    /// for the RX socket filter, e.g. `UdsSocket::new(iface, client.response_id())`.
+    pub fn with_response_id(
+        channel: T,
+        id: u32,
+        response_id: Option<u32>,
+        resp: &'a Arc<ResponseSlot>,
+    ) -> Self {
+        Self::with_id_and_response_id(channel, ExtendedId::new(id).unwrap(), response_id, resp)
+    }
+
+    /// Same as `new`, with the CAN ID `id` standard (11 bits) or extended (29 bits): `new`
+    /// always transmits extended frames, e.g. use `StandardId::new(FUNCTIONAL_REQUEST_ID)` for
+    /// the OBD functional requests.
+    pub fn with_id(channel: T, id: impl Into<Id>, resp: &'a Arc<ResponseSlot>) -> Self {
+        Self::with_id_and_response_id(channel, id, None, resp)
+    }
+
+    /// Same as `with_response_id`, with the CAN ID `id` standard or extended, see `with_id`.
+    pub fn with_id_and_response_id(
+        channel: T,
+        id: impl Into<Id>,
+        response_id: Option<u32>,
+        resp: &'a Arc<ResponseSlot>,
+    ) -> Self {
+        let id = id.into();
+        let response_id = response_id.unwrap_or_else(|| default_response_id(raw_id(id)));
+        resp.set_response_id(Some(response_id));
+        Self {
+            channel,
+            id,
+            response_id,
+            resp,
+            fc_block_size: DEFAULT_FC_BLOCK_SIZE,
+            fc_separation_time: DEFAULT_FC_SEPARATION_TIME,
+            periodic_cancel: CancellationToken::new(),
+            transfer_cancel: CancellationToken::new(),
+            suppress_positive_response: false,
+            did_registry: DidRegistry::new(),
+            n_bs: DEFAULT_N_BS,
+            n_cr: DEFAULT_N_CR,
+            busy_retries: 0,
+            busy_retry_delay: DEFAULT_BUSY_RETRY_DELAY,
+            latency: LatencyStats::default(),
+            tx_dl: DEFAULT_TX_DL,
+            bus_off_recovery: false,
+            padding: PaddingPolicy::None,
+            min_tx_gap: Duration::ZERO,
+            last_tx: None,
+            rx_active: None,
+        }
+    }
+
+    /// Create a new UdsClient instance configured from the `profile` of the ECU: request and
+    /// response IDs, padding, TX_DL, Flow Control parameters and timings.
+    ///
+    /// The response timeouts of the profile are set on the slot `resp`. A TX_DL or a separation
+    /// time that `set_tx_data_length` or `set_flow_control` rejects returns
+    /// `DiagError::ParameterInvalid`.
+    pub fn from_profile(
+        channel: T,
+        profile: &EcuProfile,
+        resp: &'a Arc<ResponseSlot>,
+    ) -> Result<Self, DiagError> {
+        let mut client =
+            Self::with_response_id(channel, profile.request_id, profile.response_id, resp);
+        client.set_tx_data_length(profile.tx_data_length)?;
+        client.set_padding(profile.padding);
+        client.set_flow_control(profile.fc_block_size, profile.fc_separation_time)?;
+        client.set_isotp_timeouts(profile.n_bs, profile.n_cr);
+        client.set_busy_retry(profile.busy_retries, profile.busy_retry_delay);
+        client.set_bus_off_recovery(profile.bus_off_recovery);
+        client.set_min_tx_gap(profile.min_tx_gap);
+        resp.set_timeout(profile.p2_timeout);
+        resp.set_pending_timeout(profile.p2_star_timeout);
+        Ok(client)
+    }
+
+    /// Returns the CAN ID the ECU responds with.
+    pub fn response_id(&self) -> u32 {
+        self.response_id
+    }
+
+    /// Returns the request/response latency statistics measured since the client was created
+    /// or since the last `reset_latency_stats`.
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency
+    }
+
+    /// Clear the latency statistics, e.g. before measuring a specific DID.
+    pub fn reset_latency_stats(&mut self) {
+        self.latency = LatencyStats::default();
+    }
+
+    /// Automatically repeat requests answered with busyRepeatRequest (NRC 0x21).
+    ///
+    /// The request is sent again after `delay`, up to `max_retries` times; the NRC is returned to
+    /// the caller once the retries are exhausted. `max_retries = 0` (the default) disables it.
+    pub fn set_busy_retry(&mut self, max_retries: u8, delay: Duration) {
+        self.busy_retries = max_retries;
+        self.busy_retry_delay = delay;
+    }
+
+    /// Returns the busyRepeatRequest retry configuration as `(max_retries, delay)`.
+    pub fn busy_retry(&self) -> (u8, Duration) {
+        (self.busy_retries, self.busy_retry_delay)
+    }
+
+    /// Configure the ISO 15765-2 timers used during multi-frame transfers (default: 1000ms each).
+    ///
+    /// - `n_bs`: maximum time to wait for a Flow Control frame after a First Frame or a block.
+    /// - `n_cr`: maximum time to wait for the next Consecutive Frame of a response.
+    ///
+    /// When a timer expires the transfer is aborted with `DiagError::IsoTpTimeout`.
+    pub fn set_isotp_timeouts(&mut self, n_bs: Duration, n_cr: Duration) {
+        self.n_bs = n_bs;
+        self.n_cr = n_cr;
+    }
+
+    /// Returns the configured ISO-TP timers as `(n_bs, n_cr)`.
+    pub fn isotp_timeouts(&self) -> (Duration, Duration) {
+        (self.n_bs, self.n_cr)
+    }
+
+    /// Set the ISO-TP TX_DL: the data length of the CAN frames we transmit.
+    ///
+    /// The default, 8, is classic CAN. On CAN FD (12, 16, 20, 24, 32, 48 or 64 bytes), requests
+    /// are packed to use the whole frame: Single Frames carry up to TX_DL - 2 bytes, First
+    /// Frames TX_DL - 2 bytes (TX_DL - 6 with the 32 bits size escape, used over 4095 bytes) and
+    /// Consecutive Frames TX_DL - 1 bytes. Shorter CAN FD frames are padded to the next valid DLC.
+    /// The channel's frame type must hold TX_DL bytes. Other values return
+    /// `DiagError::ParameterInvalid`.
+    pub fn set_tx_data_length(&mut self, tx_dl: usize) -> Result<(), DiagError> {
+        if tx_dl != DEFAULT_TX_DL && !CAN_FD_DATA_LENGTHS.contains(&tx_dl) {
+            return Err(DiagError::ParameterInvalid);
+        }
+        self.tx_dl = tx_dl;
+        Ok(())
+    }
+
+    /// Returns the configured ISO-TP TX_DL.
+    pub fn tx_data_length(&self) -> usize {
+        self.tx_dl
+    }
+
+    /// Enable or disable the automatic recovery from bus-off (disabled by default).
+    ///
+    /// A transmit failing because the CAN controller is bus-off returns `DiagError::BusOff`.
+    /// When the recovery is enabled, the channel is asked to restart the controller first (see
+    /// `CanSocketTx::restart`) and the frame is transmitted once more.
+    pub fn set_bus_off_recovery(&mut self, enabled: bool) {
+        self.bus_off_recovery = enabled;
+    }
+
+    /// Returns whether the automatic recovery from bus-off is enabled.
+    pub fn bus_off_recovery(&self) -> bool {
+        self.bus_off_recovery
+    }
+
+    /// Set the minimum gap between any two transmitted frames (none by default).
+    ///
+    /// Applied on top of the separation time of the ECU, to every frame: requests, Consecutive
+    /// Frames and our Flow Control frames. Some gateways and cheap USB-CAN adapters overrun on
+    /// back-to-back frames.
+    pub fn set_min_tx_gap(&mut self, gap: Duration) {
+        self.min_tx_gap = gap;
+    }
+
+    /// Returns the minimum gap between two transmitted frames.
+    pub fn min_tx_gap(&self) -> Duration {
+        self.min_tx_gap
+    }
+
+    /// Set the padding of the transmitted frames (`PaddingPolicy::None` by default).
+    ///
+    /// With `PaddingPolicy::StrictPad`, the response slot also rejects the received frames that
+    /// aren't padded (see `ResponseSlot::set_strict_length`): the request returns
+    /// `FrameError::InvalidCanLength`.
+    pub fn set_padding(&mut self, padding: PaddingPolicy) {
+        self.padding = padding;
+        self.resp
+            .set_strict_length(matches!(padding, PaddingPolicy::StrictPad(_)));
+    }
+
+    /// Returns the padding of the transmitted frames.
+    pub fn padding(&self) -> PaddingPolicy {
+        self.padding
+    }
+
+    /// Returns the DID length registry used to split data identifier responses.
+    pub fn did_registry(&self) -> &DidRegistry {
+        &self.did_registry
+    }
+
+    /// Returns the DID length registry, to register the lengths of custom DIDs.
+    pub fn did_registry_mut(&mut self) -> &mut DidRegistry {
+        &mut self.did_registry
+    }
+
+    /// Enable or disable the suppressPosRspMsgIndicationBit (0x80) for services with a sub-function.
+    ///
+    /// When enabled, the ECU does not send a positive response, so the client sends the request
+    /// without waiting for a reply instead of running into the response timeout.
+    pub fn set_suppress_positive_response(&mut self, suppress: bool) {
+        self.suppress_positive_response = suppress;
+    }
+
+    /// Returns whether positive responses are currently suppressed.
+    pub fn suppress_positive_response(&self) -> bool {
+        self.suppress_positive_response
+    }
+
+    /// Returns a token that interrupts an in-progress periodic (real-time) data read.
+    ///
+    /// Clone the token before starting the read and call `cancel()` from another task (e.g. a UI
+    /// "stop" button): the read sends the stop request of `stop_all_periodic` and returns. The
+    /// token is single-use: once a read has been cancelled, fetch a new token for the next read.
+    pub fn periodic_stop_token(&self) -> CancellationToken {
+        self.periodic_cancel.clone()
+    }
+
+    /// Returns a token that aborts the in-progress multi-frame transfer.
+    ///
+    /// Clone the token before starting the transfer (e.g. `transfer_file`) and call `cancel()`
+    /// from another task: no further Consecutive Frame or TransferData block is sent and the
+    /// transfer returns `DiagError::TransferAborted`. Then call `abort_current_transfer`.
+    pub fn transfer_stop_token(&self) -> CancellationToken {
+        self.transfer_cancel.clone()
+    }
+
+    /// Abort the current transfer and reset the client so the next request starts fresh.
+    ///
+    /// The token from `transfer_stop_token` is cancelled (a fresh one replaces it), the frames
+    /// left from the interrupted exchange are discarded from the response slot and the request
+    /// context is cleared. With `send_exit`, RequestTransferExit (0x37) is then sent to let the
+    /// ECU leave the download/upload state, its error being returned (e.g. NRC 0x24
+    /// requestSequenceError when no transfer was active).
+    pub async fn abort_current_transfer(&mut self, send_exit: bool) -> Result<(), DiagError> {
+        log_debug!("UDS: abort of the current transfer");
+        self.transfer_cancel.cancel();
+        self.transfer_cancel = CancellationToken::new();
+        self.flush_responses().await;
+        self.clear_request();
+        if send_exit {
+            self.request_transfer_exit().await?;
+        }
+        Ok(())
+    }
+
+    /// Internal function: returns `DiagError::TransferAborted` once the token from
+    /// `transfer_stop_token` is cancelled.
+    pub(crate) fn check_transfer_aborted(&self) -> Result<(), DiagError> {
+        if self.transfer_cancel.is_cancelled() {
+            return Err(DiagError::TransferAborted);
+        }
+        Ok(())
+    }
+
+    /// Internal function: discard any response waiting in the response slot.
+    pub(crate) async fn flush_responses(&self) {
+        self.resp.flush().await;
+    }
+
+    /// Internal function: set the response timeout and the timeout following a responsePending
+    /// of the response slot.
+    pub(crate) fn set_response_timeouts(&self, timeout: Duration, pending_timeout: Duration) {
+        self.resp.set_timeout(timeout);
+        self.resp.set_pending_timeout(Some(pending_timeout));
+    }
+
+    /// Internal function: the raw NRC of the latest response returned as
+    /// `DiagError::NotSupported`, see `ResponseSlot::not_supported_nrc`.
+    pub(crate) fn not_supported_nrc(&self) -> u8 {
+        self.resp.not_supported_nrc()
+    }
+
+    /// Internal function: stop dropping the frames that don't answer the last request.
+    pub(crate) fn clear_request(&self) {
+        self.resp.set_request(None);
+    }
+
+    /// Internal function: replace a cancelled periodic token with a fresh one.
+    pub(crate) fn reset_periodic_token(&mut self) {
+        if self.periodic_cancel.is_cancelled() {
+            self.periodic_cancel = CancellationToken::new();
+        }
+    }
+
+    /// Configure the Flow Control parameters we advertise when receiving a multi-frame response.
+    ///
+    /// `block_size` is the number of Consecutive Frames the ECU may send before waiting for
+    /// the next Flow Control frame (0 = send all without waiting). `separation_time` uses the
+    /// ISO 15765-2 STmin encoding: 0x00-0x7F are milliseconds and 0xF1-0xF9 are 100-900us.
+    /// Reserved STmin values (0x80-0xF0, 0xFA-0xFF) are rejected with `DiagError::ParameterInvalid`.
+    pub fn set_flow_control(
+        &mut self,
+        block_size: u8,
+        separation_time: u8,
+    ) -> Result<(), DiagError> {
+        if !matches!(separation_time, 0x00..=0x7F | 0xF1..=0xF9) {
+            return Err(DiagError::ParameterInvalid);
+        }
+        self.fc_block_size = block_size;
+        self.fc_separation_time = separation_time;
+        Ok(())
+    }
+
+    /// Returns the configured Flow Control `(block_size, separation_time)`.
+    pub fn flow_control(&self) -> (u8, u8) {
+        (self.fc_block_size, self.fc_separation_time)
+    }
+
+    /// Build the Flow Control (Continue To Send) frame advertised to the ECU.
+    pub(crate) fn flow_control_frame(&self) -> UdsFrame {
+        UdsFrame::FlowControl(
+            UdsFlowControlFrame::new(
+                FlowStatus::ContinueToSend,
+                self.fc_block_size,
+                self.fc_separation_time,
+                Vec::new(),
+            )
+            .unwrap(),
+        )
     }
 
     /// Send a command without expecting a response.
     ///
     /// This function sends a command using ISO 15765-2 format, which includes PCI, CMD,
     /// and ARGS. The `args` are added to the frame and sent using the `send_raw` method.
+    /// If CMD and ARGS don't fit in a classic CAN single frame, `pci` is ignored: the command is
+    /// sent in a CAN FD single frame (see `set_tx_data_length`) or segmented with
+    /// `send_multi_frame`.
     pub async fn send_command<P: Into<u8>, M: Into<u8>>(
         &mut self,
         pci: P,
         cmd: M,
         args: &[u8],
     ) -> Result<(), DiagError> {
+        if args.len() > 6 {
+            let mut payload = vec![cmd.into()];
+            payload.extend_from_slice(args);
+            return match self.single_frame(&payload) {
+                Some(data) => self.send_raw(&data).await,
+                None => self.send_multi_frame(&payload).await,
+            };
+        }
         let mut data = vec![pci.into(), cmd.into()];
         data.extend_from_slice(args);
         self.send_raw(&data).await
@@ -95,14 +493,16 @@ impl<'a, T: CanSocketTx> UdsClient<'a, T> {
     /// This function sends an `UdsFrame` to the CAN bus and waits for a response. If the
     /// response is valid (`Response::Ok`), it returns the response frame, otherwise returns
     /// the error contained in `Response::Error`.
+    /// A multi-frame response is completed with `complete_response`: the returned First Frame
+    /// carries the whole payload.
     pub async fn send_frame_with_response(
         &mut self,
         frame: UdsFrame,
     ) -> Result<UdsFrame, DiagError> {
         match self.send_raw_with_response(&frame.to_vec()?).await? {
-            Response::Ok(items) => {
-                debug!("got response: {:?}", items);
-                Ok(items)
+            Response::Ok(items, _) => {
+                log_debug!("got response: {}", items.hex_dump());
+                self.complete_response(items).await
             }
             Response::Error(e) => Err(e),
         }
@@ -112,45 +512,539 @@ impl<'a, T: CanSocketTx> UdsClient<'a, T> {
     ///
     /// This function is similar to `send_command` but expects a response after sending
     /// the command. It returns the response frame (`UdsFrame`) if successful, or the
-    /// error if something went wrong.
+    /// error if something went wrong. Like `send_command`, a command that doesn't fit in a
+    /// single frame is segmented. As for `send_frame_with_response`, a multi-frame response
+    /// is completed.
     pub async fn send_command_with_response<P: Into<u8>, M: Into<u8>>(
         &mut self,
         pci: P,
         cmd: M,
         args: &[u8],
     ) -> Result<UdsFrame, DiagError> {
-        let mut data = vec![pci.into(), cmd.into()];
+        let response = if args.len() > 6 {
+            // Sent in a CAN FD single frame or segmented
+            let mut payload = vec![cmd.into()];
+            payload.extend_from_slice(args);
+            self.send_payload_with_response(&payload).await?
+        } else {
+            let mut data = vec![pci.into(), cmd.into()];
+            data.extend_from_slice(args);
+            self.send_raw_with_response(&data).await?
+        };
+        match response {
+            Response::Ok(items, _) => {
+                log_debug!("got response: {}", items.hex_dump());
+                self.complete_response(items).await
+            }
+            Response::Error(e) => Err(e),
+        }
+    }
+
+    /// Send a command with a sub-function, honouring the suppressPosRspMsgIndicationBit.
+    ///
+    /// When positive responses are suppressed (see `set_suppress_positive_response`), bit 0x80 is
+    /// set on the `sub_function` and the request is sent without waiting: `Ok(None)` is returned.
+    /// Otherwise the command is sent with `send_command_with_response` and the response is returned.
+    pub async fn send_sub_function_command<M: Into<u8>>(
+        &mut self,
+        cmd: M,
+        sub_function: u8,
+        args: &[u8],
+    ) -> Result<Option<UdsFrame>, DiagError> {
+        let pci_byte = PciByte::new(PciType::SingleFrame, (args.len() + 2) as u8);
+        let mut data = vec![sub_function];
         data.extend_from_slice(args);
-        match self.send_raw_with_response(&data).await? {
-            Response::Ok(items) => {
-                debug!("got response: {:?}", items);
-                Ok(items)
+        if self.suppress_positive_response {
+            data[0] |= 0x80;
+            self.send_command(pci_byte, cmd, &data).await?;
+            Ok(None)
+        } else {
+            self.send_command_with_response(pci_byte, cmd, &data)
+                .await
+                .map(Some)
+        }
+    }
+
+    /// Send a payload (SID + parameters) that doesn't fit in a single frame.
+    ///
+    /// The payload is segmented by `IsoTpSegmenter` (ISO 15765-2): a First Frame is sent, then
+    /// the ECU's Flow Control frame decides how the Consecutive Frames are sent:
+    /// - Continue To Send: the Consecutive Frames are sent, separated by the requested STmin.
+    ///   With a non-zero block size, the next Flow Control frame is awaited after each block.
+    /// - Wait: the next Flow Control frame is awaited.
+    /// - Overflow: the transfer is aborted with `DiagError::FlowControlOverflow`.
+    ///
+    /// The frames are packed according to the TX_DL (see `set_tx_data_length`); a payload over
+    /// 4095 bytes is announced with the 32 bits size escape of the First Frame.
+    ///
+    /// Payloads fitting in a single frame, or over 4 GiB, return `DiagError::ParameterInvalid`.
+    /// The transfer stops with `DiagError::TransferAborted` when the token from
+    /// `transfer_stop_token` is cancelled. While a multi-frame response is still being received,
+    /// `DiagError::Busy` is returned (see `check_rx_idle`).
+    pub async fn send_multi_frame(&mut self, payload: &[u8]) -> Result<(), DiagError> {
+        self.check_rx_idle().await?;
+        let mut frames = IsoTpSegmenter::new(payload, self.tx_dl)?;
+        if frames.is_single_frame() {
+            return Err(DiagError::ParameterInvalid);
+        }
+        if let Some(first_frame) = frames.next() {
+            self.send_frame(first_frame).await?;
+        }
+
+        let mut flow_control = self.wait_flow_control().await?;
+        let mut block_count = 0;
+        for (idx, consecutive_frame) in frames.enumerate() {
+            if flow_control.block_size != 0 && block_count == flow_control.block_size {
+                // The block is complete: the ECU sends the next Flow Control frame
+                flow_control = self.wait_flow_control().await?;
+                block_count = 0;
+            } else if idx > 0 {
+                tokio::time::sleep(flow_control.separation_time_duration()).await;
             }
+            block_count += 1;
+            self.check_transfer_aborted()?;
+            self.send_frame(consecutive_frame).await?;
+        }
+        Ok(())
+    }
+
+    /// Internal function: wait for the ECU's Flow Control frame allowing us to continue sending.
+    ///
+    /// Wait frames are skipped (up to `MAX_FC_WAIT`), an Overflow aborts the transfer.
+    async fn wait_flow_control(&mut self) -> Result<UdsFlowControlFrame, DiagError> {
+        for _ in 0..=MAX_FC_WAIT {
+            match self.resp.wait_for_response_timeout(self.n_bs).await {
+                Response::Ok(UdsFrame::FlowControl(frame), _) => match frame.flag {
+                    FlowStatus::ContinueToSend => return Ok(frame),
+                    FlowStatus::Wait => {
+                        log_debug!("UDS: ECU requested to wait for the next flow control")
+                    }
+                    FlowStatus::Overflow => return Err(DiagError::FlowControlOverflow),
+                },
+                Response::Ok(frame, _) => {
+                    return Err(DiagError::WrongPciType {
+                        want: PciType::FlowControl,
+                        received: frame.pci_type(),
+                    });
+                }
+                Response::Error(DiagError::Timeout) => return Err(DiagError::IsoTpTimeout("N_Bs")),
+                Response::Error(e) => return Err(e),
+            }
+        }
+        Err(DiagError::Timeout)
+    }
+
+    /// Send a request payload (SID + parameters, no PCI) and return the complete response
+    /// payload (SID + parameters).
+    ///
+    /// The request is sent as a Single Frame or segmented with `send_multi_frame`, and a
+    /// multi-frame response is reassembled with `receive_payload`. A negative response is
+    /// returned as `DiagError::ECUError`. This is the escape hatch for services without a
+    /// dedicated method; an empty payload returns `DiagError::ParameterInvalid`. While the
+    /// multi-frame response of an interrupted request is still arriving, `DiagError::Busy` is
+    /// returned.
+    pub async fn raw_request(&mut self, payload: &[u8]) -> Result<Vec<u8>, DiagError> {
+        if payload.is_empty() {
+            return Err(DiagError::ParameterInvalid);
+        }
+        match self.send_payload_with_response(payload).await? {
+            Response::Ok(frame, _) => self.receive_payload(frame).await,
+            Response::Error(e) => Err(e),
+        }
+    }
+
+    /// Send the request `P::COMMAND` with the parameters `params` and decode its response with
+    /// `parser`, see `UdsResponseParser`.
+    ///
+    /// The request is sent as with `raw_request` and a multi-frame response is reassembled.
+    /// A negative response is returned as `DiagError::ECUError`.
+    pub async fn request_parsed<P: UdsResponseParser>(
+        &mut self,
+        parser: &P,
+        params: &[u8],
+    ) -> Result<P::Output, DiagError> {
+        let mut request = vec![P::COMMAND as u8];
+        request.extend_from_slice(params);
+        match self.send_payload_with_response(&request).await? {
+            Response::Ok(frame, _) => parser.parse(&self.complete_response(frame).await?),
             Response::Error(e) => Err(e),
         }
     }
 
+    /// Internal function: send a request payload (SID + parameters), as a Single Frame or
+    /// segmented with `send_multi_frame`, and wait for the first frame of the response.
+    pub(crate) async fn send_payload_with_response(
+        &mut self,
+        payload: &[u8],
+    ) -> Result<Response, DiagError> {
+        if let Some(data) = self.single_frame(payload) {
+            return self.send_raw_with_response(&data).await;
+        }
+
+        let mut retries = 0;
+        loop {
+            let start = Instant::now();
+            self.send_multi_frame(payload).await?;
+            let response = self.wait_measured(start).await;
+            if !self.retry_busy(&response, &mut retries).await {
+                return Ok(response);
+            }
+        }
+    }
+
+    /// Internal function: receive the complete payload (SID + parameters) of a response
+    /// starting with `first`.
+    ///
+    /// A Single Frame is returned as is. For a First Frame, Flow Control frames are sent as
+    /// configured with `set_flow_control` and the Consecutive Frames are collected until the
+    /// size announced by the First Frame is reached. A payload not matching the announced size
+    /// returns `DiagError::ResponseLengthMismatch`: a First Frame carrying the whole size (or
+    /// more), a Consecutive Frame running past it with more than padding, or a Consecutive Frame
+    /// shorter than the First Frame before the end of the transfer.
+    pub(crate) async fn receive_payload(&mut self, first: UdsFrame) -> Result<Vec<u8>, DiagError> {
+        let mut payload = Vec::new();
+        self.receive_payload_chunks(first, |chunk| {
+            payload.extend_from_slice(chunk);
+            Ok(())
+        })
+        .await?;
+        Ok(payload)
+    }
+
+    /// Internal function: same as `receive_payload`, giving the payload to `on_chunk` as it
+    /// arrives: the content of the first frame, then the data of each Consecutive Frame (without
+    /// the padding of the last one). An error returned by `on_chunk` stops the reception.
+    pub(crate) async fn receive_payload_chunks(
+        &mut self,
+        first: UdsFrame,
+        mut on_chunk: impl FnMut(&[u8]) -> Result<(), DiagError>,
+    ) -> Result<(), DiagError> {
+        let first = match first {
+            UdsFrame::Single(frame) => {
+                let mut payload = vec![frame.sid];
+                payload.extend_from_slice(&frame.params());
+                return on_chunk(&payload);
+            }
+            UdsFrame::First(frame) => frame,
+            frame => {
+                return Err(DiagError::WrongPciType {
+                    want: PciType::FirstFrame,
+                    received: frame.pci_type(),
+                });
+            }
+        };
+
+        let size = first.size as usize;
+        let mut payload = vec![first.sid];
+        payload.extend_from_slice(&first.params());
+        if payload.len() >= size {
+            log_warn!(
+                "UDS: First Frame announces {} bytes but carries {}",
+                size,
+                payload.len()
+            );
+            return Err(DiagError::ResponseLengthMismatch {
+                expected: size as u32,
+                received: payload.len() as u32,
+            });
+        }
+        // The Consecutive Frames before the last one are as long as the First Frame
+        let pci_len = if size > 0xFFF { 6 } else { 2 };
+        let consecutive_len = payload.len() + pci_len - 1;
+        self.resp.start_reassembly().await;
+        let result = match self.send_frame(self.flow_control_frame()).await {
+            Ok(()) => {
+                self.rx_active = Some(Instant::now());
+                self.receive_consecutive_frames(size, consecutive_len, payload, &mut on_chunk)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        self.resp.end_reassembly();
+        self.rx_active = None;
+        result
+    }
+
+    /// Internal function: collect the Consecutive Frames of a response of `size` bytes whose
+    /// First Frame carried `payload`, see `receive_payload_chunks`. The Consecutive Frames
+    /// before the last one carry `consecutive_len` bytes.
+    async fn receive_consecutive_frames(
+        &mut self,
+        size: usize,
+        consecutive_len: usize,
+        payload: Vec<u8>,
+        on_chunk: &mut impl FnMut(&[u8]) -> Result<(), DiagError>,
+    ) -> Result<(), DiagError> {
+        on_chunk(&payload)?;
+
+        let (block_size, _) = self.flow_control();
+        let mut seq_num = 0;
+        let mut block_count = 0;
+        let mut received = payload.len();
+        while received < size {
+            let frame = match self.resp.wait_consecutive(self.n_cr).await {
+                Response::Ok(UdsFrame::Consecutive(frame), _) => frame,
+                Response::Ok(frame, _) => {
+                    return Err(DiagError::WrongPciType {
+                        want: PciType::ConsecutiveFrame,
+                        received: frame.pci_type(),
+                    });
+                }
+                Response::Error(DiagError::Timeout) => return Err(DiagError::IsoTpTimeout("N_Cr")),
+                Response::Error(e) => return Err(e),
+            };
+            self.rx_active = Some(Instant::now());
+            frame.check_sequence(seq_num)?;
+            seq_num = frame.seq_num;
+            let remaining = size - received;
+            let len = frame.payload.len();
+            if len < remaining && len != consecutive_len
+                || len > remaining && !is_padded_last_frame(len, remaining, consecutive_len)
+            {
+                log_warn!(
+                    "UDS: Consecutive Frame of {} bytes with {} bytes remaining",
+                    len,
+                    remaining
+                );
+                return Err(DiagError::ResponseLengthMismatch {
+                    expected: size as u32,
+                    received: (received + len) as u32,
+                });
+            }
+            // The last Consecutive Frame may carry padding
+            let len = len.min(remaining);
+            received += len;
+            on_chunk(&frame.payload[..len])?;
+
+            // The ECU waits for a new Flow Control after each block
+            if block_size != 0 {
+                block_count += 1;
+                if block_count == block_size && received < size {
+                    self.send_frame(self.flow_control_frame()).await?;
+                    block_count = 0;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Internal function: complete a response starting with `frame`.
+    ///
+    /// For a First Frame, the Flow Control frames are sent and the Consecutive Frames collected
+    /// with `receive_payload`: the returned First Frame carries the whole payload after its SID.
+    /// Other frames are returned as is.
+    pub(crate) async fn complete_response(
+        &mut self,
+        frame: UdsFrame,
+    ) -> Result<UdsFrame, DiagError> {
+        let UdsFrame::First(first) = frame else {
+            return Ok(frame);
+        };
+        let (size, sid) = (first.size, first.sid);
+        let payload = self.receive_payload(UdsFrame::First(first)).await?;
+        Ok(UdsFrame::First(UdsFirstFrame {
+            size,
+            sid,
+            payload: payload.get(1..).unwrap_or_default().to_vec(),
+        }))
+    }
+
     /// Internal function: Send raw data to the CAN bus.
     ///
     /// This function sends the provided byte array `data` as a CAN frame using the `channel`.
     /// It creates a new `Frame` using the `id` and the data, and transmits it over the CAN bus.
     async fn send_raw(&mut self, data: &[u8]) -> Result<(), DiagError> {
-        let frame = T::Frame::new(self.id, data).unwrap();
-        println!("send raw data frame: {:?}", frame.data());
-        self.channel.transmit(&frame).await.unwrap();
+        let frame = self.can_frame(data)?;
+        log_debug!("send raw data frame: {}", hex_dump(frame.data()));
+        self.track_request(data);
+        self.transmit(&frame).await
+    }
+
+    /// Internal function: transmit `frame` on the channel, then the frames it displaced from
+    /// the TX buffer (see `CanSocketTx::transmit`).
+    ///
+    /// A channel displacing more than `MAX_DISPLACED_FRAMES` frames in a row is faulty:
+    /// `DiagError::ChannelError` is returned.
+    async fn transmit(&mut self, frame: &T::Frame) -> Result<(), DiagError> {
+        let mut displaced = self.transmit_once(frame).await?;
+        for _ in 0..MAX_DISPLACED_FRAMES {
+            let Some(pending) = displaced else {
+                return Ok(());
+            };
+            log_debug!(
+                "CAN: a pending frame was displaced, sending it again: {}",
+                hex_dump(pending.data())
+            );
+            displaced = self.transmit_once(&pending).await?;
+        }
+        if displaced.is_some() {
+            log_warn!("CAN: the channel keeps displacing the pending frames");
+            return Err(DiagError::ChannelError);
+        }
         Ok(())
     }
 
+    /// Internal function: transmit `frame` on the channel, restarting the CAN controller once
+    /// if it is bus-off and the recovery is enabled. Returns the frame displaced from the TX
+    /// buffer, if any.
+    async fn transmit_once(&mut self, frame: &T::Frame) -> Result<Option<T::Frame>, DiagError> {
+        if let Some(last_tx) = self.last_tx {
+            tokio::time::sleep_until((last_tx + self.min_tx_gap).into()).await;
+        }
+        let deadline = Instant::now() + TX_TIMEOUT;
+        let result = loop {
+            match self.channel.transmit(frame).await {
+                // The TX buffer is full: the bus is busy, or dead if it stays full
+                Err(nb::Error::WouldBlock) if Instant::now() < deadline => {
+                    tokio::time::sleep(TX_RETRY_DELAY).await;
+                }
+                Err(nb::Error::WouldBlock) => {
+                    log_warn!("CAN: TX buffer full for {:?}", TX_TIMEOUT);
+                    break Err(DiagError::TxTimeout);
+                }
+                Err(nb::Error::Other(e)) if T::is_bus_off(&e) && self.bus_off_recovery => {
+                    log_warn!("CAN: controller is bus-off, restarting it");
+                    break if self.channel.restart() {
+                        self.channel
+                            .transmit(frame)
+                            .await
+                            .map_err(transmit_error::<T>)
+                    } else {
+                        Err(DiagError::BusOff)
+                    };
+                }
+                result => break result.map_err(transmit_error::<T>),
+            }
+        };
+        self.last_tx = Some(Instant::now());
+        result
+    }
+
     /// Internal function: Send raw data to the CAN bus and wait for a response.
     ///
     /// This function sends the byte array `data` as a CAN frame and waits for a response using
     /// the `ResponseSlot`. It uses `wait_for_response` to receive the response, and returns the
     /// received `Response`.
     async fn send_raw_with_response(&mut self, data: &[u8]) -> Result<Response, DiagError> {
-        let frame = T::Frame::new(self.id, data).unwrap();
-        self.channel.transmit(&frame).await.unwrap();
-        let response = self.resp.wait_for_response().await;
-        Ok(response)
+        self.check_rx_idle().await?;
+        let frame = self.can_frame(data)?;
+        self.track_request(data);
+        let mut retries = 0;
+        loop {
+            let start = Instant::now();
+            self.transmit(&frame).await?;
+            let response = self.wait_measured(start).await;
+            if !self.retry_busy(&response, &mut retries).await {
+                return Ok(response);
+            }
+        }
+    }
+
+    /// Internal function: make sure no multi-frame response is being received before sending a
+    /// request.
+    ///
+    /// A reception only stays active when the future receiving it was dropped (e.g. by a
+    /// timeout) while the ECU was still sending its Consecutive Frames. `DiagError::Busy` is
+    /// returned until N_Cr elapsed since its last frame: the ECU then gave up, the reception is
+    /// abandoned and its leftover frames flushed.
+    async fn check_rx_idle(&mut self) -> Result<(), DiagError> {
+        let Some(last) = self.rx_active else {
+            return Ok(());
+        };
+        if last.elapsed() < self.n_cr {
+            return Err(DiagError::Busy);
+        }
+        log_warn!("UDS: abandoning an interrupted multi-frame response");
+        self.rx_active = None;
+        self.flush_responses().await;
+        Ok(())
+    }
+
+    /// Internal function: wait for the response to a request transmitted at `start` and record
+    /// its latency. Timeouts are not recorded.
+    async fn wait_measured(&mut self, start: Instant) -> Response {
+        let (response, pending) = self
+            .resp
+            .wait_for_response_counted(self.resp.timeout())
+            .await;
+        if !matches!(response, Response::Error(DiagError::Timeout)) {
+            self.latency.record(start.elapsed(), pending);
+        }
+        response
+    }
+
+    /// Internal function: build the Single Frame (PCI + `payload`) of a request payload (SID +
+    /// parameters), or `None` if it needs to be segmented.
+    ///
+    /// On CAN FD, payloads over 7 bytes use the escaped size: the PCI byte is 0 and the size
+    /// follows.
+    fn single_frame(&self, payload: &[u8]) -> Option<Vec<u8>> {
+        let mut data = match payload.len() {
+            len @ 0..=7 => vec![len as u8],
+            len if len <= self.tx_dl - 2 => vec![0x00, len as u8],
+            _ => return None,
+        };
+        data.extend_from_slice(payload);
+        Some(data)
+    }
+
+    /// Internal function: build the CAN frame carrying `data`.
+    ///
+    /// CAN FD frames are padded to the next valid data length, classic CAN frames to 8 bytes
+    /// according to the `PaddingPolicy`. Data the channel's frame type can't hold returns
+    /// `FrameError::InvalidCanLength`.
+    fn can_frame(&self, data: &[u8]) -> Result<T::Frame, DiagError> {
+        let len = match CAN_FD_DATA_LENGTHS.iter().find(|&&len| len >= data.len()) {
+            Some(&len) if data.len() > DEFAULT_TX_DL => Some(len),
+            _ => self.padding.value().map(|_| DEFAULT_TX_DL),
+        };
+        let frame = match len {
+            Some(len) => {
+                let mut padded = data.to_vec();
+                padded.resize(len, self.padding.value().unwrap_or(CAN_FD_PADDING));
+                T::Frame::new(self.id, &padded)
+            }
+            None => T::Frame::new(self.id, data),
+        };
+        frame.ok_or(DiagError::FrameError {
+            error: FrameError::InvalidCanLength,
+        })
+    }
+
+    /// Internal function: remember the SID of a request sent as `data`, so the response slot
+    /// drops frames that don't answer it. Consecutive and Flow Control frames keep the context.
+    fn track_request(&self, data: &[u8]) {
+        // The SID follows the PCI, which is longer when the size is escaped
+        let sid = match data {
+            [0x00, ..] => data.get(2),
+            [0x01..=0x0F, ..] => data.get(1),
+            [0x10, 0x00, ..] => data.get(6),
+            [0x10..=0x1F, ..] => data.get(2),
+            _ => return,
+        };
+        self.resp.set_request(sid.copied());
+    }
+
+    /// Internal function: decide whether a request answered with `response` must be sent again.
+    ///
+    /// Returns `true`, after waiting for the configured delay, when the ECU replied with
+    /// busyRepeatRequest (NRC 0x21) and `retries` didn't reach the configured maximum yet.
+    async fn retry_busy(&self, response: &Response, retries: &mut u8) -> bool {
+        let busy = matches!(
+            response,
+            Response::Error(DiagError::ECUError { code, .. }) if *code == UdsError::BusyRepeatRequest
+        );
+        if !busy || *retries >= self.busy_retries {
+            return false;
+        }
+        *retries += 1;
+        log_debug!(
+            "UDS: ECU is busy, repeating the request ({}/{})",
+            retries,
+            self.busy_retries
+        );
+        tokio::time::sleep(self.busy_retry_delay).await;
+        true
     }
 
     /// Receive a frame from the UDS server.
@@ -160,4 +1054,122 @@ impl<'a, T: CanSocketTx> UdsClient<'a, T> {
     pub async fn receive(&mut self) -> Response {
         self.resp.wait_for_response().await
     }
+
+    /// Receive the response to the service `expected_sid`, waiting at most `timeout`.
+    ///
+    /// Unlike `receive`, frames that don't answer `expected_sid` (unsolicited frames, e.g. with
+    /// ResponseOnEvent, stray Consecutive/Flow Control frames or undecodable frames) are discarded
+    /// and the wait goes on. It returns the positive response (SID + 0x40) or the negative
+    /// response for `expected_sid`; responsePending (NRC 0x78) keeps waiting. If the timeout
+    /// elapses, `DiagError::Timeout` is returned.
+    pub async fn receive_matching(&mut self, expected_sid: u8, timeout: Duration) -> Response {
+        // Let the slot drop the unrelated frames as well, then restore the request context.
+        let previous = self.resp.request();
+        self.resp.set_request(Some(expected_sid));
+        let deadline = Instant::now() + timeout;
+        let response = loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.resp.wait_timeout(remaining).await {
+                Some(response) if matches_sid(&response, expected_sid) => break response,
+                Some(response) => {
+                    log_debug!("UDS: discarded an unrelated response: {:?}", response)
+                }
+                None => break Response::Error(DiagError::Timeout),
+            }
+        };
+        self.resp.set_request(previous);
+        response
+    }
+}
+
+/// Check whether `response` is the final answer to the service `sid`: its positive response or
+/// a negative response other than responsePending (NRC 0x78). `DiagError::NotSupported` carries
+/// no SID: the slot only delivers it for the request context `sid`.
+fn matches_sid(response: &Response, sid: u8) -> bool {
+    match response {
+        Response::Ok(UdsFrame::Single(frame), _) => frame.sid == sid | 0x40,
+        Response::Ok(UdsFrame::First(frame), _) => frame.sid == sid | 0x40,
+        Response::Error(DiagError::ECUError { rsid, .. }) => {
+            u8::from(*rsid) == sid && !response.is_pending()
+        }
+        Response::Error(DiagError::NotSupported) => true,
+        _ => false,
+    }
+}
+
+/// Returns the raw value of the standard or extended CAN ID `id`.
+fn raw_id(id: Id) -> u32 {
+    match id {
+        Id::Standard(id) => id.as_raw() as u32,
+        Id::Extended(id) => id.as_raw(),
+    }
+}
+
+/// Check whether a last Consecutive Frame of `len` bytes, with `remaining` bytes of payload
+/// expected, only adds padding: up to the full length of the Consecutive Frames, or to the
+/// smallest CAN data length (8 or a CAN FD length) fitting the remaining bytes and the PCI.
+fn is_padded_last_frame(len: usize, remaining: usize, consecutive_len: usize) -> bool {
+    let padded_len = std::iter::once(DEFAULT_TX_DL)
+        .chain(CAN_FD_DATA_LENGTHS)
+        .find(|&padded| padded > remaining);
+    len == consecutive_len || padded_len == Some(len + 1)
+}
+
+/// Check that `frame` is the positive response (SID + 0x40) to `cmd` and return the parameters
+/// following the response SID.
+pub(crate) fn positive_response_params(
+    frame: &UdsFrame,
+    cmd: UdsCommand,
+) -> Result<Vec<u8>, DiagError> {
+    let UdsFrame::Single(frame) = frame else {
+        return Err(DiagError::WrongPciType {
+            want: PciType::SingleFrame,
+            received: frame.pci_type(),
+        });
+    };
+    check_positive_sid(frame.sid, cmd)?;
+    Ok(frame.params())
+}
+
+/// Check that the response `payload` (SID + parameters) is the positive response to `cmd` and
+/// return the parameters following the response SID.
+pub(crate) fn positive_response_data(payload: &[u8], cmd: UdsCommand) -> Result<&[u8], DiagError> {
+    let (sid, data) = payload
+        .split_first()
+        .ok_or(DiagError::InvalidResponseLength)?;
+    check_positive_sid(*sid, cmd)?;
+    Ok(data)
+}
+
+/// Check that `sid` is the positive response SID (request SID + 0x40) of `cmd`.
+pub(crate) fn check_positive_sid(sid: u8, cmd: UdsCommand) -> Result<(), DiagError> {
+    if sid != cmd as u8 | 0x40 {
+        let received = UdsCommand::from_repr(sid & !0x40).ok_or(DiagError::FrameError {
+            error: FrameError::InvalidSid,
+        })?;
+        return Err(DiagError::WrongMessage {
+            want: cmd,
+            received,
+        });
+    }
+    Ok(())
+}
+
+/// Map a CAN transmit error to a `DiagError`.
+///
+/// Transient conditions (a full transmit queue, an overrun) are reported as `ChannelError` and
+/// can be retried, a bus-off controller is `BusOff` and any other bus or controller failure is
+/// a `HardwareError`.
+fn transmit_error<T: CanSocketTx>(error: nb::Error<T::Error>) -> DiagError {
+    match error {
+        nb::Error::WouldBlock => DiagError::ChannelError,
+        nb::Error::Other(e) if T::is_bus_off(&e) => DiagError::BusOff,
+        nb::Error::Other(e) => match e.kind() {
+            embedded_can::ErrorKind::Overrun => DiagError::ChannelError,
+            kind => {
+                log_debug!("CAN transmit failed: {:?}", kind);
+                DiagError::HardwareError
+            }
+        },
+    }
 }
@@ -1,9 +1,13 @@
-use automotive_diag::uds::{UdsCommand, UdsError};
+use std::time::Duration;
 
-use super::{DiagError, PciType};
+use super::{DiagError, Nrc, PciByte, PciType};
+
+/// Maximum data length of a CAN FD frame, the largest ISO-TP TX_DL.
+pub(crate) const CAN_FD_MAX_DL: usize = 64;
 
 /// Represents errors that can occur while processing UDS frames.
 #[derive(Debug, Clone, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FrameError {
     /// The frame type is not recognized.
     #[error("Invalid UDS frame type.")]
@@ -20,36 +24,120 @@ pub enum FrameError {
     /// The CAN message length is invalid.
     #[error("Invalid CAN message length.")]
     InvalidCanLength,
+    /// The Flow Status of a Flow Control frame is reserved.
+    #[error("Invalid Flow Control flow status.")]
+    InvalidFlowStatus,
+    /// The sequence number of a Consecutive Frame doesn't fit in 4 bits.
+    #[error("Invalid Consecutive Frame sequence number.")]
+    InvalidSequenceNumber,
     /// Other unspecified errors.
     #[error("An unknown error occurred.")]
     Others,
 }
 
+/// Render `data` as space-separated uppercase hex bytes.
+pub(crate) fn hex_dump(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Padding of the CAN frames transmitted by `UdsClient`, see `UdsClient::set_padding`.
+///
+/// Some ECUs require classic CAN frames to be exactly 8 bytes long, the unused bytes holding a
+/// specific value, and reject other frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PaddingPolicy {
+    /// Frames are sent with their data length only (CAN FD frames are still padded to a DLC)
+    #[default]
+    None,
+    /// Classic CAN frames are padded to 8 bytes with the given value
+    Pad(u8),
+    /// Same as `Pad`, and received frames must be padded too: a frame shorter than 8 bytes, or
+    /// without a valid CAN FD data length, is rejected
+    StrictPad(u8),
+}
+
+impl PaddingPolicy {
+    /// Returns the padding value, `None` if frames aren't padded.
+    pub fn value(&self) -> Option<u8> {
+        match self {
+            PaddingPolicy::None => None,
+            PaddingPolicy::Pad(value) | PaddingPolicy::StrictPad(value) => Some(*value),
+        }
+    }
+}
+
 /// UDS frame types:
 ///     - Single Frame (SF)
 ///     - First Frame (FF)
 ///     - Consecutive Frame (CF)
 ///     - Flow Control Frame (FC)
 ///     - Negative Response Frame (NRC)
+///
+/// `UdsFrame::from_vec(frame.to_vec()?)` always yields a frame equal to `frame`.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum UdsFrame {
     Single(UdsSingleFrame),
     First(UdsFirstFrame),
     Consecutive(UdsConsecutiveFrame),
     FlowControl(UdsFlowControlFrame),
+    Negative(UdsNegativeResponse),
 }
 
 impl Default for UdsFrame {
     fn default() -> Self {
         UdsFrame::Single(UdsSingleFrame {
-            size: 0,
+            size: 1,
             sid: 0,
-            did: None,
             payload: Vec::new(),
         })
     }
 }
 
+/// A one-line summary of the decoded fields, e.g. `SF sid=0x62 len=19` or
+/// `FC flag=CTS bs=0 st=10ms`. See `hex_dump` for the raw bytes.
+impl std::fmt::Display for UdsFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UdsFrame::Single(frame) => {
+                write!(f, "SF sid=0x{:02X} len={}", frame.sid, frame.payload.len())
+            }
+            UdsFrame::First(frame) => {
+                write!(f, "FF sid=0x{:02X} size={}", frame.sid, frame.size)
+            }
+            UdsFrame::Consecutive(frame) => {
+                write!(f, "CF sn={} len={}", frame.seq_num, frame.payload.len())
+            }
+            UdsFrame::FlowControl(frame) => {
+                let flag = match frame.flag {
+                    FlowStatus::ContinueToSend => "CTS",
+                    FlowStatus::Wait => "WT",
+                    FlowStatus::Overflow => "OVFLW",
+                };
+                write!(
+                    f,
+                    "FC flag={} bs={} st={:?}",
+                    flag,
+                    frame.block_size,
+                    frame.separation_time_duration()
+                )
+            }
+            UdsFrame::Negative(frame) => {
+                write!(f, "NR sid=0x{:02X} nrc=0x{:02X}", frame.rsid, frame.nrc)?;
+                match frame.code().standard() {
+                    Some(code) => write!(f, " ({code:?})"),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
 impl UdsFrame {
     /// return PCI type of UDS frame
     pub fn pci_type(&self) -> PciType {
@@ -58,6 +146,7 @@ impl UdsFrame {
             UdsFrame::First(_) => PciType::FirstFrame,
             UdsFrame::Consecutive(_) => PciType::ConsecutiveFrame,
             UdsFrame::FlowControl(_) => PciType::FlowControl,
+            UdsFrame::Negative(_) => PciType::SingleFrame,
         }
     }
 
@@ -81,231 +170,333 @@ impl UdsFrame {
         matches!(self, UdsFrame::FlowControl(_frame))
     }
 
+    /// verify if the frame is a negative response frame.
+    pub fn is_negative_response(&self) -> bool {
+        matches!(self, UdsFrame::Negative(_frame))
+    }
+
+    /// Render the encoded frame as space-separated uppercase hex bytes, as candump does,
+    /// e.g. `03 22 F1 90`. A frame that can't be encoded renders as its error.
+    pub fn hex_dump(&self) -> String {
+        match self.to_vec() {
+            Ok(data) => hex_dump(&data),
+            Err(e) => format!("<{e}>"),
+        }
+    }
+
+    /// Check that the frame can be encoded, see the `validate` of each frame type. Called by
+    /// `to_vec`, so a malformed frame never reaches the bus.
+    pub fn validate(&self) -> Result<(), FrameError> {
+        match self {
+            UdsFrame::Single(frame) => frame.validate(),
+            UdsFrame::First(frame) => frame.validate(),
+            UdsFrame::Consecutive(frame) => frame.validate(),
+            UdsFrame::FlowControl(frame) => frame.validate(),
+            UdsFrame::Negative(frame) => frame.validate(),
+        }
+    }
+
     pub fn to_vec(&self) -> Result<Vec<u8>, DiagError> {
         match self {
             UdsFrame::Single(uds_single_frame) => uds_single_frame.to_vec(),
             UdsFrame::First(uds_first_frame) => uds_first_frame.to_vec(),
             UdsFrame::Consecutive(uds_consecutive_frame) => uds_consecutive_frame.to_vec(),
             UdsFrame::FlowControl(uds_flow_control_frame) => uds_flow_control_frame.to_vec(),
+            UdsFrame::Negative(uds_negative_response) => uds_negative_response.to_vec(),
         }
     }
 
+    /// Decode a CAN frame, dispatching on its PCI nibble to the `TryFrom<&[u8]>` of the
+    /// concrete frame type. A Single or First Frame carrying a 0x7F SID is a negative response.
     pub fn from_vec(data: Vec<u8>) -> Result<Self, DiagError> {
-        let frame_type = data.first().map(|b| b >> 4).ok_or(DiagError::FrameError {
-            error: FrameError::InvalidCanLength,
-        })?;
-
-        match frame_type {
-            0x0 => {
-                // Single Frame
-                let size = data[0] & 0x0F;
-                let sid = *data.get(1).ok_or(DiagError::FrameError {
-                    error: FrameError::InvalidSize,
-                })?;
-
-                if sid == 0x7F {
-                    let rsid =
-                        UdsCommand::from_repr(*data.get(2).ok_or(DiagError::FrameError {
-                            error: FrameError::InvalidSid,
-                        })?)
-                        .ok_or(DiagError::FrameError {
-                            error: FrameError::InvalidSid,
-                        })?;
-                    let nrc = UdsError::from_repr(*data.get(3).ok_or(DiagError::FrameError {
-                        error: FrameError::InvalidNrc,
-                    })?)
-                    .ok_or(DiagError::FrameError {
-                        error: FrameError::InvalidNrc,
-                    })?;
-                    return Err(DiagError::ECUError {
-                        code: nrc,
-                        rsid,
-                        def: None,
-                    });
-                }
-
-                let did = if data.len() > 2 {
-                    Some(u16::from_be_bytes([data[2], *data.get(3).unwrap_or(&0)]))
-                } else {
-                    None
-                };
+        let data = data.as_slice();
+        Ok(match frame_type(data, &ALL_PCI_TYPES)? {
+            PciType::SingleFrame | PciType::FirstFrame if message_data(data)?.1[0] == 0x7F => {
+                UdsFrame::Negative(UdsNegativeResponse::try_from(data)?)
+            }
+            PciType::SingleFrame => UdsFrame::Single(UdsSingleFrame::try_from(data)?),
+            PciType::FirstFrame => UdsFrame::First(UdsFirstFrame::try_from(data)?),
+            PciType::ConsecutiveFrame => {
+                UdsFrame::Consecutive(UdsConsecutiveFrame::try_from(data)?)
+            }
+            PciType::FlowControl => UdsFrame::FlowControl(UdsFlowControlFrame::try_from(data)?),
+        })
+    }
+}
 
-                let payload_start = if did.is_some() { 4 } else { 2 };
-                let payload = data.get(payload_start..).unwrap_or(&[]).to_vec();
+/// The PCI types `UdsFrame::from_vec` decodes.
+const ALL_PCI_TYPES: [PciType; 4] = [
+    PciType::SingleFrame,
+    PciType::FirstFrame,
+    PciType::ConsecutiveFrame,
+    PciType::FlowControl,
+];
+
+/// Returns the frame type (decoded from the PCI nibble) of the CAN frame `data`, one of
+/// `expected`.
+fn frame_type(data: &[u8], expected: &[PciType]) -> Result<PciType, DiagError> {
+    let pci = data.first().ok_or(DiagError::FrameError {
+        error: FrameError::InvalidCanLength,
+    })?;
+    let frame_type = PciByte::from_byte(*pci)?.pci_type;
+    if !expected.contains(&frame_type) {
+        return Err(DiagError::FrameError {
+            error: FrameError::InvalidFrameType,
+        });
+    }
+    Ok(frame_type)
+}
 
-                Ok(UdsFrame::Single(UdsSingleFrame {
-                    size,
-                    sid,
-                    did,
-                    payload,
-                }))
-            }
-            0x1 => {
-                // First Frame
-                let size = (((data[0] & 0x0F) as u16) << 8)
-                    | (*data.get(1).ok_or(DiagError::FrameError {
-                        error: FrameError::InvalidSize,
-                    })? as u16);
-                let sid = *data.get(2).ok_or(DiagError::FrameError {
+/// Returns the message size and the data (starting with the SID) of a Single or First Frame.
+fn message_data(data: &[u8]) -> Result<(u32, &[u8]), DiagError> {
+    let (size, frame_data) = if frame_type(data, &[PciType::SingleFrame, PciType::FirstFrame])?
+        == PciType::SingleFrame
+    {
+        // Single Frame: only the `size` bytes after the PCI byte are data, the rest is padding.
+        // A size of 0 is the escape used by CAN FD and some gateways: the size follows.
+        let (size, data_start) = match data[0] & 0x0F {
+            0 => (*data.get(1).unwrap_or(&0), 2),
+            size => (size, 1),
+        };
+        let frame_data =
+            data.get(data_start..data_start + size as usize)
+                .ok_or(DiagError::FrameError {
                     error: FrameError::InvalidSize,
                 })?;
-
-                let did = data
-                    .get(3..5)
-                    .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]));
-                let payload_start = if did.is_some() { 5 } else { 3 };
-                let payload = data.get(payload_start..).unwrap_or(&[]).to_vec();
-
-                Ok(UdsFrame::First(UdsFirstFrame {
-                    size,
-                    sid,
-                    did,
-                    payload,
-                }))
-            }
-            0x2 => {
-                // Consecutive Frame
-                let seq_num = data[0] & 0x0F;
-                let payload = data.get(1..).unwrap_or(&[]).to_vec();
-                Ok(UdsFrame::Consecutive(UdsConsecutiveFrame {
-                    seq_num,
-                    payload,
-                }))
-            }
-            0x3 => {
-                // Flow Control Frame
-                let (flag, block_size, separation_time) = (
-                    data[0] & 0x0F,
-                    *data.get(1).ok_or(DiagError::FrameError {
-                        error: FrameError::InvalidSize,
-                    })?,
-                    *data.get(2).ok_or(DiagError::FrameError {
-                        error: FrameError::InvalidSize,
-                    })?,
-                );
-                let padding = data.get(3..).unwrap_or(&[]).to_vec();
-                Ok(UdsFrame::FlowControl(UdsFlowControlFrame {
-                    flag,
-                    block_size,
-                    separation_time,
-                    padding,
-                }))
+        (size as u32, frame_data)
+    } else {
+        // First Frame: a 12 bits size of 0 is the escape, the 32 bits size follows.
+        let size = (((data[0] & 0x0F) as u32) << 8)
+            | (*data.get(1).ok_or(DiagError::FrameError {
+                error: FrameError::InvalidSize,
+            })? as u32);
+        let (size, data_start) = match size {
+            0 => {
+                let bytes = data.get(2..6).ok_or(DiagError::FrameError {
+                    error: FrameError::InvalidSize,
+                })?;
+                let size = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                (size, 6)
             }
-            _ => Err(DiagError::FrameError {
-                error: FrameError::InvalidFrameType,
-            }),
-        }
+            size => (size, 2),
+        };
+        (size, data.get(data_start..).unwrap_or(&[]))
+    };
+    if frame_data.is_empty() {
+        return Err(DiagError::FrameError {
+            error: FrameError::InvalidSize,
+        });
+    }
+    Ok((size, frame_data))
+}
+
+/// Split the data of a positive Single or First Frame into its SID and parameters.
+/// A negative response (0x7F SID) returns `FrameError::InvalidSid`.
+fn positive_message(frame_data: &[u8]) -> Result<(u8, Vec<u8>), DiagError> {
+    let sid = frame_data[0];
+    if sid == 0x7F {
+        return Err(DiagError::FrameError {
+            error: FrameError::InvalidSid,
+        });
     }
+    Ok((sid, frame_data[1..].to_vec()))
 }
 
 /// Represents a UDS Single Frame.
 /// This frame is used when the total payload fits within a single CAN frame.
+///
+/// The parameters following the SID are opaque: whether they start with a DID depends on the
+/// service, the DID services (0x22, 0x2E, 0x2A) split it from the parameters.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct UdsSingleFrame {
-    /// Size of the payload (only 4 bits are used, max value is 7).
+    /// Size of the SID and payload (max 7 on CAN, 62 on CAN FD with the escaped size).
     pub size: u8,
     /// Service Identifier (SID) for the request or response.
     pub sid: u8,
-    /// Optional Diagnostic Identifier (DID), used in certain services.
-    pub did: Option<u16>,
-    /// The actual payload data for the request or response.
+    /// The service parameters of the request or response.
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_hex"))]
     pub payload: Vec<u8>,
 }
 
 /// Represents a UDS First Frame.
 /// This frame is sent when the payload is too large for a single frame.
 /// It contains the total size of the payload and the initial data.
+///
+/// As for single frames, the parameters following the SID are opaque.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct UdsFirstFrame {
-    /// Total size of the payload (only 12 bits are used).
-    pub size: u16,
+    /// Total size of the payload (12 bits, escaped to 32 bits over 4095 bytes).
+    pub size: u32,
     /// Service Identifier (SID) for the request or response.
     pub sid: u8,
-    /// Optional Diagnostic Identifier (DID), used in certain services.
-    pub did: Option<u16>,
-    /// The first portion of the payload.
+    /// The first portion of the service parameters.
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_hex"))]
     pub payload: Vec<u8>,
 }
 
 /// Represents a UDS Consecutive Frame.
 /// This frame is used for multi-frame transmissions after the First Frame.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct UdsConsecutiveFrame {
     /// Sequence number (4 bits, values range from 0 to 15).
     pub seq_num: u8,
     /// The next portion of the payload.
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_hex"))]
     pub payload: Vec<u8>,
 }
 
+/// The Flow Status (FS) of a Flow Control frame.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlowStatus {
+    ContinueToSend = 0x00, // Continue to send (CTS)
+    Wait = 0x01,           // Wait for the next Flow Control frame (WT)
+    Overflow = 0x02,       // Overflow, abort the transfer (OVFLW)
+}
+
+impl From<FlowStatus> for u8 {
+    fn from(status: FlowStatus) -> Self {
+        status as u8
+    }
+}
+
+impl TryFrom<u8> for FlowStatus {
+    type Error = FrameError;
+    fn try_from(status: u8) -> Result<Self, Self::Error> {
+        match status {
+            0x00 => Ok(FlowStatus::ContinueToSend),
+            0x01 => Ok(FlowStatus::Wait),
+            0x02 => Ok(FlowStatus::Overflow),
+            _ => Err(FrameError::InvalidFlowStatus),
+        }
+    }
+}
+
 /// Represents a UDS Flow Control Frame.
 /// This frame is sent by the receiver to control the flow of multi-frame transmissions.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct UdsFlowControlFrame {
-    /// Flow control flag:
-    /// - `0x00` = Continue to send (CTS)
-    /// - `0x01` = Wait (WT)
-    /// - `0x02` = Overflow/abort (OVFLW)
-    pub flag: u8,
+    /// Flow control flag: Continue to send, Wait or Overflow.
+    pub flag: FlowStatus,
     /// The number of Consecutive Frames the sender can transmit before waiting.
     pub block_size: u8,
-    /// Minimum separation time (ST) in milliseconds between transmitted frames.
+    /// Minimum separation time (STmin) between transmitted frames, ISO 15765-2 encoded (see
+    /// `separation_time_duration`).
     pub separation_time: u8,
     /// Optional padding bytes (if required for 8-byte CAN frames).
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_hex"))]
     pub padding: Vec<u8>,
 }
 
+/// Represents a UDS Negative Response (SID 0x7F).
+/// This frame is sent by the ECU when it rejects a request.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UdsNegativeResponse {
+    /// The Service Identifier (SID) of the rejected request.
+    pub rsid: u8,
+    /// The raw Negative Response Code (NRC).
+    pub nrc: u8,
+}
+
 impl UdsSingleFrame {
     /// Creates a new UDS Single Frame.
     ///
     /// # Parameters:
     /// - `sid`: Service Identifier.
-    /// - `did`: Optional Diagnostic Identifier.
-    /// - `payload`: The payload data (max 7 bytes).
+    /// - `payload`: The service parameters.
+    ///
+    /// The SID and payload fit in 7 bytes on CAN; up to 62 bytes are accepted for CAN FD, where
+    /// the size is escaped.
     ///
     /// # Returns:
     /// - `Ok(UdsSingleFrame)`: If the payload size is valid.
-    /// - `Err(FrameError)`: If the payload exceeds 7 bytes.
-    pub fn new(sid: u8, did: Option<u16>, payload: Vec<u8>) -> Result<Self, FrameError> {
-        if payload.len() > 7 {
+    /// - `Err(FrameError)`: If the SID and payload exceed 62 bytes, or the SID is 0x7F (a
+    ///   negative response, see `UdsNegativeResponse`).
+    pub fn new(sid: u8, payload: Vec<u8>) -> Result<Self, FrameError> {
+        let size = payload.len() + 1;
+        if size > CAN_FD_MAX_DL - 2 {
             return Err(FrameError::InvalidCanLength);
         }
-
-        let size = if did.is_some() {
-            payload.len() + 3
-        } else {
-            payload.len() + 1
-        } as u8;
+        if sid == 0x7F {
+            return Err(FrameError::InvalidSid);
+        }
 
         Ok(Self {
-            size,
+            size: size as u8,
             sid,
-            did,
             payload,
         })
     }
 
+    /// Check that the single frame can be encoded: the SID and payload fit in 62 bytes (7
+    /// on classic CAN, the larger sizes being escaped) and `size` is their length. The SID
+    /// can't be 0x7F, the frame would decode as a negative response.
+    pub fn validate(&self) -> Result<(), FrameError> {
+        let len = 1 + self.params().len();
+        if len > CAN_FD_MAX_DL - 2 || self.size as usize != len {
+            return Err(FrameError::InvalidSize);
+        }
+        if self.sid == 0x7F {
+            return Err(FrameError::InvalidSid);
+        }
+        Ok(())
+    }
+
     /// Converts the single frame into a CAN frame byte vector.
     ///
+    /// A size over 7 bytes is escaped as on CAN FD: the PCI byte is 0 and the size follows.
+    ///
     /// # Returns:
     /// - `Ok(Vec<u8>)`: The CAN frame representation.
-    /// - `Err(DiagError)`: If the payload size exceeds 7 bytes.
+    /// - `Err(DiagError)`: If the frame isn't valid, see `validate`.
     pub fn to_vec(&self) -> Result<Vec<u8>, DiagError> {
-        if self.payload.len() > 7 {
-            return Err(DiagError::FrameError {
-                error: FrameError::InvalidSize,
-            });
-        }
+        self.validate()?;
 
         let mut frame = Vec::new();
-        frame.push(self.size & 0x0F); // PCI byte (first nibble is 0 for Single Frame)
-        frame.push(self.sid);
-        if let Some(did) = self.did {
-            frame.extend_from_slice(&did.to_be_bytes());
+        if self.size > 7 {
+            frame.extend_from_slice(&[0x00, self.size]); // Escaped PCI: the size follows
+        } else {
+            frame.push(self.size & 0x0F); // PCI byte (first nibble is 0 for Single Frame)
         }
-        frame.extend_from_slice(&self.payload);
+        frame.push(self.sid);
+        frame.extend_from_slice(&self.params());
 
         Ok(frame)
     }
+
+    /// Returns the service parameters following the SID.
+    pub fn params(&self) -> Vec<u8> {
+        self.payload.clone()
+    }
+}
+
+impl TryFrom<&[u8]> for UdsSingleFrame {
+    type Error = DiagError;
+
+    /// Decode a Single Frame (PCI nibble 0x0), the bytes after its size being padding.
+    /// A negative response returns `FrameError::InvalidSid`, see `UdsNegativeResponse`.
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        frame_type(data, &[PciType::SingleFrame])?;
+        let (size, frame_data) = message_data(data)?;
+        let (sid, payload) = positive_message(frame_data)?;
+        Ok(Self {
+            size: size as u8,
+            sid,
+            payload,
+        })
+    }
 }
 
 impl UdsFirstFrame {
@@ -314,48 +505,86 @@ impl UdsFirstFrame {
     /// # Parameters:
     /// - `sid`: Service Identifier.
     /// - `size`: Total payload size.
-    /// - `did`: Optional Diagnostic Identifier.
-    /// - `payload`: Initial chunk of the payload (max 6 bytes).
+    /// - `payload`: Initial chunk of the service parameters.
+    ///
+    /// On CAN, the SID and payload fill the 6 data bytes of a First Frame (max 5 bytes of
+    /// payload); frames up to 64 bytes are accepted for CAN FD.
     ///
     /// # Returns:
     /// - `Ok(UdsFirstFrame)`: If the payload size is valid.
-    /// - `Err(FrameError)`: If the payload exceeds 6 bytes.
-    pub fn new(sid: u8, size: u16, did: Option<u16>, payload: Vec<u8>) -> Result<Self, FrameError> {
-        if payload.len() > 6 {
+    /// - `Err(FrameError)`: If the First Frame exceeds 64 bytes, or the SID is 0x7F.
+    pub fn new(sid: u8, size: u32, payload: Vec<u8>) -> Result<Self, FrameError> {
+        if sid == 0x7F {
+            return Err(FrameError::InvalidSid);
+        }
+        let frame = Self { size, sid, payload };
+        if frame.pci_len() + 1 + frame.params().len() > CAN_FD_MAX_DL {
             return Err(FrameError::InvalidCanLength);
         }
 
-        Ok(Self {
-            size,
-            sid,
-            did,
-            payload,
-        })
+        Ok(frame)
+    }
+
+    /// Returns the length of the PCI: 2 bytes, or 6 bytes when the size is escaped.
+    pub fn pci_len(&self) -> usize {
+        if self.size > 0xFFF { 6 } else { 2 }
+    }
+
+    /// Check that the first frame can be encoded: the PCI, SID and payload fit in 64 bytes
+    /// (8 on classic CAN), and `size` covers the data of the frame. As for single frames, the
+    /// SID can't be 0x7F.
+    pub fn validate(&self) -> Result<(), FrameError> {
+        let len = 1 + self.params().len();
+        if self.pci_len() + len > CAN_FD_MAX_DL || (self.size as usize) < len {
+            return Err(FrameError::InvalidSize);
+        }
+        if self.sid == 0x7F {
+            return Err(FrameError::InvalidSid);
+        }
+        Ok(())
     }
 
     /// Converts the first frame into a CAN frame byte vector.
     ///
+    /// A size over 4095 bytes is escaped: the 12 bits size is 0 and the 32 bits size follows.
+    ///
     /// # Returns:
     /// - `Ok(Vec<u8>)`: The CAN frame representation.
-    /// - `Err(DiagError)`: If the payload size exceeds 6 bytes.
+    /// - `Err(DiagError)`: If the frame isn't valid, see `validate`.
     pub fn to_vec(&self) -> Result<Vec<u8>, DiagError> {
-        if self.payload.len() > 6 {
-            return Err(DiagError::FrameError {
-                error: FrameError::InvalidSize,
-            });
-        }
+        self.validate()?;
 
         let mut frame = Vec::new();
-        frame.push(0x10 | ((self.size >> 8) as u8 & 0x0F)); // PCI first byte
-        frame.push((self.size & 0xFF) as u8); // PCI second byte
-        frame.push(self.sid);
-        if let Some(did) = self.did {
-            frame.extend_from_slice(&did.to_be_bytes());
+        if self.size > 0xFFF {
+            frame.extend_from_slice(&[0x10, 0x00]); // Escaped PCI: the 32 bits size follows
+            frame.extend_from_slice(&self.size.to_be_bytes());
+        } else {
+            frame.push(0x10 | ((self.size >> 8) as u8 & 0x0F)); // PCI first byte
+            frame.push((self.size & 0xFF) as u8); // PCI second byte
         }
-        frame.extend_from_slice(&self.payload);
+        frame.push(self.sid);
+        frame.extend_from_slice(&self.params());
 
         Ok(frame)
     }
+
+    /// Returns the service parameters following the SID.
+    pub fn params(&self) -> Vec<u8> {
+        self.payload.clone()
+    }
+}
+
+impl TryFrom<&[u8]> for UdsFirstFrame {
+    type Error = DiagError;
+
+    /// Decode a First Frame (PCI nibble 0x1), with a 12 bits or an escaped 32 bits size.
+    /// A negative response returns `FrameError::InvalidSid`, see `UdsNegativeResponse`.
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        frame_type(data, &[PciType::FirstFrame])?;
+        let (size, frame_data) = message_data(data)?;
+        let (sid, payload) = positive_message(frame_data)?;
+        Ok(Self { size, sid, payload })
+    }
 }
 
 impl UdsConsecutiveFrame {
@@ -363,30 +592,57 @@ impl UdsConsecutiveFrame {
     ///
     /// # Parameters:
     /// - `seq_num`: Sequence number (0-15).
-    /// - `payload`: Payload data (max 7 bytes).
+    /// - `payload`: Payload data (max 7 bytes on CAN, 63 bytes on CAN FD).
     ///
     /// # Returns:
     /// - `Ok(UdsConsecutiveFrame)`: If the payload size is valid.
-    /// - `Err(FrameError)`: If the payload exceeds 7 bytes.
+    /// - `Err(FrameError)`: If the payload exceeds 63 bytes.
     pub fn new(seq_num: u8, payload: Vec<u8>) -> Result<Self, FrameError> {
-        if payload.len() > 7 {
+        if payload.len() > CAN_FD_MAX_DL - 1 {
             return Err(FrameError::InvalidCanLength);
         }
 
         Ok(Self { seq_num, payload })
     }
 
+    /// Check that this frame follows the frame with sequence number `previous`.
+    ///
+    /// The First Frame has the implied sequence number 0, so the first Consecutive Frame is 1,
+    /// and the sequence number wraps from 15 to 0.
+    ///
+    /// # Returns:
+    /// - `Ok(())`: If the sequence number is the expected one.
+    /// - `Err(DiagError::SequenceError)`: If a frame was lost, repeated or reordered.
+    pub fn check_sequence(&self, previous: u8) -> Result<(), DiagError> {
+        let expected = previous.wrapping_add(1) & 0x0F;
+        if self.seq_num != expected {
+            return Err(DiagError::SequenceError {
+                expected,
+                received: self.seq_num,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that the consecutive frame can be encoded: the sequence number fits in 4 bits and
+    /// the payload in 63 bytes (7 on classic CAN).
+    pub fn validate(&self) -> Result<(), FrameError> {
+        if self.seq_num > 0x0F {
+            return Err(FrameError::InvalidSequenceNumber);
+        }
+        if self.payload.len() > CAN_FD_MAX_DL - 1 {
+            return Err(FrameError::InvalidSize);
+        }
+        Ok(())
+    }
+
     /// Converts the consecutive frame into a CAN frame byte vector.
     ///
     /// # Returns:
     /// - `Ok(Vec<u8>)`: The CAN frame representation.
-    /// - `Err(DiagError)`: If the payload size exceeds 7 bytes.
+    /// - `Err(DiagError)`: If the frame isn't valid, see `validate`.
     pub fn to_vec(&self) -> Result<Vec<u8>, DiagError> {
-        if self.payload.len() > 7 {
-            return Err(DiagError::FrameError {
-                error: FrameError::InvalidSize,
-            });
-        }
+        self.validate()?;
 
         let mut frame = Vec::new();
         frame.push(0x20 | (self.seq_num & 0x0F)); // PCI byte
@@ -396,11 +652,24 @@ impl UdsConsecutiveFrame {
     }
 }
 
+impl TryFrom<&[u8]> for UdsConsecutiveFrame {
+    type Error = DiagError;
+
+    /// Decode a Consecutive Frame (PCI nibble 0x2).
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        frame_type(data, &[PciType::ConsecutiveFrame])?;
+        Ok(Self {
+            seq_num: data[0] & 0x0F,
+            payload: data[1..].to_vec(),
+        })
+    }
+}
+
 impl UdsFlowControlFrame {
     /// Creates a new UDS Flow Control Frame.
     ///
     /// # Parameters:
-    /// - `flag`: Flow control flag (0=CTS, 1=Wait, 2=Overflow).
+    /// - `flag`: Flow control flag (Continue to send, Wait or Overflow).
     /// - `block_size`: Number of consecutive frames before next flow control.
     /// - `separation_time`: Time delay (in ms) between frames.
     /// - `padding`: Optional padding data (max 5 bytes).
@@ -409,7 +678,7 @@ impl UdsFlowControlFrame {
     /// - `Ok(UdsFlowControlFrame)`: If the padding size is valid.
     /// - `Err(FrameError)`: If the padding exceeds 5 bytes.
     pub fn new(
-        flag: u8,
+        flag: FlowStatus,
         block_size: u8,
         separation_time: u8,
         padding: Vec<u8>,
@@ -425,13 +694,47 @@ impl UdsFlowControlFrame {
         })
     }
 
+    /// Decode the STmin: 0x00-0x7F are milliseconds, 0xF1-0xF9 are 100-900us.
+    /// Reserved values are treated as the longest valid separation time (127ms).
+    pub fn separation_time_duration(&self) -> Duration {
+        match self.separation_time {
+            st @ 0x00..=0x7F => Duration::from_millis(st as u64),
+            st @ 0xF1..=0xF9 => Duration::from_micros((st - 0xF0) as u64 * 100),
+            _ => Duration::from_millis(0x7F),
+        }
+    }
+
+    /// Encode `duration` as a STmin, the inverse of `separation_time_duration`.
+    ///
+    /// STmin is a minimum: `duration` is rounded up to the next encodable value (100us steps
+    /// below 1ms, milliseconds above), and capped to 127ms.
+    pub fn separation_time_from_duration(duration: Duration) -> u8 {
+        let micros = duration.as_micros();
+        match micros {
+            0 => 0x00,
+            1..=900 => 0xF0 + micros.div_ceil(100) as u8,
+            _ => micros.div_ceil(1000).min(0x7F) as u8,
+        }
+    }
+
+    /// Check that the flow control frame can be encoded: the padding completes the 3 bytes of
+    /// the frame to 8 bytes at most (CAN FD frames are padded to their DLC when transmitted).
+    pub fn validate(&self) -> Result<(), FrameError> {
+        if self.padding.len() > 5 {
+            return Err(FrameError::InvalidCanLength);
+        }
+        Ok(())
+    }
+
     /// Converts the flow control frame into a CAN frame byte vector.
     ///
     /// # Returns:
     /// - `Ok(Vec<u8>)`: The CAN frame representation.
+    /// - `Err(DiagError)`: If the frame isn't valid, see `validate`.
     pub fn to_vec(&self) -> Result<Vec<u8>, DiagError> {
+        self.validate()?;
         let mut frame = vec![
-            0x30 | (self.flag & 0x0F), // PCI byte
+            0x30 | u8::from(self.flag), // PCI byte
             self.block_size,
             self.separation_time,
         ];
@@ -442,3 +745,72 @@ impl UdsFlowControlFrame {
         Ok(frame)
     }
 }
+
+impl TryFrom<&[u8]> for UdsFlowControlFrame {
+    type Error = DiagError;
+
+    /// Decode a Flow Control frame (PCI nibble 0x3), the bytes after STmin being padding.
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        frame_type(data, &[PciType::FlowControl])?;
+        let [pci, block_size, separation_time, padding @ ..] = data else {
+            return Err(DiagError::FrameError {
+                error: FrameError::InvalidSize,
+            });
+        };
+        Ok(Self {
+            flag: FlowStatus::try_from(pci & 0x0F)?,
+            block_size: *block_size,
+            separation_time: *separation_time,
+            padding: padding.to_vec(),
+        })
+    }
+}
+
+impl UdsNegativeResponse {
+    /// Creates a new UDS Negative Response.
+    ///
+    /// # Parameters:
+    /// - `rsid`: Service Identifier of the rejected request.
+    /// - `nrc`: Negative Response Code.
+    pub fn new(rsid: u8, nrc: u8) -> Self {
+        Self { rsid, nrc }
+    }
+
+    /// Returns the Negative Response Code, vendor specific codes are kept as raw bytes.
+    pub fn code(&self) -> Nrc {
+        Nrc::from(self.nrc)
+    }
+
+    /// A negative response is always valid: its 3 bytes fit in any frame.
+    pub fn validate(&self) -> Result<(), FrameError> {
+        Ok(())
+    }
+
+    /// Converts the negative response into a CAN frame byte vector.
+    ///
+    /// # Returns:
+    /// - `Ok(Vec<u8>)`: The CAN frame representation `[0x03, 0x7F, rsid, nrc]`.
+    pub fn to_vec(&self) -> Result<Vec<u8>, DiagError> {
+        Ok(vec![0x03, 0x7F, self.rsid, self.nrc])
+    }
+}
+
+impl TryFrom<&[u8]> for UdsNegativeResponse {
+    type Error = DiagError;
+
+    /// Decode a negative response: a Single Frame (PCI nibble 0x0), or a First Frame as some
+    /// gateways segment it, carrying `[0x7F, rsid, nrc]`. Another SID returns
+    /// `FrameError::InvalidSid`.
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let (_, frame_data) = message_data(data)?;
+        match *frame_data {
+            [0x7F, rsid, nrc, ..] => Ok(Self { rsid, nrc }),
+            [0x7F, ..] => Err(DiagError::FrameError {
+                error: FrameError::InvalidSize,
+            }),
+            _ => Err(DiagError::FrameError {
+                error: FrameError::InvalidSid,
+            }),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+/// Request/response latency statistics, accumulated by `UdsClient`.
+///
+/// A latency is the time between transmitting a request and resolving its response, including
+/// the time the ECU extended with responsePending (NRC 0x78). Requests that timed out are not
+/// measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub count: u32,              // Number of measured requests
+    pub min: Duration,           // Shortest latency
+    pub max: Duration,           // Longest latency
+    pub total: Duration,         // Sum of all latencies
+    pub last: Duration,          // Latency of the last measured request
+    pub pending_extensions: u32, // Number of responsePending (NRC 0x78) received
+}
+
+impl LatencyStats {
+    /// Returns the average latency, `None` if nothing was measured yet.
+    pub fn avg(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.total / self.count)
+    }
+
+    /// Internal function: add a measured request.
+    pub(crate) fn record(&mut self, latency: Duration, pending_extensions: u32) {
+        self.min = if self.count == 0 {
+            latency
+        } else {
+            self.min.min(latency)
+        };
+        self.max = self.max.max(latency);
+        self.total += latency;
+        self.last = latency;
+        self.count += 1;
+        self.pending_extensions += pending_extensions;
+    }
+}
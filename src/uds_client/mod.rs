@@ -1,26 +1,61 @@
+mod addressing;
+mod checksum;
 mod client;
 mod frame;
+mod metrics;
+mod nrc;
+mod parser;
 mod pci;
+mod profile;
 mod response;
+mod segmenter;
+#[cfg(feature = "serde")]
+mod serde_hex;
+#[cfg(feature = "serde")]
+mod serde_millis;
 mod services;
 
+use crate::socket_can::CanBusError;
+pub use addressing::{default_response_id, normal_fixed_response_id};
 use automotive_diag::uds::{UdsCommand, UdsError};
+pub use checksum::Crc;
 pub use client::UdsClient;
 pub use frame::*;
+pub use metrics::LatencyStats;
+pub use nrc::Nrc;
+pub use parser::UdsResponseParser;
 pub use pci::{PciByte, PciType};
-pub use response::{Response, ResponseSlot};
-pub use services::RealTimeType;
+pub use profile::EcuProfile;
+pub use response::{Response, ResponseSlot, Slot};
+pub use segmenter::IsoTpSegmenter;
+pub use services::{
+    CommandOutput, CommunicationControlType, DID_ACTIVE_DIAGNOSTIC_SESSION, DID_ECU_HW_VERSION,
+    DID_ECU_SW_VERSION, DID_SUPPLIER_ECU_HW_NUMBER, DID_VIN, DataFormat, DiagnosticSessionType,
+    DidRegistry, Dtc, DtcExtendedDataRecord, DtcSettingType, DtcSeverityRecord, DtcSnapshotRecord,
+    EcuResetType, FUNCTIONAL_REQUEST_ID, FromUdsBytes, IntoUdsBytes,
+    NETWORK_MANAGEMENT_COMMUNICATION_MESSAGES, NORMAL_COMMUNICATION_MESSAGES, PeriodicParsers,
+    PeriodicSample, ProgrammingOptions, ROUTINE_CHECK_PROGRAMMING_DEPENDENCIES,
+    ROUTINE_ERASE_MEMORY, RealTimeType, RoutineControlType, RoutineResult, SessionTiming,
+    TimingParameterAccessType, decode_addr_and_length, encode_addr_and_length,
+};
 
 #[derive(Clone, Debug, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Diagnostic server error
 pub enum DiagError {
+    /// The ECU doesn't support the request: NRC 0x11 (serviceNotSupported) or 0x12
+    /// (subFunctionNotSupported)
     #[error("Diagnostic server does not support the request")]
     NotSupported,
     /// Negative Response from ECU
-    #[error("ECU error: 0x{:02X} ({:?})", *code as u8, def)]
+    #[error(
+        "ECU error: 0x{:02X} ({})",
+        u8::from(*code),
+        def.as_deref().unwrap_or(code.description())
+    )]
     ECUError {
-        /// Raw Negative response code from ECU
-        code: UdsError,
+        /// Negative response code from ECU, vendor specific codes are kept as raw bytes
+        code: Nrc,
         /// Requested SID
         rsid: UdsCommand,
         /// Negative response code definition according to protocol
@@ -51,6 +86,20 @@ pub enum DiagError {
     /// ECU Responded with a message, but the length was incorrect
     #[error("ECU response size was not the correct length")]
     InvalidResponseLength,
+    /// The payload of a multi-frame response doesn't match the size announced by its First
+    /// Frame
+    #[error(
+        "ECU response size was not the correct length. Expected: {expected}, received {received}"
+    )]
+    ResponseLengthMismatch {
+        /// Size announced by the First Frame
+        expected: u32,
+        /// Payload received, up to the frame ending the transfer
+        received: u32,
+    },
+    /// ECU Responded with a message of the correct length, but its content can't be decoded
+    #[error("ECU response data could not be decoded")]
+    InvalidResponseData,
     /// A parameter given to the function is invalid. Check the function's documentation
     /// for more information
     #[error("Diagnostic function parameter invalid")]
@@ -61,6 +110,9 @@ pub enum DiagError {
     /// Device hardware error
     #[error("Diagnostic server hardware error")]
     HardwareError,
+    /// The CAN controller is bus-off: the physical layer is down, not the ECU unresponsive
+    #[error("CAN controller is bus-off")]
+    BusOff,
     /// Feauture is not iumplemented yet
     #[error("Diagnostic server feature is unimplemented: '{0}'")]
     NotImplemented(String),
@@ -76,13 +128,91 @@ pub enum DiagError {
         /// Received PID from ECU
         received: u16,
     },
-    /// timeout response
+    /// RX timeout: the request was transmitted, but the ECU didn't respond in time
     #[error("ECU server didn't response in time")]
     Timeout,
-    /// Other Diagnostic Error
+    /// TX timeout: the request couldn't be transmitted in time, the bus is busy or dead
+    #[error("CAN frame could not be transmitted in time")]
+    TxTimeout,
+    /// A Consecutive Frame was received out of sequence during a multi-frame transfer
+    #[error("Consecutive Frame out of sequence. Expected: {expected}, received {received}")]
+    SequenceError {
+        /// Expected sequence number
+        expected: u8,
+        /// Received sequence number
+        received: u8,
+    },
+    /// An ISO 15765-2 network layer timer (N_Bs or N_Cr) expired during a multi-frame transfer
+    #[error("ISO-TP {0} timeout: the multi-frame transfer was aborted")]
+    IsoTpTimeout(&'static str),
+    /// A request was sent while a multi-frame response was still being received, e.g. after
+    /// the future receiving it was dropped: ISO-TP is half-duplex, the frames would interleave
+    #[error("A multi-frame response is still being received")]
+    Busy,
+    /// The ECU answered a multi-frame request with a Flow Control Overflow, aborting the transfer
+    #[error("ECU aborted the multi-frame transfer: flow control overflow")]
+    FlowControlOverflow,
+    /// Error while encoding or decoding a frame, available as the error `source()`
     #[error("Diag Frame Error: {error}")]
-    FrameError { error: FrameError },
+    FrameError {
+        #[from]
+        error: FrameError,
+    },
+    /// The transfer was aborted with the token from `UdsClient::transfer_stop_token`
+    #[error("The transfer was aborted")]
+    TransferAborted,
+    /// The data read from the ECU couldn't be written to the sink, with the I/O error message
+    #[error("Failed to write the read data: {0}")]
+    WriteError(String),
+    /// The data to transfer couldn't be read from the source, with the I/O error message
+    #[error("Failed to read the data to transfer: {0}")]
+    ReadError(String),
     /// Other Diagnostic Error
     #[error("Unkown Diagnostic Error")]
     Others,
 }
+
+impl From<CanBusError> for DiagError {
+    /// A transmit timeout reported by the controller is a `TxTimeout`, not an unresponsive ECU.
+    fn from(error: CanBusError) -> Self {
+        match error {
+            CanBusError::TransmitTimeout => DiagError::TxTimeout,
+            CanBusError::BusOff => DiagError::BusOff,
+            _ => DiagError::ChannelError,
+        }
+    }
+}
+
+impl DiagError {
+    /// Build the `ECUError` of the negative response `nr`, with the description of its code.
+    ///
+    /// A negative response to an unknown service returns `FrameError::InvalidSid`.
+    pub fn from_negative_response(nr: &UdsNegativeResponse) -> Self {
+        let Some(rsid) = UdsCommand::from_repr(nr.rsid) else {
+            return DiagError::FrameError {
+                error: FrameError::InvalidSid,
+            };
+        };
+        let code = nr.code();
+        DiagError::ECUError {
+            code,
+            rsid,
+            def: Some(code.description().to_string()),
+        }
+    }
+
+    /// Check whether the ECU doesn't support the request: `NotSupported`, or a negative
+    /// response 0x7E/0x7F (the sub-function/service isn't supported in the active session).
+    pub fn is_not_supported(&self) -> bool {
+        match self {
+            DiagError::NotSupported => true,
+            DiagError::ECUError { code, .. } => {
+                *code == UdsError::ServiceNotSupported
+                    || *code == UdsError::SubFunctionNotSupported
+                    || *code == UdsError::SubFunctionNotSupportedInActiveSession
+                    || *code == UdsError::ServiceNotSupportedInActiveSession
+            }
+            _ => false,
+        }
+    }
+}
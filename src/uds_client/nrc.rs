@@ -0,0 +1,122 @@
+use automotive_diag::uds::UdsError;
+
+/// A Negative Response Code (NRC) received from the ECU.
+///
+/// ISO 14229 leaves ranges of codes to the vehicle manufacturers (e.g. 0x38..=0x4F, 0x90..=0xFF),
+/// which `UdsError` doesn't know. These are kept as their raw byte instead of being rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "u8", into = "u8")
+)]
+pub enum Nrc {
+    Standard(UdsError), // A code defined by ISO 14229
+    VendorSpecific(u8), // A manufacturer specific (or reserved) code
+}
+
+impl Nrc {
+    /// Returns the standard definition of the code, `None` for a vendor specific code.
+    pub fn standard(&self) -> Option<UdsError> {
+        match self {
+            Nrc::Standard(code) => Some(*code),
+            Nrc::VendorSpecific(_) => None,
+        }
+    }
+
+    /// Returns the ISO 14229 description of the code, e.g. "request out of range".
+    pub fn description(&self) -> &'static str {
+        match self {
+            Nrc::Standard(UdsError::GeneralReject) => "general reject",
+            Nrc::Standard(UdsError::ServiceNotSupported) => "service not supported",
+            Nrc::Standard(UdsError::SubFunctionNotSupported) => "sub-function not supported",
+            Nrc::Standard(UdsError::IncorrectMessageLengthOrInvalidFormat) => {
+                "incorrect message length or invalid format"
+            }
+            Nrc::Standard(UdsError::ResponseTooLong) => "response too long",
+            Nrc::Standard(UdsError::BusyRepeatRequest) => "busy, repeat request",
+            Nrc::Standard(UdsError::ConditionsNotCorrect) => "conditions not correct",
+            Nrc::Standard(UdsError::RequestSequenceError) => "request sequence error",
+            Nrc::Standard(UdsError::NoResponseFromSubnetComponent) => {
+                "no response from subnet component"
+            }
+            Nrc::Standard(UdsError::FailurePreventsExecutionOfRequestedAction) => {
+                "failure prevents execution of requested action"
+            }
+            Nrc::Standard(UdsError::RequestOutOfRange) => "request out of range",
+            Nrc::Standard(UdsError::SecurityAccessDenied) => "security access denied",
+            Nrc::Standard(UdsError::InvalidKey) => "invalid key",
+            Nrc::Standard(UdsError::ExceedNumberOfAttempts) => "exceeded number of attempts",
+            Nrc::Standard(UdsError::RequiredTimeDelayNotExpired) => {
+                "required time delay not expired"
+            }
+            Nrc::Standard(UdsError::UploadDownloadNotAccepted) => "upload/download not accepted",
+            Nrc::Standard(UdsError::TransferDataSuspended) => "transfer data suspended",
+            Nrc::Standard(UdsError::GeneralProgrammingFailure) => "general programming failure",
+            Nrc::Standard(UdsError::WrongBlockSequenceCounter) => "wrong block sequence counter",
+            Nrc::Standard(UdsError::RequestCorrectlyReceivedResponsePending) => {
+                "request correctly received, response pending"
+            }
+            Nrc::Standard(UdsError::SubFunctionNotSupportedInActiveSession) => {
+                "sub-function not supported in active session"
+            }
+            Nrc::Standard(UdsError::ServiceNotSupportedInActiveSession) => {
+                "service not supported in active session"
+            }
+            Nrc::Standard(UdsError::RpmTooHigh) => "RPM too high",
+            Nrc::Standard(UdsError::RpmTooLow) => "RPM too low",
+            Nrc::Standard(UdsError::EngineIsRunning) => "engine is running",
+            Nrc::Standard(UdsError::EngineIsNotRunning) => "engine is not running",
+            Nrc::Standard(UdsError::EngineRunTimeTooLow) => "engine run time too low",
+            Nrc::Standard(UdsError::TemperatureTooHigh) => "temperature too high",
+            Nrc::Standard(UdsError::TemperatureTooLow) => "temperature too low",
+            Nrc::Standard(UdsError::VehicleSpeedTooHigh) => "vehicle speed too high",
+            Nrc::Standard(UdsError::VehicleSpeedTooLow) => "vehicle speed too low",
+            Nrc::Standard(UdsError::ThrottleTooHigh) => "throttle/pedal too high",
+            Nrc::Standard(UdsError::ThrottleTooLow) => "throttle/pedal too low",
+            Nrc::Standard(UdsError::TransmissionRangeNotInNeutral) => {
+                "transmission range not in neutral"
+            }
+            Nrc::Standard(UdsError::TransmissionRangeNotInGear) => "transmission range not in gear",
+            Nrc::Standard(UdsError::BrakeSwitchNotClosed) => "brake switch(es) not closed",
+            Nrc::Standard(UdsError::ShifterLeverNotInPark) => "shifter lever not in park",
+            Nrc::Standard(UdsError::TorqueConverterClutchLocked) => {
+                "torque converter clutch locked"
+            }
+            Nrc::Standard(UdsError::VoltageTooHigh) => "voltage too high",
+            Nrc::Standard(UdsError::VoltageTooLow) => "voltage too low",
+            Nrc::VendorSpecific(_) => "vehicle manufacturer specific",
+        }
+    }
+}
+
+impl From<u8> for Nrc {
+    fn from(value: u8) -> Self {
+        match UdsError::from_repr(value) {
+            Some(code) => Nrc::Standard(code),
+            None => Nrc::VendorSpecific(value),
+        }
+    }
+}
+
+impl From<UdsError> for Nrc {
+    fn from(value: UdsError) -> Self {
+        Nrc::Standard(value)
+    }
+}
+
+impl From<Nrc> for u8 {
+    fn from(value: Nrc) -> Self {
+        match value {
+            Nrc::Standard(code) => code as u8,
+            Nrc::VendorSpecific(code) => code,
+        }
+    }
+}
+
+impl PartialEq<UdsError> for Nrc {
+    fn eq(&self, other: &UdsError) -> bool {
+        *self == Nrc::Standard(*other)
+    }
+}
@@ -0,0 +1,40 @@
+use automotive_diag::uds::UdsCommand;
+
+use super::{DiagError, PciType, UdsFrame, client::check_positive_sid};
+
+/// Decoding of the response to a service, see `UdsClient::request_parsed`.
+///
+/// A parser declares the service it answers and decodes the parameters of its positive
+/// response. The provided `parse` does the validation common to every service: a negative
+/// response is mapped to its `DiagError::ECUError`, and the response SID must be the positive
+/// response SID of `COMMAND` (`DiagError::WrongMessage` otherwise). The parser is a value, so it
+/// can carry the context of the request, e.g. the requested sub-function.
+pub trait UdsResponseParser {
+    /// The decoded response.
+    type Output;
+
+    /// The service answered, its positive response SID is `COMMAND | 0x40`.
+    const COMMAND: UdsCommand;
+
+    /// Decode `params`, the parameters following the positive response SID.
+    fn parse_params(&self, params: &[u8]) -> Result<Self::Output, DiagError>;
+
+    /// Validate and decode a complete response frame. A First Frame must carry the whole
+    /// payload (see `UdsClient::send_frame_with_response`), Consecutive and Flow Control frames
+    /// return `DiagError::WrongPciType`.
+    fn parse(&self, frame: &UdsFrame) -> Result<Self::Output, DiagError> {
+        let (sid, params) = match frame {
+            UdsFrame::Single(frame) => (frame.sid, frame.params()),
+            UdsFrame::First(frame) => (frame.sid, frame.params()),
+            UdsFrame::Negative(frame) => return Err(DiagError::from_negative_response(frame)),
+            frame => {
+                return Err(DiagError::WrongPciType {
+                    want: PciType::SingleFrame,
+                    received: frame.pci_type(),
+                });
+            }
+        };
+        check_positive_sid(sid, Self::COMMAND)?;
+        self.parse_params(&params)
+    }
+}
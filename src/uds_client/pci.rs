@@ -1,5 +1,8 @@
+use super::frame::FrameError;
+
 /// The definition for the Protocol Control Information (PCI) byte type used in ISO 15765-2 (CAN TP).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PciType {
     /// Single Frame (SF): Used for messages that fit in a single frame (<= 7 bytes).
     /// PCI byte range: 0x00-0x0F.
@@ -36,13 +39,57 @@ impl From<PciByte> for u8 {
     }
 }
 
+/// Implements conversion from `u8` to `PciByte`, see `PciByte::from_byte`.
+impl TryFrom<u8> for PciByte {
+    type Error = FrameError;
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        Self::from_byte(byte)
+    }
+}
+
 /// Implementation for PCI byte handling based on ISO 15765-2 (CAN TP).
 impl PciByte {
     /// Creates a new `PciByte` instance with the specified PCI type and value.
+    ///
+    /// The value is not checked: only its low nibble is kept when the byte is encoded, so
+    /// `PciByte::new(PciType::FirstFrame, 20)` encodes as 0x14. Use `try_new` to reject
+    /// values that don't fit.
     pub fn new(pci_type: PciType, value: u8) -> Self {
         Self { pci_type, value }
     }
 
+    /// Creates a new `PciByte` instance, checking that the value fits the PCI type.
+    ///
+    /// A Single Frame value is the payload length and must be 0..=7 (0 being the escape for
+    /// longer CAN FD frames), otherwise `FrameError::InvalidSize` is returned. For the other
+    /// frame types the value is the whole low nibble and must be 0..=0x0F, otherwise
+    /// `FrameError::InvalidSize`, `FrameError::InvalidSequenceNumber` or
+    /// `FrameError::InvalidFlowStatus` is returned for First, Consecutive and Flow Control
+    /// frames respectively.
+    pub fn try_new(pci_type: PciType, value: u8) -> Result<Self, FrameError> {
+        match pci_type {
+            PciType::SingleFrame if value > 0x07 => Err(FrameError::InvalidSize),
+            PciType::FirstFrame if value > 0x0F => Err(FrameError::InvalidSize),
+            PciType::ConsecutiveFrame if value > 0x0F => Err(FrameError::InvalidSequenceNumber),
+            PciType::FlowControl if value > 0x0F => Err(FrameError::InvalidFlowStatus),
+            _ => Ok(Self::new(pci_type, value)),
+        }
+    }
+
+    /// Decodes a PCI byte, the inverse of `as_byte`: the high nibble is the PCI type and the
+    /// low nibble the value. The high nibbles 0x4-0xF are reserved and return
+    /// `FrameError::InvalidFrameType`.
+    pub fn from_byte(byte: u8) -> Result<Self, FrameError> {
+        let pci_type = match byte >> 4 {
+            0x0 => PciType::SingleFrame,
+            0x1 => PciType::FirstFrame,
+            0x2 => PciType::ConsecutiveFrame,
+            0x3 => PciType::FlowControl,
+            _ => return Err(FrameError::InvalidFrameType),
+        };
+        Ok(Self::new(pci_type, byte & 0x0F))
+    }
+
     /// Returns the PCI type of this byte.
     pub fn get_type(&self) -> PciType {
         self.pci_type
@@ -0,0 +1,86 @@
+//! Per-ECU configuration profiles.
+//!
+//! ECUs differ in padding, timings, addressing and CAN IDs. An `EcuProfile` bundles these
+//! settings so a tool talking to several ECUs configures each client in one call, see
+//! `UdsClient::from_profile`.
+//!
+//! With the `serde` feature, profiles can be loaded from a file in any serde format. Durations
+//! are written in milliseconds and missing fields keep their default, e.g. in JSON:
+//! ```json
+//! { "request_id": 2016, "padding": { "Pad": 170 }, "p2_timeout": 50, "p2_star_timeout": 5000 }
+//! ```
+
+use super::{
+    PaddingPolicy,
+    client::{
+        DEFAULT_BUSY_RETRY_DELAY, DEFAULT_FC_BLOCK_SIZE, DEFAULT_FC_SEPARATION_TIME, DEFAULT_N_BS,
+        DEFAULT_N_CR, DEFAULT_TX_DL,
+    },
+};
+use std::time::Duration;
+
+/// Default response timeout (P2), the default of `ResponseSlot::new`.
+const DEFAULT_P2_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// The communication settings of an ECU, see `UdsClient::from_profile`.
+///
+/// `Default` holds the defaults of `UdsClient::new` for the physical request ID 0x7E0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct EcuProfile {
+    pub request_id: u32,          // The CAN ID of the requests
+    pub response_id: Option<u32>, // The CAN ID of the responses, None for `default_response_id`
+    pub padding: PaddingPolicy,   // Padding of the transmitted frames
+    pub tx_data_length: usize,    // ISO-TP TX_DL: 8 on CAN, up to 64 on CAN FD
+    pub fc_block_size: u8,        // Block size advertised in our Flow Control frames
+    pub fc_separation_time: u8,   // Separation time advertised in our Flow Control frames
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_millis"))]
+    pub p2_timeout: Duration, // Response timeout (P2)
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_millis::option"))]
+    pub p2_star_timeout: Option<Duration>, // Timeout following a responsePending (P2*)
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_millis"))]
+    pub n_bs: Duration, // ISO-TP N_Bs: maximum wait for a Flow Control frame
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_millis"))]
+    pub n_cr: Duration, // ISO-TP N_Cr: maximum wait for a Consecutive Frame
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_millis"))]
+    pub min_tx_gap: Duration, // Minimum gap between two transmitted frames
+    pub busy_retries: u8,         // Repeats on busyRepeatRequest (0 = disabled)
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_millis"))]
+    pub busy_retry_delay: Duration, // Delay before repeating a busy request
+    pub bus_off_recovery: bool,   // Restart the controller after a bus-off
+}
+
+impl EcuProfile {
+    /// Create a profile with the default settings for the request ID `request_id`.
+    pub fn new(request_id: u32) -> Self {
+        Self {
+            request_id,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for EcuProfile {
+    fn default() -> Self {
+        Self {
+            request_id: 0x7E0,
+            response_id: None,
+            padding: PaddingPolicy::None,
+            tx_data_length: DEFAULT_TX_DL,
+            fc_block_size: DEFAULT_FC_BLOCK_SIZE,
+            fc_separation_time: DEFAULT_FC_SEPARATION_TIME,
+            p2_timeout: DEFAULT_P2_TIMEOUT,
+            p2_star_timeout: None,
+            n_bs: DEFAULT_N_BS,
+            n_cr: DEFAULT_N_CR,
+            min_tx_gap: Duration::ZERO,
+            busy_retries: 0,
+            busy_retry_delay: DEFAULT_BUSY_RETRY_DELAY,
+            bus_off_recovery: false,
+        }
+    }
+}
@@ -1,18 +1,209 @@
+use crate::logging::{log_debug, log_warn};
 use automotive_diag::uds::UdsError;
-use std::{cell::RefCell, time::Duration};
-use tokio::sync::{Mutex, Notify};
+use std::{
+    cell::RefCell,
+    sync::atomic::{AtomicBool, AtomicU8, AtomicU16, AtomicU32, AtomicU64, Ordering},
+    time::Duration,
+};
+use tokio::sync::{
+    Mutex, MutexGuard, Notify,
+    mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+};
 
-use super::{DiagError, frame::UdsFrame};
+use crate::socket_can::CanBusError;
+
+use super::{
+    DiagError,
+    client::CAN_FD_DATA_LENGTHS,
+    frame::{FrameError, UdsFrame},
+};
 
 #[derive(Debug, Clone)]
 pub enum Response {
-    Ok(UdsFrame),     // Successful response with a UDS frame
-    Error(DiagError), // Error response with a diagnostic error
+    Ok(UdsFrame, Option<u32>), // Successful response with a UDS frame and its source CAN ID
+    Error(DiagError),          // Error response with a diagnostic error
 }
 
-/// The response slot for each UDS request
-/// This struct holds the response data and a notification object to signal when the response is ready
-pub struct ResponseSlot(pub Mutex<RefCell<Response>>, pub Notify, Duration);
+impl Response {
+    /// Render the response frame as space-separated uppercase hex bytes (see
+    /// `UdsFrame::hex_dump`), or the error message.
+    pub fn hex_dump(&self) -> String {
+        match self {
+            Response::Ok(frame, _) => frame.hex_dump(),
+            Response::Error(e) => e.to_string(),
+        }
+    }
+
+    /// Check whether the response is a responsePending (NRC 0x78): the ECU needs more time
+    /// and the final response is still to come.
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            Response::Error(DiagError::ECUError { code, .. })
+                if *code == UdsError::RequestCorrectlyReceivedResponsePending
+        )
+    }
+
+    /// Returns the response frame, or `None` for an error.
+    pub fn as_frame(&self) -> Option<&UdsFrame> {
+        match self {
+            Response::Ok(frame, _) => Some(frame),
+            Response::Error(_) => None,
+        }
+    }
+
+    /// Returns the CAN ID of the ECU that sent the response frame, `None` for an error or a
+    /// frame given to `ResponseSlot::update_response` without its CAN ID.
+    ///
+    /// With several ECUs answering a functional request (slot without response ID), it tells
+    /// which one replied.
+    pub fn source_id(&self) -> Option<u32> {
+        match self {
+            Response::Ok(_, id) => *id,
+            Response::Error(_) => None,
+        }
+    }
+
+    /// Returns the error, or `None` for a response frame.
+    pub fn as_error(&self) -> Option<&DiagError> {
+        match self {
+            Response::Ok(..) => None,
+            Response::Error(e) => Some(e),
+        }
+    }
+}
+
+impl Default for Response {
+    fn default() -> Self {
+        Response::Error(DiagError::NotSupported)
+    }
+}
+
+/// A single-value slot shared between a producer (e.g. an RX task) and a waiting consumer.
+/// It holds the latest value and a notification object to signal when a new value is ready.
+///
+/// The slot isn't tied to UDS: any response type (raw bytes, DoIP messages...) can reuse the
+/// timeout logic. `ResponseSlot` wraps the slot used by `UdsClient`, with the context of the
+/// in-flight request and the CAN ID of the ECU to drop frames that don't answer it.
+pub struct Slot<T> {
+    value: Mutex<RefCell<T>>, // The latest value
+    notify: Notify,           // Signals that a new value is ready
+    timeout: AtomicU64,       // Default wait timeout, in microseconds
+}
+
+/// Request context value meaning "no request in flight": every frame is accepted.
+const NO_REQUEST: u16 = u16::MAX;
+/// Response ID value meaning "no response ID set": frames of any CAN ID are accepted.
+const ANY_RESPONSE_ID: u32 = u32::MAX;
+/// Pending timeout value meaning "not set": the response timeout applies after a 0x78 too.
+const NO_PENDING_TIMEOUT: u64 = u64::MAX;
+/// Default response timeout of a `ResponseSlot`.
+const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+impl<T: Default> Default for Slot<T> {
+    fn default() -> Self {
+        Self::with_timeout(T::default(), DEFAULT_RESPONSE_TIMEOUT)
+    }
+}
+
+impl<T> Slot<T> {
+    /// Create a new Slot holding `initial`, with `timeout` as the default wait timeout.
+    pub fn with_timeout(initial: T, timeout: Duration) -> Self {
+        Self {
+            value: Mutex::new(RefCell::new(initial)),
+            notify: Notify::new(),
+            timeout: AtomicU64::new(timeout.as_micros() as u64),
+        }
+    }
+
+    /// Returns the default wait timeout of the slot.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_micros(self.timeout.load(Ordering::Relaxed))
+    }
+
+    /// Change the default wait timeout of the slot, e.g. to the P2 timing of the ECU.
+    pub fn set_timeout(&self, timeout: Duration) {
+        self.timeout
+            .store(timeout.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Store a new value in the slot and notify the waiting task.
+    pub async fn update(&self, value: T) {
+        // Lock the Mutex and update the slot with the new value.
+        self.value.lock().await.replace(value);
+
+        // Notify any waiting task that a value is available.
+        self.notify.notify_one();
+    }
+
+    /// Lock the slot: updates wait, and `try_get` returns `None`, until the guard is dropped.
+    pub async fn lock(&self) -> MutexGuard<'_, RefCell<T>> {
+        self.value.lock().await
+    }
+}
+
+impl<T: Clone> Slot<T> {
+    /// Get a value in a blocking manner. This will block forever until a value is available.
+    ///
+    /// It waits for the notification to be triggered and then locks the Mutex to retrieve the value.
+    pub async fn get(&self) -> T {
+        // Wait for the notification signal.
+        self.notify.notified().await;
+
+        // Once notified, lock the Mutex and retrieve the value.
+        self.value.lock().await.borrow().clone()
+    }
+
+    /// Take the value if one was stored since the last wait, without suspending.
+    ///
+    /// Returns `None` if no new value is ready or if the slot is locked (e.g. by the RX task
+    /// storing a value): call it again on the next poll. Like a wait, a returned value is
+    /// consumed, the next `try_get` or wait only returns a value stored after it.
+    pub fn try_get(&self) -> Option<T> {
+        let value = self.value.try_lock().ok()?;
+        let notified = std::pin::pin!(self.notify.notified());
+        notified.enable().then(|| value.borrow().clone())
+    }
+
+    /// Wait for the next value, at most `timeout`. Returns `None` if the timeout expires.
+    pub async fn wait_timeout(&self, timeout: Duration) -> Option<T> {
+        tokio::select! {
+            _ = self.notify.notified() => Some(self.value.lock().await.borrow().clone()),
+            _ = tokio::time::sleep(timeout) => None,
+        }
+    }
+}
+
+impl<T: Default> Slot<T> {
+    /// Discard any value currently held in the slot.
+    ///
+    /// The slot is reset to its default value and a pending notification is consumed, so the
+    /// next wait only returns a value stored after the flush.
+    pub async fn flush(&self) {
+        self.value.lock().await.replace(T::default());
+
+        // Consume a stored notification, if any, without waiting for a new one.
+        let _ = tokio::time::timeout(Duration::ZERO, self.notify.notified()).await;
+    }
+}
+
+/// The response slot for each UDS request: a `Slot<Response>` fed by the RX task, and the
+/// context used to drop the frames that don't answer the in-flight request.
+///
+/// While a segmented response is received, its Consecutive Frames are queued instead of
+/// stored in the slot: an ECU sending them back-to-back (STmin 0) would otherwise overwrite a
+/// frame before the client takes it.
+pub struct ResponseSlot {
+    slot: Slot<Response>,                               // The latest response
+    consecutive_tx: UnboundedSender<Response>,          // Queue of the received Consecutive Frames
+    consecutive_rx: Mutex<UnboundedReceiver<Response>>, // Reassembly end of the queue
+    reassembling: AtomicBool,                           // Consecutive Frames go to the queue
+    request: AtomicU16,         // SID of the in-flight request, NO_REQUEST if none
+    response_id: AtomicU32,     // CAN ID the ECU responds with, ANY_RESPONSE_ID if not set
+    strict_length: AtomicBool,  // Reject the received frames that aren't padded
+    pending_timeout: AtomicU64, // Timeout after a responsePending in us, NO_PENDING_TIMEOUT if not set
+    not_supported_nrc: AtomicU8, // NRC of the latest response delivered as NotSupported
+}
 
 impl Default for ResponseSlot {
     fn default() -> Self {
@@ -26,55 +217,209 @@ impl ResponseSlot {
     /// This will initialize the slot with a default error (NotSupported) and set up the notification system.
     /// The `timeout_ms` is an optional input in milisecs, the default timeout is 1000ms.
     pub fn new(timeout_ms: Option<u64>) -> Self {
-        Self(
-            Mutex::new(RefCell::new(Response::Error(DiagError::NotSupported))), // Default to NotSupported error.
-            Notify::new(), // Create a Notify object to handle asynchronous notifications.
-            Duration::from_millis(timeout_ms.unwrap_or(1000)), // Use provided timeout or default to 1000ms.
-        )
+        let (consecutive_tx, consecutive_rx) = unbounded_channel();
+        Self {
+            slot: Slot::with_timeout(
+                Response::default(),
+                timeout_ms.map_or(DEFAULT_RESPONSE_TIMEOUT, Duration::from_millis),
+            ),
+            consecutive_tx,
+            consecutive_rx: Mutex::new(consecutive_rx),
+            reassembling: AtomicBool::new(false),
+            request: AtomicU16::new(NO_REQUEST),
+            response_id: AtomicU32::new(ANY_RESPONSE_ID),
+            strict_length: AtomicBool::new(false),
+            pending_timeout: AtomicU64::new(NO_PENDING_TIMEOUT),
+            not_supported_nrc: AtomicU8::new(0),
+        }
+    }
+
+    /// Returns the response timeout, see `Slot::timeout`.
+    pub fn timeout(&self) -> Duration {
+        self.slot.timeout()
     }
 
-    /// Get a response in a blocking manner. This will block forever until a response is available.
+    /// Change the response timeout, e.g. to the P2 timing of the ECU.
+    pub fn set_timeout(&self, timeout: Duration) {
+        self.slot.set_timeout(timeout);
+    }
+
+    /// Store a response and notify the waiting task, without any check, see `Slot::update`.
+    pub async fn update(&self, response: Response) {
+        self.slot.update(response).await;
+    }
+
+    /// Lock the slot, see `Slot::lock`.
+    pub async fn lock(&self) -> MutexGuard<'_, RefCell<Response>> {
+        self.slot.lock().await
+    }
+
+    /// Wait for the next response without timeout, see `Slot::get`.
+    pub async fn get(&self) -> Response {
+        self.slot.get().await
+    }
+
+    /// Take the response if one was stored since the last wait, see `Slot::try_get`.
+    pub fn try_get(&self) -> Option<Response> {
+        self.slot.try_get()
+    }
+
+    /// Wait for the next response, at most `timeout`, see `Slot::wait_timeout`.
+    pub async fn wait_timeout(&self, timeout: Duration) -> Option<Response> {
+        self.slot.wait_timeout(timeout).await
+    }
+
+    /// Discard the response currently held, see `Slot::flush`, and the queued Consecutive
+    /// Frames of an interrupted reassembly.
+    pub async fn flush(&self) {
+        self.slot.flush().await;
+        self.end_reassembly();
+        self.drain_consecutive().await;
+    }
+
+    /// Internal function: queue the Consecutive Frames received from now on, until
+    /// `end_reassembly`. Frames queued by an earlier reassembly are discarded.
+    pub(crate) async fn start_reassembly(&self) {
+        self.drain_consecutive().await;
+        self.reassembling.store(true, Ordering::Relaxed);
+    }
+
+    /// Internal function: store the Consecutive Frames in the slot again, see `start_reassembly`.
+    pub(crate) fn end_reassembly(&self) {
+        self.reassembling.store(false, Ordering::Relaxed);
+    }
+
+    /// Internal function: discard the queued Consecutive Frames.
+    async fn drain_consecutive(&self) {
+        let mut queue = self.consecutive_rx.lock().await;
+        while queue.try_recv().is_ok() {}
+    }
+
+    /// Internal function: wait for the next Consecutive Frame of the reassembly, at most
+    /// `timeout`. A response stored in the slot meanwhile (e.g. an error or a frame of another
+    /// type) is returned as well, after the queued frames.
+    pub(crate) async fn wait_consecutive(&self, timeout: Duration) -> Response {
+        let mut queue = self.consecutive_rx.lock().await;
+        if let Ok(resp) = queue.try_recv() {
+            return resp;
+        }
+        tokio::select! {
+            biased;
+            Some(resp) = queue.recv() => resp,
+            resp = self.wait_for_response_timeout(timeout) => resp,
+        }
+    }
+
+    /// Set the SID of the in-flight request, `None` to accept every frame.
     ///
-    /// It waits for the notification to be triggered and then locks the Mutex to retrieve the response.
-    pub async fn get(&self) -> Result<Response, DiagError> {
-        // Wait for the notification signal.
-        self.1.notified().await;
+    /// While a request is in flight, `update_response` drops the Single/First Frames and negative
+    /// responses that don't answer it (e.g. an unsolicited periodic frame) and logs a warning,
+    /// instead of resolving the wait with the wrong frame.
+    pub fn set_request(&self, sid: Option<u8>) {
+        let context = sid.map_or(NO_REQUEST, u16::from);
+        self.request.store(context, Ordering::Relaxed);
+    }
+
+    /// Returns the SID of the in-flight request, if any.
+    pub fn request(&self) -> Option<u8> {
+        u8::try_from(self.request.load(Ordering::Relaxed)).ok()
+    }
+
+    /// Returns the raw NRC (0x11 or 0x12) of the latest negative response delivered as
+    /// `DiagError::NotSupported`, 0 if none was received yet.
+    pub(crate) fn not_supported_nrc(&self) -> u8 {
+        self.not_supported_nrc.load(Ordering::Relaxed)
+    }
+
+    /// Set the CAN ID the ECU responds with, `None` to accept frames of any CAN ID.
+    ///
+    /// `UdsClient` sets it when created, see `UdsClient::with_response_id`. Only the frames
+    /// given to `update_response_from` are checked against it.
+    pub fn set_response_id(&self, id: Option<u32>) {
+        self.response_id
+            .store(id.unwrap_or(ANY_RESPONSE_ID), Ordering::Relaxed);
+    }
 
-        // Once notified, lock the Mutex and retrieve the response data.
-        let res = self.0.try_lock().unwrap().to_owned().into_inner();
+    /// Returns the CAN ID the ECU responds with, if set.
+    pub fn response_id(&self) -> Option<u32> {
+        Some(self.response_id.load(Ordering::Relaxed)).filter(|id| *id != ANY_RESPONSE_ID)
+    }
+
+    /// Reject the received frames that aren't padded: frames shorter than 8 bytes or without a
+    /// valid CAN FD data length resolve the wait with `FrameError::InvalidCanLength`.
+    ///
+    /// Set by `UdsClient::set_padding` for `PaddingPolicy::StrictPad`.
+    pub fn set_strict_length(&self, strict: bool) {
+        self.strict_length.store(strict, Ordering::Relaxed);
+    }
+
+    /// Returns whether the received frames must be padded.
+    pub fn strict_length(&self) -> bool {
+        self.strict_length.load(Ordering::Relaxed)
+    }
+
+    /// Set the timeout following a responsePending (NRC 0x78), e.g. to the P2* timing of the
+    /// ECU. `None` keeps waiting with the response timeout.
+    pub fn set_pending_timeout(&self, timeout: Option<Duration>) {
+        let timeout = timeout.map_or(NO_PENDING_TIMEOUT, |timeout| timeout.as_micros() as u64);
+        self.pending_timeout.store(timeout, Ordering::Relaxed);
+    }
 
-        // Return the response wrapped in Ok.
-        Ok(res)
+    /// Returns the timeout following a responsePending (NRC 0x78), if set.
+    pub fn pending_timeout(&self) -> Option<Duration> {
+        Some(self.pending_timeout.load(Ordering::Relaxed))
+            .filter(|timeout| *timeout != NO_PENDING_TIMEOUT)
+            .map(Duration::from_micros)
+    }
+
+    /// Check whether `frame` can answer the in-flight request.
+    /// Consecutive and Flow Control frames carry no SID and are always accepted.
+    fn answers_request(&self, frame: &UdsFrame) -> bool {
+        let Some(sid) = self.request() else {
+            return true;
+        };
+        match frame {
+            UdsFrame::Single(frame) => frame.sid == sid | 0x40,
+            UdsFrame::First(frame) => frame.sid == sid | 0x40,
+            UdsFrame::Negative(nr) => nr.rsid == sid,
+            UdsFrame::Consecutive(_) | UdsFrame::FlowControl(_) => true,
+        }
     }
 
     /// Get a response with a timeout. If no response is received within the timeout period, an error is returned.
     ///
-    /// This function uses `tokio::select!` to wait for either the notification or the timeout.
     /// If the timeout expires, it returns a `Timeout` error.
     pub async fn wait_for_response(&self) -> Response {
+        self.wait_for_response_timeout(self.timeout()).await
+    }
+
+    /// Same as `wait_for_response`, with a `timeout` other than the slot's response timeout.
+    ///
+    /// Used for the ISO-TP N_Bs/N_Cr timers, which are shorter than the response timeout.
+    pub async fn wait_for_response_timeout(&self, timeout: Duration) -> Response {
+        self.wait_for_response_counted(timeout).await.0
+    }
+
+    /// Internal function: same as `wait_for_response_timeout`, also returning the number of
+    /// responsePending (NRC 0x78) received while waiting. After a responsePending, the wait
+    /// uses the pending timeout if set (see `set_pending_timeout`).
+    pub(crate) async fn wait_for_response_counted(&self, timeout: Duration) -> (Response, u32) {
         let mut pending_response = None;
+        let mut pending_count = 0;
+        let mut timeout = timeout;
         loop {
-            tokio::select! {
-                _ = self.1.notified() => {
-                    let data = self.0.lock().await;
-                    match &*data.borrow() {
-                        // handle the case where the response is a pending response
-                        // and we need to wait for the next response or timeout
-                        Response::Error(DiagError::ECUError { code, rsid: _, def: _ })
-                            if *code == UdsError::RequestCorrectlyReceivedResponsePending =>
-                        {
-                            pending_response = Some(data.borrow().clone());
-                            continue;
-                        }
-                        resp => return resp.clone(),
-                    }
+            match self.wait_timeout(timeout).await {
+                // handle the case where the response is a pending response
+                // and we need to wait for the next response or timeout
+                Some(resp) if resp.is_pending() => {
+                    pending_response = Some(resp);
+                    pending_count += 1;
+                    timeout = self.pending_timeout().unwrap_or(timeout);
                 }
-                _ = tokio::time::sleep(self.2) => {
-                    if let Some(pending_response) = pending_response {
-                        return pending_response
-                    } else {
-                        return Response::Error(DiagError::Timeout)
-                    }
+                Some(resp) => return (resp, pending_count),
+                None => {
+                    let resp = pending_response.unwrap_or(Response::Error(DiagError::Timeout));
+                    return (resp, pending_count);
                 }
             }
         }
@@ -85,17 +430,95 @@ impl ResponseSlot {
     /// This function is used to update the response after receiving new data.
     /// It creates a UdsFrame from the provided `new_data` and replaces the current response data.
     /// After updating, it notifies the waiting task that the response is ready.
+    /// The source CAN ID of the response is unknown, see `update_response_from`.
     pub async fn update_response(&self, new_data: Vec<u8>) {
+        self.update_response_with_id(None, new_data).await;
+    }
+
+    /// Internal function: `update_response` of a frame received with the CAN ID `id`, if known.
+    async fn update_response_with_id(&self, id: Option<u32>, new_data: Vec<u8>) {
         // Convert the new data into a UdsFrame, handling any errors.
-        let resp = match UdsFrame::from_vec(new_data) {
-            Ok(frame) => Response::Ok(frame),
+        // A negative response from the ECU is delivered as `DiagError::ECUError`, except
+        // serviceNotSupported and subFunctionNotSupported delivered as `DiagError::NotSupported`.
+        let len = new_data.len();
+        if self.strict_length() && len != 8 && !CAN_FD_DATA_LENGTHS.contains(&len) {
+            log_warn!(
+                "UDS: rejected a frame of {} bytes, padding is required",
+                len
+            );
+            self.update(Response::Error(DiagError::FrameError {
+                error: FrameError::InvalidCanLength,
+            }))
+            .await;
+            return;
+        }
+        let frame = UdsFrame::from_vec(new_data);
+        if let Ok(frame) = &frame
+            && !self.answers_request(frame)
+        {
+            log_warn!(
+                "UDS: dropped a frame not answering the request 0x{:02X?}: {}",
+                self.request(),
+                frame
+            );
+            return;
+        }
+
+        let resp = match frame {
+            Ok(UdsFrame::Negative(nr))
+                if nr.code() == UdsError::ServiceNotSupported
+                    || nr.code() == UdsError::SubFunctionNotSupported =>
+            {
+                log_debug!(
+                    "UDS: request 0x{:02X} not supported: {:?}",
+                    nr.rsid,
+                    nr.code()
+                );
+                self.not_supported_nrc
+                    .store(nr.code().into(), Ordering::Relaxed);
+                Response::Error(DiagError::NotSupported)
+            }
+            Ok(UdsFrame::Negative(nr)) => Response::Error(DiagError::from_negative_response(&nr)),
+            Ok(frame @ UdsFrame::Consecutive(_)) if self.reassembling.load(Ordering::Relaxed) => {
+                // The receiver lives as long as the slot, the send can't fail
+                let _ = self.consecutive_tx.send(Response::Ok(frame, id));
+                return;
+            }
+            Ok(frame) => Response::Ok(frame, id),
             Err(e) => Response::Error(e),
         };
 
-        // Lock the Mutex and update the response with the new data.
-        self.0.lock().await.replace(resp); // Lock and modify data
+        // Store the response and notify any waiting task.
+        self.update(resp).await;
+    }
+
+    /// Report a CAN bus error seen by the RX task (see `UdsSocketRx::error_monitor`).
+    ///
+    /// While a request is in flight, a transmit timeout or a bus-off resolves the wait with
+    /// `DiagError::TxTimeout` or `DiagError::BusOff`: the request never reached the ECU, the
+    /// wait would otherwise end with the RX `DiagError::Timeout`. Other errors are only logged.
+    pub async fn update_bus_error(&self, error: CanBusError) {
+        match error {
+            CanBusError::TransmitTimeout | CanBusError::BusOff if self.request().is_some() => {
+                self.update(Response::Error(error.into())).await;
+            }
+            _ => log_debug!("UDS: bus error: {}", error),
+        }
+    }
 
-        // Notify any waiting task that a response is available.
-        self.1.notify_one(); // Notify the waiting thread
+    /// Same as `update_response`, for a frame received with the CAN ID `id`.
+    ///
+    /// The frame is dropped if `id` isn't the response ID of the slot (see `set_response_id`),
+    /// e.g. when the RX socket of a shared bus receives the responses of several ECUs.
+    /// The response frame carries `id`, see `Response::source_id`.
+    pub async fn update_response_from(&self, id: u32, new_data: Vec<u8>) {
+        if self
+            .response_id()
+            .is_some_and(|response_id| response_id != id)
+        {
+            log_debug!("UDS: dropped a frame from the CAN ID 0x{:X}", id);
+            return;
+        }
+        self.update_response_with_id(Some(id), new_data).await;
     }
 }
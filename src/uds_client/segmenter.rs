@@ -0,0 +1,91 @@
+//! ISO 15765-2 segmentation of a payload into the frames that carry it.
+//!
+//! The encoding counterpart of the reassembly done by the client: `IsoTpSegmenter` yields the
+//! frames `UdsClient::send_multi_frame` transmits, so the framing can be inspected (or tested)
+//! before sending.
+
+use super::{
+    DiagError,
+    client::{CAN_FD_DATA_LENGTHS, DEFAULT_TX_DL},
+    frame::{UdsConsecutiveFrame, UdsFirstFrame, UdsFrame, UdsSingleFrame},
+};
+
+/// Iterator over the frames of a payload (SID + parameters): a Single Frame when the payload
+/// fits, otherwise a First Frame followed by the Consecutive Frames.
+///
+/// The frames are sized for the data length `max_dlc` (TX_DL): 8 on CAN, 12 to 64 on CAN FD.
+/// The Flow Control frames of the receiver aren't part of the sequence.
+#[derive(Debug, Clone)]
+pub struct IsoTpSegmenter<'a> {
+    payload: &'a [u8], // The payload to segment
+    max_dlc: usize,    // The data length of the frames (TX_DL)
+    offset: usize,     // Length of the payload already yielded
+    seq_num: u8,       // Sequence number of the last yielded frame
+}
+
+impl<'a> IsoTpSegmenter<'a> {
+    /// Create a segmenter of `payload` for the data length `max_dlc`.
+    ///
+    /// An empty payload, a payload over 4 GiB or a `max_dlc` that isn't a CAN or CAN FD data
+    /// length returns `DiagError::ParameterInvalid`.
+    pub fn new(payload: &'a [u8], max_dlc: usize) -> Result<Self, DiagError> {
+        if payload.is_empty()
+            || u32::try_from(payload.len()).is_err()
+            || (max_dlc != DEFAULT_TX_DL && !CAN_FD_DATA_LENGTHS.contains(&max_dlc))
+        {
+            return Err(DiagError::ParameterInvalid);
+        }
+        Ok(Self {
+            payload,
+            max_dlc,
+            offset: 0,
+            seq_num: 0,
+        })
+    }
+
+    /// Returns whether the payload is sent in a Single Frame: up to 7 bytes, or TX_DL - 2
+    /// bytes with the escaped size of CAN FD.
+    pub fn is_single_frame(&self) -> bool {
+        self.payload.len() <= 7 || self.payload.len() <= self.max_dlc - 2
+    }
+}
+
+impl Iterator for IsoTpSegmenter<'_> {
+    type Item = UdsFrame;
+
+    fn next(&mut self) -> Option<UdsFrame> {
+        if self.offset >= self.payload.len() {
+            return None;
+        }
+
+        let (frame, end) = if self.offset > 0 {
+            // Consecutive Frame: TX_DL - 1 bytes after the PCI byte, the sequence number wraps
+            let end = self.payload.len().min(self.offset + self.max_dlc - 1);
+            self.seq_num = (self.seq_num + 1) & 0x0F;
+            let frame = UdsConsecutiveFrame {
+                seq_num: self.seq_num,
+                payload: self.payload[self.offset..end].to_vec(),
+            };
+            (UdsFrame::Consecutive(frame), end)
+        } else if self.is_single_frame() {
+            let frame = UdsSingleFrame {
+                size: self.payload.len() as u8,
+                sid: self.payload[0],
+                payload: self.payload[1..].to_vec(),
+            };
+            (UdsFrame::Single(frame), self.payload.len())
+        } else {
+            // First Frame: a 2 bytes PCI, 6 bytes with the 32 bits size escape over 4095 bytes
+            let size = self.payload.len() as u32;
+            let end = self.max_dlc - if size > 0xFFF { 6 } else { 2 };
+            let frame = UdsFirstFrame {
+                size,
+                sid: self.payload[0],
+                payload: self.payload[1..end].to_vec(),
+            };
+            (UdsFrame::First(frame), end)
+        };
+        self.offset = end;
+        Some(frame)
+    }
+}
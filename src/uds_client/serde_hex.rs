@@ -0,0 +1,35 @@
+//! Serde helpers that encode byte vectors as readable hex strings (e.g. `"62 F1 90"`).
+//!
+//! Used with `#[serde(with = "serde_hex")]` on the payload fields of the UDS frames so captured
+//! exchanges and golden test vectors stay easy to read and edit by hand.
+
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// Serialize bytes as space-separated uppercase hex.
+pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    serializer.serialize_str(&hex)
+}
+
+/// Deserialize bytes from a hex string, with or without separating whitespace.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex: String = String::deserialize(deserializer)?
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return Err(D::Error::custom("expected an even number of hex digits"));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|idx| {
+            u8::from_str_radix(&hex[idx..idx + 2], 16)
+                .map_err(|_| D::Error::custom(format!("invalid hex byte '{}'", &hex[idx..idx + 2])))
+        })
+        .collect()
+}
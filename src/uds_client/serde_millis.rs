@@ -0,0 +1,40 @@
+//! Serde helpers that encode durations as a number of milliseconds (e.g. `50`).
+//!
+//! Used with `#[serde(with = "serde_millis")]` on the timing fields of `EcuProfile` so profile
+//! files hold the values as written in the ECU specifications.
+
+use serde::{Deserialize, Deserializer, Serializer};
+use std::time::Duration;
+
+/// Serialize a duration as whole milliseconds.
+pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+/// Deserialize a duration from milliseconds.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    u64::deserialize(deserializer).map(Duration::from_millis)
+}
+
+/// Same as the parent module, for an optional duration (`null` when `None`).
+pub mod option {
+    use super::*;
+
+    /// Serialize an optional duration as whole milliseconds.
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&(duration.as_millis() as u64)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    /// Deserialize an optional duration from milliseconds.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<u64>::deserialize(deserializer).map(|millis| millis.map(Duration::from_millis))
+    }
+}
@@ -0,0 +1,241 @@
+//!  Provides a single programmatic entry point running any implemented service by its
+//!  `UdsCommand`, with the raw request parameters.
+//!
+
+use std::time::Duration;
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient, frame::hex_dump},
+};
+use automotive_diag::uds::UdsCommand;
+use tokio::sync::mpsc::unbounded_channel;
+
+use super::{
+    CommunicationControlType, DataFormat, Dtc, EcuResetType, RealTimeType, RoutineControlType,
+    decode_addr_and_length,
+    dtc::{REPORT_DTC_BY_STATUS_MASK, REPORT_NUMBER_OF_DTC_BY_STATUS_MASK},
+};
+
+/// TesterPresent sub-function: zeroSubFunction
+const ZERO_SUB_FUNCTION: u8 = 0x00;
+
+/// The decoded response of `UdsClient::run_command`, depending on the service run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandOutput {
+    Done,                      // Positive response without data to return
+    PowerDownTime(Option<u8>), // ECUReset: powerDownTime of enableRapidPowerShutDown
+    RoundTrip(Duration),       // TesterPresent: round-trip time of the request
+    Data(Vec<u8>),             // Record of the response: DID data, seed, routine status...
+    DtcCount(u16),             // ReadDTCInformation: reportNumberOfDTCByStatusMask
+    Dtcs(Vec<Dtc>),            // ReadDTCInformation: reportDTCByStatusMask
+    MaxBlockLength(usize),     // RequestDownload, RequestUpload: maxNumberOfBlockLength
+}
+
+/// A readable rendering of the output, e.g. `power down time: 10s` or one `0x123456
+/// status=0x08` line per DTC. Records are rendered as hex bytes, see `UdsFrame::hex_dump`.
+impl std::fmt::Display for CommandOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandOutput::Done | CommandOutput::PowerDownTime(None) => write!(f, "OK"),
+            CommandOutput::PowerDownTime(Some(time)) => write!(f, "power down time: {time}s"),
+            CommandOutput::RoundTrip(rtt) => write!(f, "round trip: {rtt:?}"),
+            CommandOutput::Data(data) => write!(f, "{}", hex_dump(data)),
+            CommandOutput::DtcCount(count) => write!(f, "{count} DTCs"),
+            CommandOutput::Dtcs(dtcs) if dtcs.is_empty() => write!(f, "no DTC"),
+            CommandOutput::Dtcs(dtcs) => {
+                let lines = dtcs
+                    .iter()
+                    .map(|dtc| format!("0x{:06X} status=0x{:02X}", dtc.code, dtc.status));
+                write!(f, "{}", lines.collect::<Vec<_>>().join("\n"))
+            }
+            CommandOutput::MaxBlockLength(len) => write!(f, "max block length: {len}"),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: `cmd`
+    /// Description:
+    ///     The function will run the service `cmd` with the request parameters `args` (e.g. the
+    ///     sub-function and its data) through the method of the client implementing it, e.g.
+    ///     `uds_reset` for ECUReset. The parameters are decoded as in the request: the
+    ///     sub-function (without the suppressPosRspMsgIndicationBit, see
+    ///     `set_suppress_positive_response`), then its data. Memory services take an
+    ///     addressAndLengthFormatIdentifier whose address and size fit in 4 bytes. Invalid
+    ///     parameters return `DiagError::ParameterInvalid`. The services without such a method
+    ///     return `DiagError::NotImplemented` with the name of the command, as do the
+    ///     ReadDTCInformation sub-functions other than 0x01 and 0x02, and the
+    ///     ReadDataByPeriodicIdentifier requests other than stop (0x04): their periodic
+    ///     messages need `read_periodic_data`.
+    /// Returns:
+    ///     The response decoded by the method, see `CommandOutput`. A negative response is
+    ///     returned as `DiagError::ECUError`.
+    pub async fn run_command(
+        &mut self,
+        cmd: UdsCommand,
+        args: &[u8],
+    ) -> Result<CommandOutput, DiagError> {
+        log_debug!("UDS: run {:?} {:02X?}", cmd, args);
+        match cmd {
+            UdsCommand::DiagnosticSessionControl => {
+                let (session, []) = sub_function(args)? else {
+                    return Err(DiagError::ParameterInvalid);
+                };
+                self.diagnostic_session_control(session.try_into()?).await?;
+                Ok(CommandOutput::Done)
+            }
+            UdsCommand::ECUReset => {
+                let (kind, []) = sub_function(args)? else {
+                    return Err(DiagError::ParameterInvalid);
+                };
+                let kind = EcuResetType::try_from(kind)?;
+                Ok(CommandOutput::PowerDownTime(self.uds_reset(kind).await?))
+            }
+            UdsCommand::SecurityAccess => {
+                // An odd level requests the seed, the next even level sends the key
+                match sub_function(args)? {
+                    (level, []) if level & 0x01 == 1 => {
+                        let seed = self.security_access_request_seed(level).await?;
+                        Ok(CommandOutput::Data(seed))
+                    }
+                    (level, key) if level & 0x01 == 0 && level != 0 => {
+                        self.security_access_send_key(level - 1, key).await?;
+                        Ok(CommandOutput::Done)
+                    }
+                    _ => Err(DiagError::ParameterInvalid),
+                }
+            }
+            UdsCommand::CommunicationControl => {
+                let (control, [communication_type, node_id @ ..]) = sub_function(args)? else {
+                    return Err(DiagError::ParameterInvalid);
+                };
+                let control = CommunicationControlType::try_from(control)
+                    .map_err(|_| DiagError::ParameterInvalid)?;
+                let node_id = match node_id {
+                    [] => None,
+                    [high, low] => Some(u16::from_be_bytes([*high, *low])),
+                    _ => return Err(DiagError::ParameterInvalid),
+                };
+                self.communication_control_node(control, *communication_type, node_id)
+                    .await?;
+                Ok(CommandOutput::Done)
+            }
+            UdsCommand::TesterPresent => match sub_function(args)? {
+                (ZERO_SUB_FUNCTION, []) => Ok(CommandOutput::RoundTrip(self.ping().await?)),
+                _ => Err(DiagError::ParameterInvalid),
+            },
+            UdsCommand::ControlDTCSetting => {
+                let (setting, []) = sub_function(args)? else {
+                    return Err(DiagError::ParameterInvalid);
+                };
+                self.control_dtc_setting(setting.try_into()?).await?;
+                Ok(CommandOutput::Done)
+            }
+            UdsCommand::ReadDTCInformation => match sub_function(args)? {
+                (REPORT_NUMBER_OF_DTC_BY_STATUS_MASK, [mask]) => {
+                    Ok(CommandOutput::DtcCount(self.read_dtc_count(*mask).await?))
+                }
+                (REPORT_DTC_BY_STATUS_MASK, [mask]) => {
+                    let (dtcs, mut received) = unbounded_channel();
+                    self.read_dtc_stream(*mask, dtcs).await?;
+                    let dtcs = std::iter::from_fn(|| received.try_recv().ok()).collect();
+                    Ok(CommandOutput::Dtcs(dtcs))
+                }
+                (REPORT_NUMBER_OF_DTC_BY_STATUS_MASK | REPORT_DTC_BY_STATUS_MASK, _) => {
+                    Err(DiagError::ParameterInvalid)
+                }
+                (sub_function, _) => Err(DiagError::NotImplemented(format!(
+                    "{cmd:?} 0x{sub_function:02X}"
+                ))),
+            },
+            UdsCommand::RoutineControl => {
+                let (kind, [high, low, options @ ..]) = sub_function(args)? else {
+                    return Err(DiagError::ParameterInvalid);
+                };
+                let kind = RoutineControlType::try_from(kind)?;
+                let routine_id = u16::from_be_bytes([*high, *low]);
+                let status = self.routine_control(kind, routine_id, options).await?;
+                Ok(CommandOutput::Data(status))
+            }
+            UdsCommand::ReadDataByIdentifier => {
+                let &[high, low] = args else {
+                    return Err(DiagError::ParameterInvalid);
+                };
+                let did = u16::from_be_bytes([high, low]);
+                Ok(CommandOutput::Data(
+                    self.read_data_by_identifier(did).await?,
+                ))
+            }
+            UdsCommand::WriteDataByIdentifier => {
+                let [high, low, data @ ..] = args else {
+                    return Err(DiagError::ParameterInvalid);
+                };
+                let did = u16::from_be_bytes([*high, *low]);
+                self.write_data_by_identifier(did, data).await?;
+                Ok(CommandOutput::Done)
+            }
+            UdsCommand::ReadMemoryByAddress => {
+                let (addr, size) = memory_range(args)?;
+                let data = self.read_memory_by_address(addr, size).await?;
+                Ok(CommandOutput::Data(data))
+            }
+            UdsCommand::RequestDownload | UdsCommand::RequestUpload => {
+                let [format, range @ ..] = args else {
+                    return Err(DiagError::ParameterInvalid);
+                };
+                let (addr, size) = memory_range(range)?;
+                let format = DataFormat::from(*format);
+                let max_block_length = if cmd == UdsCommand::RequestDownload {
+                    self.request_download(addr, size, format).await?
+                } else {
+                    self.request_upload(addr, size, format).await?
+                };
+                Ok(CommandOutput::MaxBlockLength(max_block_length))
+            }
+            UdsCommand::TransferData => {
+                let [block_counter, data @ ..] = args else {
+                    return Err(DiagError::ParameterInvalid);
+                };
+                let record = self.transfer_data(*block_counter, data).await?;
+                Ok(CommandOutput::Data(record))
+            }
+            UdsCommand::RequestTransferExit => {
+                if !args.is_empty() {
+                    return Err(DiagError::ParameterInvalid);
+                }
+                Ok(CommandOutput::Data(self.request_transfer_exit().await?))
+            }
+            UdsCommand::ReadDataByPeriodicIdentifier if args == [u8::from(RealTimeType::Stop)] => {
+                self.stop_all_periodic().await?;
+                Ok(CommandOutput::Done)
+            }
+            _ => Err(DiagError::NotImplemented(format!("{cmd:?}"))),
+        }
+    }
+}
+
+/// Split the request parameters `args` into the sub-function and its data.
+/// The suppressPosRspMsgIndicationBit is set with `UdsClient::set_suppress_positive_response`:
+/// a sub-function with it, or a missing one, returns `DiagError::ParameterInvalid`.
+fn sub_function(args: &[u8]) -> Result<(u8, &[u8]), DiagError> {
+    match args.split_first() {
+        Some((sub_function, data)) if sub_function & 0x80 == 0 => Ok((*sub_function, data)),
+        _ => Err(DiagError::ParameterInvalid),
+    }
+}
+
+/// Decode the addressAndLengthFormatIdentifier, memoryAddress and memorySize of `args`, which
+/// must hold them alone, the address and the size fitting in 4 bytes.
+fn memory_range(args: &[u8]) -> Result<(u32, u32), DiagError> {
+    let (addr, size, len) =
+        decode_addr_and_length(args).map_err(|_| DiagError::ParameterInvalid)?;
+    if len != args.len() {
+        return Err(DiagError::ParameterInvalid);
+    }
+    let addr = u32::try_from(addr).map_err(|_| DiagError::ParameterInvalid)?;
+    let size = u32::try_from(size).map_err(|_| DiagError::ParameterInvalid)?;
+    Ok((addr, size))
+}
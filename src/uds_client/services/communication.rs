@@ -0,0 +1,123 @@
+//!  Provides methods to enable or disable the communication of the ECU: CommunicationControl (0x28).
+//!
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient, client::positive_response_params},
+};
+use automotive_diag::uds::UdsCommand;
+
+/// communicationType: normal communication messages, on all the networks.
+pub const NORMAL_COMMUNICATION_MESSAGES: u8 = 0x01;
+/// communicationType: network management communication messages, on all the networks.
+pub const NETWORK_MANAGEMENT_COMMUNICATION_MESSAGES: u8 = 0x02;
+
+/// Communication Control sub-function (ISO 14229-1)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommunicationControlType {
+    EnableRxAndTx = 0x00,        // Enable the reception and the transmission
+    EnableRxAndDisableTx = 0x01, // Enable the reception, disable the transmission
+    DisableRxAndEnableTx = 0x02, // Disable the reception, enable the transmission
+    DisableRxAndTx = 0x03,       // Disable the reception and the transmission
+    EnableRxAndDisableTxWithEnhancedAddressInformation = 0x04, // Same as 0x01, for one node
+    EnableRxAndTxWithEnhancedAddressInformation = 0x05, // Same as 0x00, for one node
+}
+
+impl CommunicationControlType {
+    /// Returns whether the control type addresses a single node: the request then carries its
+    /// nodeIdentificationNumber.
+    pub fn has_node_id(self) -> bool {
+        matches!(
+            self,
+            CommunicationControlType::EnableRxAndDisableTxWithEnhancedAddressInformation
+                | CommunicationControlType::EnableRxAndTxWithEnhancedAddressInformation
+        )
+    }
+}
+
+impl From<CommunicationControlType> for u8 {
+    fn from(control: CommunicationControlType) -> Self {
+        control as u8
+    }
+}
+
+impl TryFrom<u8> for CommunicationControlType {
+    type Error = DiagError;
+    fn try_from(control: u8) -> Result<Self, Self::Error> {
+        match control {
+            0x00 => Ok(CommunicationControlType::EnableRxAndTx),
+            0x01 => Ok(CommunicationControlType::EnableRxAndDisableTx),
+            0x02 => Ok(CommunicationControlType::DisableRxAndEnableTx),
+            0x03 => Ok(CommunicationControlType::DisableRxAndTx),
+            0x04 => {
+                Ok(CommunicationControlType::EnableRxAndDisableTxWithEnhancedAddressInformation)
+            }
+            0x05 => Ok(CommunicationControlType::EnableRxAndTxWithEnhancedAddressInformation),
+            _ => Err(DiagError::InvalidResponseData),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x28 - Communication Control
+    ///     Sub-ID: `control`
+    /// Description:
+    ///     The function will enable or disable the reception and the transmission of the
+    ///     messages of `communication_type` (e.g. `NORMAL_COMMUNICATION_MESSAGES`).
+    ///     Doesn't wait for a response when positive responses are suppressed. The control
+    ///     types addressing a single node need `communication_control_node`.
+    pub async fn communication_control(
+        &mut self,
+        control: CommunicationControlType,
+        communication_type: u8,
+    ) -> Result<(), DiagError> {
+        self.communication_control_node(control, communication_type, None)
+            .await
+    }
+
+    /// Service ID: 0x28 - Communication Control
+    ///     Sub-ID: `control`
+    /// Description:
+    ///     Same as `communication_control`, with the nodeIdentificationNumber `node_id` of the
+    ///     node whose communication is controlled (e.g. a sub-bus behind a gateway). The node
+    ///     ID is required by the control types 0x04 and 0x05 and refused by the others:
+    ///     `DiagError::ParameterInvalid` is returned otherwise.
+    /// Returns:
+    ///     A response echoing another control type returns `DiagError::InvalidResponseData`.
+    pub async fn communication_control_node(
+        &mut self,
+        control: CommunicationControlType,
+        communication_type: u8,
+        node_id: Option<u16>,
+    ) -> Result<(), DiagError> {
+        if control.has_node_id() != node_id.is_some() {
+            return Err(DiagError::ParameterInvalid);
+        }
+        log_debug!(
+            "UDS: communication control {:?} of 0x{:02X}, node {:?}",
+            control,
+            communication_type,
+            node_id
+        );
+        let mut args = vec![communication_type];
+        if let Some(node_id) = node_id {
+            args.extend_from_slice(&node_id.to_be_bytes());
+        }
+        let Some(response) = self
+            .send_sub_function_command(UdsCommand::CommunicationControl, control.into(), &args)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let params = positive_response_params(&response, UdsCommand::CommunicationControl)?;
+        let echoed = params.first().ok_or(DiagError::InvalidResponseLength)?;
+        if CommunicationControlType::try_from(*echoed & 0x7F)? != control {
+            return Err(DiagError::InvalidResponseData);
+        }
+        Ok(())
+    }
+}
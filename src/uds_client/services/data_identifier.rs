@@ -0,0 +1,360 @@
+//!  Provides methods to read data records by their identifier (DID), one or several at a time,
+//!  or to write them, and the `DidRegistry` holding the data length of each DID.
+//!
+
+use std::collections::HashMap;
+
+use crate::{
+    logging::log_warn,
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient, client::positive_response_data},
+};
+use automotive_diag::uds::UdsCommand;
+
+/// Identification DID: Vehicle Identification Number (17 ASCII characters).
+pub const DID_VIN: u16 = 0xF190;
+/// Identification DID: active diagnostic session (1 byte).
+pub const DID_ACTIVE_DIAGNOSTIC_SESSION: u16 = 0xF186;
+/// Identification DID: vehicle manufacturer ECU hardware number.
+pub const DID_ECU_HW_VERSION: u16 = 0xF191;
+/// Identification DID: system supplier ECU hardware number.
+pub const DID_SUPPLIER_ECU_HW_NUMBER: u16 = 0xF192;
+/// Identification DID: system supplier ECU software version number.
+pub const DID_ECU_SW_VERSION: u16 = 0xF195;
+
+/// Registry of the data record length of each DID.
+///
+/// A batched ReadDataByIdentifier response is a plain concatenation of `DID || data` records,
+/// so the length of every record must be known to split it unambiguously.
+/// `DidRegistry::new()` starts with the well-known fixed-length identifiers (e.g. 0xF190 VIN = 17 bytes).
+#[derive(Debug, Clone)]
+pub struct DidRegistry {
+    lengths: HashMap<u16, usize>,
+}
+
+impl Default for DidRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DidRegistry {
+    /// Create a registry with the well-known fixed-length identifiers.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry
+            .register(DID_VIN, 17)
+            .register(DID_ACTIVE_DIAGNOSTIC_SESSION, 1);
+        registry
+    }
+
+    /// Create a registry without any identifier.
+    pub fn empty() -> Self {
+        Self {
+            lengths: HashMap::new(),
+        }
+    }
+
+    /// Register (or replace) the data length of `did`.
+    pub fn register(&mut self, did: u16, len: usize) -> &mut Self {
+        self.lengths.insert(did, len);
+        self
+    }
+
+    /// Remove `did` from the registry.
+    pub fn unregister(&mut self, did: u16) -> &mut Self {
+        self.lengths.remove(&did);
+        self
+    }
+
+    /// Returns the registered data length of `did`, if any.
+    pub fn length(&self, did: u16) -> Option<usize> {
+        self.lengths.get(&did).copied()
+    }
+
+    /// Split a concatenation of `DID || data` records, the DIDs following the order of `dids`.
+    ///
+    /// DIDs missing from the data are skipped (ECUs omit unsupported identifiers). Only the record
+    /// of the last requested DID may have no registered length, it takes the rest of the data:
+    /// the end of any other record would be ambiguous, `DiagError::InvalidResponseLength` is
+    /// returned.
+    pub fn split_records(
+        &self,
+        mut data: &[u8],
+        dids: &[u16],
+    ) -> Result<Vec<(u16, Vec<u8>)>, DiagError> {
+        let mut records = Vec::new();
+        let mut expected = dids.iter();
+        while !data.is_empty() {
+            let (did, rest) = data
+                .split_first_chunk::<2>()
+                .ok_or(DiagError::InvalidResponseLength)?;
+            let did = u16::from_be_bytes(*did);
+
+            // Skip the requested DIDs the ECU didn't answer, the order is kept
+            let want = expected.clone().next().copied().unwrap_or_default();
+            if !expected.any(|requested| *requested == did) {
+                return Err(DiagError::MismatchedIdentResponse {
+                    want,
+                    received: did,
+                });
+            }
+
+            let len = match self.length(did) {
+                Some(len) => len,
+                None if expected.as_slice().is_empty() => rest.len(),
+                None => {
+                    log_warn!("UDS: the length of the DID 0x{:04X} isn't registered", did);
+                    return Err(DiagError::InvalidResponseLength);
+                }
+            };
+            let record = rest.get(..len).ok_or(DiagError::InvalidResponseLength)?;
+            records.push((did, record.to_vec()));
+            data = &rest[len..];
+        }
+        Ok(records)
+    }
+}
+
+/// Conversion of a DID data record into a typed value, see `UdsClient::read_did_as`.
+///
+/// Implemented for the integer types (big-endian, the record must have the exact size of the
+/// type), fixed-size byte arrays and `Vec<u8>`. Implement it for a user type to parse a
+/// structured record.
+pub trait FromUdsBytes: Sized {
+    /// Parse `data`, the record following the DID in the response.
+    fn from_uds_bytes(data: &[u8]) -> Result<Self, DiagError>;
+}
+
+macro_rules! impl_from_uds_bytes_for_int {
+    ($($int:ty),*) => {
+        $(
+            impl FromUdsBytes for $int {
+                fn from_uds_bytes(data: &[u8]) -> Result<Self, DiagError> {
+                    let bytes = data.try_into().map_err(|_| DiagError::InvalidResponseLength)?;
+                    Ok(<$int>::from_be_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_uds_bytes_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl<const N: usize> FromUdsBytes for [u8; N] {
+    fn from_uds_bytes(data: &[u8]) -> Result<Self, DiagError> {
+        data.try_into()
+            .map_err(|_| DiagError::InvalidResponseLength)
+    }
+}
+
+impl FromUdsBytes for Vec<u8> {
+    fn from_uds_bytes(data: &[u8]) -> Result<Self, DiagError> {
+        Ok(data.to_vec())
+    }
+}
+
+/// Conversion of a typed value into a DID data record, see `UdsClient::write_did_as`.
+///
+/// Implemented for the integer types (big-endian), byte arrays, byte slices and vectors, and
+/// strings (written as their UTF-8 bytes, without terminator). Implement it for a user type to
+/// write a structured record.
+pub trait IntoUdsBytes {
+    /// Serialize the value into the record following the DID in the request.
+    fn into_uds_bytes(self) -> Vec<u8>;
+}
+
+macro_rules! impl_into_uds_bytes_for_int {
+    ($($int:ty),*) => {
+        $(
+            impl IntoUdsBytes for $int {
+                fn into_uds_bytes(self) -> Vec<u8> {
+                    self.to_be_bytes().to_vec()
+                }
+            }
+        )*
+    };
+}
+
+impl_into_uds_bytes_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl<const N: usize> IntoUdsBytes for [u8; N] {
+    fn into_uds_bytes(self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl IntoUdsBytes for &[u8] {
+    fn into_uds_bytes(self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl IntoUdsBytes for Vec<u8> {
+    fn into_uds_bytes(self) -> Vec<u8> {
+        self
+    }
+}
+
+impl IntoUdsBytes for &str {
+    fn into_uds_bytes(self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl IntoUdsBytes for String {
+    fn into_uds_bytes(self) -> Vec<u8> {
+        self.into_bytes()
+    }
+}
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x22 - Read Data By Identifier
+    /// Description:
+    ///     The function will read the data record of `did`. Multi-frame responses are reassembled.
+    pub async fn read_data_by_identifier(&mut self, did: u16) -> Result<Vec<u8>, DiagError> {
+        let mut request = vec![UdsCommand::ReadDataByIdentifier as u8];
+        request.extend_from_slice(&did.to_be_bytes());
+        let response = self.raw_request(&request).await?;
+
+        let data = positive_response_data(&response, UdsCommand::ReadDataByIdentifier)?;
+        let (received, record) = data
+            .split_first_chunk::<2>()
+            .ok_or(DiagError::InvalidResponseLength)?;
+        let received = u16::from_be_bytes(*received);
+        if received != did {
+            return Err(DiagError::MismatchedIdentResponse {
+                want: did,
+                received,
+            });
+        }
+        Ok(record.to_vec())
+    }
+
+    /// Service ID: 0x22 - Read Data By Identifier
+    /// Description:
+    ///     The function will read the data record of `did` and parse it into `V`
+    ///     (see `FromUdsBytes`), e.g. `read_did_as::<u32>(did)` or `read_did_as::<[u8; 17]>(DID_VIN)`.
+    ///     `InvalidResponseLength` is returned if the record doesn't have the size of `V`.
+    pub async fn read_did_as<V: FromUdsBytes>(&mut self, did: u16) -> Result<V, DiagError> {
+        let record = self.read_data_by_identifier(did).await?;
+        V::from_uds_bytes(&record)
+    }
+
+    /// Service ID: 0x2E - Write Data By Identifier
+    /// Description:
+    ///     The function will write `data` as the data record of `did`. Long records are sent
+    ///     segmented. The ECU must echo `did`, otherwise `MismatchedIdentResponse` is returned.
+    pub async fn write_data_by_identifier(
+        &mut self,
+        did: u16,
+        data: &[u8],
+    ) -> Result<(), DiagError> {
+        let mut request = vec![UdsCommand::WriteDataByIdentifier as u8];
+        request.extend_from_slice(&did.to_be_bytes());
+        request.extend_from_slice(data);
+        let response = self.raw_request(&request).await?;
+
+        let data = positive_response_data(&response, UdsCommand::WriteDataByIdentifier)?;
+        let received = data
+            .first_chunk::<2>()
+            .ok_or(DiagError::InvalidResponseLength)?;
+        let received = u16::from_be_bytes(*received);
+        if received != did {
+            return Err(DiagError::MismatchedIdentResponse {
+                want: did,
+                received,
+            });
+        }
+        Ok(())
+    }
+
+    /// Service ID: 0x2E - Write Data By Identifier
+    /// Description:
+    ///     The function will serialize `value` (see `IntoUdsBytes`) and write it as the data
+    ///     record of `did`, e.g. `write_did_as(did, 100_000u32)` or `write_did_as(did, "ABC")`.
+    pub async fn write_did_as<V: IntoUdsBytes>(
+        &mut self,
+        did: u16,
+        value: V,
+    ) -> Result<(), DiagError> {
+        self.write_data_by_identifier(did, &value.into_uds_bytes())
+            .await
+    }
+
+    /// Service ID: 0x22 - Read Data By Identifier (DID 0xF190)
+    /// Description:
+    ///     The function will read the Vehicle Identification Number. The VIN must be made of
+    ///     17 ASCII characters: `InvalidResponseLength` is returned for any other length and
+    ///     `InvalidResponseData` for non-ASCII content.
+    pub async fn read_vin(&mut self) -> Result<String, DiagError> {
+        let vin = self.read_data_by_identifier(DID_VIN).await?;
+        if vin.len() != 17 {
+            return Err(DiagError::InvalidResponseLength);
+        }
+        if !vin.is_ascii() {
+            return Err(DiagError::InvalidResponseData);
+        }
+        String::from_utf8(vin).map_err(|_| DiagError::InvalidResponseData)
+    }
+
+    /// Service ID: 0x22 - Read Data By Identifier
+    /// Description:
+    ///     The function will read the identification string stored in `did`.
+    ///     Trailing padding (0x00, 0xFF and whitespace) is removed, `InvalidResponseData`
+    ///     is returned if the remaining data isn't valid UTF-8.
+    pub async fn read_identification(&mut self, did: u16) -> Result<String, DiagError> {
+        let data = self.read_data_by_identifier(did).await?;
+        let len = data
+            .iter()
+            .rposition(|byte| !matches!(byte, 0x00 | 0xFF) && !byte.is_ascii_whitespace())
+            .map_or(0, |last| last + 1);
+        String::from_utf8(data[..len].to_vec()).map_err(|_| DiagError::InvalidResponseData)
+    }
+
+    /// Service ID: 0x22 - Read Data By Identifier (DID 0xF195)
+    /// Description:
+    ///     The function will read the system supplier ECU software version number.
+    pub async fn read_ecu_sw_version(&mut self) -> Result<String, DiagError> {
+        self.read_identification(DID_ECU_SW_VERSION).await
+    }
+
+    /// Service ID: 0x22 - Read Data By Identifier (DID 0xF191)
+    /// Description:
+    ///     The function will read the vehicle manufacturer ECU hardware number.
+    pub async fn read_ecu_hw_version(&mut self) -> Result<String, DiagError> {
+        self.read_identification(DID_ECU_HW_VERSION).await
+    }
+
+    /// Service ID: 0x22 - Read Data By Identifier (DID 0xF192)
+    /// Description:
+    ///     The function will read the system supplier ECU hardware number.
+    pub async fn read_supplier_ecu_hardware_number(&mut self) -> Result<String, DiagError> {
+        self.read_identification(DID_SUPPLIER_ECU_HW_NUMBER).await
+    }
+
+    /// Service ID: 0x22 - Read Data By Identifier
+    /// Description:
+    ///     The function will read the data records of several DIDs with a single request.
+    ///     The response is a concatenation of `DID || data` records, split using the client's
+    ///     `DidRegistry` (see `DidRegistry::split_records`).
+    ///     Unsupported DIDs are omitted by the ECU and are missing from the result.
+    pub async fn read_data_by_identifier_multi(
+        &mut self,
+        dids: &[u16],
+    ) -> Result<Vec<(u16, Vec<u8>)>, DiagError> {
+        if dids.is_empty() {
+            return Err(DiagError::ParameterInvalid);
+        }
+
+        let mut request = vec![UdsCommand::ReadDataByIdentifier as u8];
+        for did in dids {
+            request.extend_from_slice(&did.to_be_bytes());
+        }
+        let response = self.raw_request(&request).await?;
+
+        let data = positive_response_data(&response, UdsCommand::ReadDataByIdentifier)?;
+        self.did_registry().split_records(data, dids)
+    }
+}
@@ -0,0 +1,614 @@
+//!  Provides methods to read the Diagnostic Trouble Codes stored by the ECU and the data recorded
+//!  with them: ReadDTCInformation (0x19), and to stop or resume their setting: ControlDTCSetting
+//!  (0x85).
+//!
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{
+        DiagError, DidRegistry, Response, UdsClient, UdsResponseParser,
+        client::{check_positive_sid, positive_response_data},
+    },
+};
+use automotive_diag::uds::UdsCommand;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// ReadDTCInformation sub-function: reportNumberOfDTCByStatusMask
+pub(super) const REPORT_NUMBER_OF_DTC_BY_STATUS_MASK: u8 = 0x01;
+/// ReadDTCInformation sub-function: reportDTCByStatusMask
+pub(super) const REPORT_DTC_BY_STATUS_MASK: u8 = 0x02;
+/// ReadDTCInformation sub-function: reportDTCSnapshotIdentification
+const REPORT_DTC_SNAPSHOT_IDENTIFICATION: u8 = 0x03;
+/// ReadDTCInformation sub-function: reportDTCSnapshotRecordByDTCNumber
+const REPORT_DTC_SNAPSHOT_RECORD_BY_DTC_NUMBER: u8 = 0x04;
+/// ReadDTCInformation sub-function: reportDTCExtDataRecordByDTCNumber
+const REPORT_DTC_EXT_DATA_RECORD_BY_DTC_NUMBER: u8 = 0x06;
+/// ReadDTCInformation sub-function: reportNumberOfDTCBySeverityMaskRecord
+const REPORT_NUMBER_OF_DTC_BY_SEVERITY_MASK_RECORD: u8 = 0x07;
+/// ReadDTCInformation sub-function: reportDTCBySeverityMaskRecord
+const REPORT_DTC_BY_SEVERITY_MASK_RECORD: u8 = 0x08;
+/// ReadDTCInformation sub-function: reportSeverityInformationOfDTC
+const REPORT_SEVERITY_INFORMATION_OF_DTC: u8 = 0x09;
+/// ReadDTCInformation sub-function: reportDTCExtDataRecordByRecordNumber
+const REPORT_DTC_EXT_DATA_RECORD_BY_RECORD_NUMBER: u8 = 0x16;
+/// ReadDTCInformation sub-function: reportUserDefMemoryDTCExtDataRecordByDTCNumber
+const REPORT_USER_DEF_MEMORY_DTC_EXT_DATA_RECORD_BY_DTC_NUMBER: u8 = 0x19;
+
+/// Control DTC Setting sub-function (ISO 14229-1)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DtcSettingType {
+    On = 0x01,  // Resume the update of the DTC status bits
+    Off = 0x02, // Stop the update of the DTC status bits
+}
+
+impl From<DtcSettingType> for u8 {
+    fn from(setting: DtcSettingType) -> Self {
+        setting as u8
+    }
+}
+
+impl TryFrom<u8> for DtcSettingType {
+    type Error = DiagError;
+    fn try_from(setting: u8) -> Result<Self, Self::Error> {
+        match setting {
+            0x01 => Ok(DtcSettingType::On),
+            0x02 => Ok(DtcSettingType::Off),
+            _ => Err(DiagError::ParameterInvalid),
+        }
+    }
+}
+
+/// A snapshot record (freeze frame) stored with a DTC: the data identifiers captured when the
+/// DTC was detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DtcSnapshotRecord {
+    pub number: u8,                   // DTCSnapshotRecordNumber
+    pub records: Vec<(u16, Vec<u8>)>, // DID and data of each captured identifier
+}
+
+/// A DTC and its status, as listed by reportDTCByStatusMask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dtc {
+    pub code: u32,  // 3 bytes DTC
+    pub status: u8, // DTC status bits
+}
+
+/// An extended data record and the DTC it is stored with, as listed by
+/// reportDTCExtDataRecordByRecordNumber.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DtcExtendedDataRecord {
+    pub dtc: Dtc,      // DTC and its status
+    pub number: u8,    // DTCExtDataRecordNumber
+    pub data: Vec<u8>, // Content of the record
+}
+
+/// A DTC with its severity information (DTCAndSeverityRecord), to prioritize the repairs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DtcSeverityRecord {
+    pub severity: u8,        // DTCSeverity, e.g. 0x20: checkImmediately
+    pub functional_unit: u8, // DTCFunctionalUnit: the function group the DTC belongs to
+    pub dtc: u32,            // 3 bytes DTC
+    pub status: u8,          // DTC status bits
+}
+
+/// Parser of the ControlDTCSetting response, which carries no data to decode.
+struct ControlDtcSettingResponse;
+
+impl UdsResponseParser for ControlDtcSettingResponse {
+    type Output = ();
+    const COMMAND: UdsCommand = UdsCommand::ControlDTCSetting;
+
+    fn parse_params(&self, _params: &[u8]) -> Result<(), DiagError> {
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x01 (reportNumberOfDTCByStatusMask)
+    /// Description:
+    ///     The function will request the number of DTCs whose status matches `mask` (0xFF: any
+    ///     status bit), a cheap check before reading the DTCs themselves.
+    /// Returns:
+    ///     The number of matching DTCs. A response echoing another sub-function returns
+    ///     `DiagError::InvalidResponseData`.
+    pub async fn read_dtc_count(&mut self, mask: u8) -> Result<u16, DiagError> {
+        let response = self
+            .raw_request(&[
+                UdsCommand::ReadDTCInformation as u8,
+                REPORT_NUMBER_OF_DTC_BY_STATUS_MASK,
+                mask,
+            ])
+            .await?;
+        let count = dtc_count(&response, REPORT_NUMBER_OF_DTC_BY_STATUS_MASK)?;
+        log_debug!("UDS: {} DTCs match the status mask 0x{:02X}", count, mask);
+        Ok(count)
+    }
+
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x02 (reportDTCByStatusMask)
+    /// Description:
+    ///     The function will read the DTCs whose status matches `mask`, sending each one to
+    ///     `dtcs` as soon as its Consecutive Frame is received instead of buffering the whole
+    ///     list: a UI can show a large fault memory while it is still being read. The DTCs
+    ///     sent before an error stay valid. A response echoing another sub-function returns
+    ///     `DiagError::InvalidResponseData`, a truncated DTC record
+    ///     `DiagError::InvalidResponseLength`.
+    /// Returns:
+    ///     The number of DTCs read.
+    pub async fn read_dtc_stream(
+        &mut self,
+        mask: u8,
+        dtcs: UnboundedSender<Dtc>,
+    ) -> Result<usize, DiagError> {
+        let request = [
+            UdsCommand::ReadDTCInformation as u8,
+            REPORT_DTC_BY_STATUS_MASK,
+            mask,
+        ];
+        let first = match self.send_payload_with_response(&request).await? {
+            Response::Ok(frame, _) => frame,
+            Response::Error(e) => return Err(e),
+        };
+
+        // SID, sub-function and DTCStatusAvailabilityMask, then 4 bytes per DTC
+        let mut pending = Vec::new();
+        let mut header = false;
+        let mut count = 0;
+        self.receive_payload_chunks(first, |chunk| {
+            pending.extend_from_slice(chunk);
+            if !header {
+                let [sid, sub_function, _availability_mask, ..] = pending[..] else {
+                    return Ok(());
+                };
+                check_positive_sid(sid, UdsCommand::ReadDTCInformation)?;
+                if sub_function != REPORT_DTC_BY_STATUS_MASK {
+                    return Err(DiagError::InvalidResponseData);
+                }
+                pending.drain(..3);
+                header = true;
+            }
+            let (records, _) = pending.as_chunks::<4>();
+            for &[high, middle, low, status] in records {
+                let code = u32::from_be_bytes([0, high, middle, low]);
+                // A dropped receiver doesn't stop the ISO-TP reception
+                let _ = dtcs.send(Dtc { code, status });
+            }
+            count += records.len();
+            pending.drain(..records.len() * 4);
+            Ok(())
+        })
+        .await?;
+
+        if !header || !pending.is_empty() {
+            return Err(DiagError::InvalidResponseLength);
+        }
+        log_debug!("UDS: {} DTCs match the status mask 0x{:02X}", count, mask);
+        Ok(count)
+    }
+
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x07 (reportNumberOfDTCBySeverityMaskRecord)
+    /// Description:
+    ///     The function will request the number of DTCs whose severity matches `severity_mask`
+    ///     (e.g. 0x20: checkImmediately) and whose status matches `status_mask`.
+    /// Returns:
+    ///     The number of matching DTCs. A response echoing another sub-function returns
+    ///     `DiagError::InvalidResponseData`.
+    pub async fn read_dtc_count_by_severity(
+        &mut self,
+        severity_mask: u8,
+        status_mask: u8,
+    ) -> Result<u16, DiagError> {
+        let response = self
+            .raw_request(&[
+                UdsCommand::ReadDTCInformation as u8,
+                REPORT_NUMBER_OF_DTC_BY_SEVERITY_MASK_RECORD,
+                severity_mask,
+                status_mask,
+            ])
+            .await?;
+        let count = dtc_count(&response, REPORT_NUMBER_OF_DTC_BY_SEVERITY_MASK_RECORD)?;
+        log_debug!(
+            "UDS: {} DTCs match the severity mask 0x{:02X} and the status mask 0x{:02X}",
+            count,
+            severity_mask,
+            status_mask
+        );
+        Ok(count)
+    }
+
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x08 (reportDTCBySeverityMaskRecord)
+    /// Description:
+    ///     The function will read the DTCs whose severity matches `severity_mask` and whose
+    ///     status matches `status_mask`, with their severity and functional unit. The response
+    ///     is usually segmented, it is reassembled by `raw_request`.
+    /// Returns:
+    ///     The severity record of each matching DTC. A response echoing another sub-function
+    ///     returns `DiagError::InvalidResponseData`.
+    pub async fn read_dtc_by_severity(
+        &mut self,
+        severity_mask: u8,
+        status_mask: u8,
+    ) -> Result<Vec<DtcSeverityRecord>, DiagError> {
+        let response = self
+            .raw_request(&[
+                UdsCommand::ReadDTCInformation as u8,
+                REPORT_DTC_BY_SEVERITY_MASK_RECORD,
+                severity_mask,
+                status_mask,
+            ])
+            .await?;
+        severity_records(&response, REPORT_DTC_BY_SEVERITY_MASK_RECORD)
+    }
+
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x09 (reportSeverityInformationOfDTC)
+    /// Description:
+    ///     The function will read the severity and functional unit of the 3 bytes DTC `dtc`.
+    /// Returns:
+    ///     The severity record of `dtc`, `None` if the ECU doesn't know it. A response for
+    ///     another DTC returns `DiagError::InvalidResponseData`.
+    pub async fn read_dtc_severity(
+        &mut self,
+        dtc: u32,
+    ) -> Result<Option<DtcSeverityRecord>, DiagError> {
+        if dtc > 0xFF_FFFF {
+            return Err(DiagError::ParameterInvalid);
+        }
+        let [_, high, middle, low] = dtc.to_be_bytes();
+        let response = self
+            .raw_request(&[
+                UdsCommand::ReadDTCInformation as u8,
+                REPORT_SEVERITY_INFORMATION_OF_DTC,
+                high,
+                middle,
+                low,
+            ])
+            .await?;
+        match severity_records(&response, REPORT_SEVERITY_INFORMATION_OF_DTC)?[..] {
+            [] => Ok(None),
+            [record] if record.dtc == dtc => Ok(Some(record)),
+            _ => Err(DiagError::InvalidResponseData),
+        }
+    }
+
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x03 (reportDTCSnapshotIdentification)
+    /// Description:
+    ///     The function will list the snapshot records stored by the ECU, to know which ones
+    ///     to fetch with `read_dtc_snapshot_records`.
+    /// Returns:
+    ///     The 3 bytes DTC and the DTCSnapshotRecordNumber of each stored snapshot record.
+    pub async fn read_dtc_snapshot_identification(&mut self) -> Result<Vec<(u32, u8)>, DiagError> {
+        let response = self
+            .raw_request(&[
+                UdsCommand::ReadDTCInformation as u8,
+                REPORT_DTC_SNAPSHOT_IDENTIFICATION,
+            ])
+            .await?;
+
+        let data = positive_response_data(&response, UdsCommand::ReadDTCInformation)?;
+        let [sub_function, records @ ..] = data else {
+            return Err(DiagError::InvalidResponseLength);
+        };
+        if *sub_function != REPORT_DTC_SNAPSHOT_IDENTIFICATION {
+            return Err(DiagError::InvalidResponseData);
+        }
+        // DTC (3 bytes) and DTCSnapshotRecordNumber of each record
+        let (records, rest) = records.as_chunks::<4>();
+        if !rest.is_empty() {
+            return Err(DiagError::InvalidResponseLength);
+        }
+        Ok(records
+            .iter()
+            .map(|&[high, middle, low, number]| {
+                (u32::from_be_bytes([0, high, middle, low]), number)
+            })
+            .collect())
+    }
+
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x04 (reportDTCSnapshotRecordByDTCNumber)
+    /// Description:
+    ///     The function will read the snapshot record `record` (0xFF: all records) stored with
+    ///     the 3 bytes DTC `dtc`. Each record lists the identifiers it captured as `DID || data`,
+    ///     split using the client's `DidRegistry`: the data of an unregistered DID extends to the
+    ///     end of the response, so only the last identifier of the response may be unregistered.
+    /// Returns:
+    ///     The snapshot records, empty if the ECU has none stored for `dtc`.
+    pub async fn read_dtc_snapshot_records(
+        &mut self,
+        dtc: u32,
+        record: u8,
+    ) -> Result<Vec<DtcSnapshotRecord>, DiagError> {
+        let response = self
+            .dtc_record_request(REPORT_DTC_SNAPSHOT_RECORD_BY_DTC_NUMBER, dtc, record)
+            .await?;
+        let mut data = dtc_records(&response, REPORT_DTC_SNAPSHOT_RECORD_BY_DTC_NUMBER, dtc)?;
+
+        let mut snapshots = Vec::new();
+        while let [number, identifiers, rest @ ..] = data {
+            let number = *number;
+            data = rest;
+            let records = split_snapshot(self.did_registry(), &mut data, *identifiers)?;
+            snapshots.push(DtcSnapshotRecord { number, records });
+        }
+        if !data.is_empty() {
+            return Err(DiagError::InvalidResponseLength);
+        }
+        Ok(snapshots)
+    }
+
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x06 (reportDTCExtDataRecordByDTCNumber)
+    /// Description:
+    ///     The function will read the extended data record `record` (e.g. occurrence counter,
+    ///     aging counter) stored with the 3 bytes DTC `dtc`. The length of the records is
+    ///     manufacturer specific, so they are read one at a time: the record numbers 0xFE and
+    ///     0xFF (all OBD / all records) return `DiagError::ParameterInvalid`.
+    /// Returns:
+    ///     The data of the record, `None` if the ECU has no such record stored for `dtc`.
+    pub async fn read_dtc_extended_data(
+        &mut self,
+        dtc: u32,
+        record: u8,
+    ) -> Result<Option<Vec<u8>>, DiagError> {
+        if record >= 0xFE {
+            return Err(DiagError::ParameterInvalid);
+        }
+        let response = self
+            .dtc_record_request(REPORT_DTC_EXT_DATA_RECORD_BY_DTC_NUMBER, dtc, record)
+            .await?;
+        match dtc_records(&response, REPORT_DTC_EXT_DATA_RECORD_BY_DTC_NUMBER, dtc)? {
+            [] => Ok(None),
+            [number, data @ ..] if *number == record => Ok(Some(data.to_vec())),
+            _ => Err(DiagError::InvalidResponseData),
+        }
+    }
+
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x16 (reportDTCExtDataRecordByRecordNumber)
+    /// Description:
+    ///     The function will read the extended data record `record` of every DTC storing it,
+    ///     for the ECUs organizing their extended data by global record number. The response
+    ///     lists `DTC || status || data` for each DTC without telling the length of the data,
+    ///     which is manufacturer specific: it must be given as `record_len`. The record numbers
+    ///     0xF0-0xFF are reserved and return `DiagError::ParameterInvalid`.
+    /// Returns:
+    ///     The record of each DTC, empty if no DTC stores it. A response echoing another
+    ///     sub-function or record returns `DiagError::InvalidResponseData`, a truncated record
+    ///     `DiagError::InvalidResponseLength`.
+    pub async fn read_dtc_extended_data_record_by_record_number(
+        &mut self,
+        record: u8,
+        record_len: usize,
+    ) -> Result<Vec<DtcExtendedDataRecord>, DiagError> {
+        if record >= 0xF0 {
+            return Err(DiagError::ParameterInvalid);
+        }
+        let response = self
+            .raw_request(&[
+                UdsCommand::ReadDTCInformation as u8,
+                REPORT_DTC_EXT_DATA_RECORD_BY_RECORD_NUMBER,
+                record,
+            ])
+            .await?;
+
+        let data = positive_response_data(&response, UdsCommand::ReadDTCInformation)?;
+        let [sub_function, number, records @ ..] = data else {
+            return Err(DiagError::InvalidResponseLength);
+        };
+        let mut records = records;
+        if *sub_function != REPORT_DTC_EXT_DATA_RECORD_BY_RECORD_NUMBER || *number != record {
+            return Err(DiagError::InvalidResponseData);
+        }
+
+        let mut result = Vec::new();
+        while let [high, middle, low, status, rest @ ..] = records {
+            let data = rest
+                .get(..record_len)
+                .ok_or(DiagError::InvalidResponseLength)?;
+            result.push(DtcExtendedDataRecord {
+                dtc: Dtc {
+                    code: u32::from_be_bytes([0, *high, *middle, *low]),
+                    status: *status,
+                },
+                number: record,
+                data: data.to_vec(),
+            });
+            records = &rest[record_len..];
+        }
+        if !records.is_empty() {
+            return Err(DiagError::InvalidResponseLength);
+        }
+        Ok(result)
+    }
+
+    /// Service ID: 0x19 - Read DTC Information
+    ///     Sub-ID: 0x19 (reportUserDefMemoryDTCExtDataRecordByDTCNumber)
+    /// Description:
+    ///     The function will read the extended data record `record` stored with the 3 bytes DTC
+    ///     `dtc` in the user defined fault memory `memory` (e.g. a secondary or OEM specific
+    ///     memory). As for `read_dtc_extended_data`, the records are read one at a time: the
+    ///     record numbers 0xFE and 0xFF return `DiagError::ParameterInvalid`.
+    /// Returns:
+    ///     The data of the record, `None` if the ECU has no such record stored for `dtc`. A
+    ///     response for another memory, DTC or record returns `DiagError::InvalidResponseData`.
+    pub async fn read_user_memory_dtc_extended_data(
+        &mut self,
+        dtc: u32,
+        record: u8,
+        memory: u8,
+    ) -> Result<Option<Vec<u8>>, DiagError> {
+        if record >= 0xFE || dtc > 0xFF_FFFF {
+            return Err(DiagError::ParameterInvalid);
+        }
+        let [_, high, middle, low] = dtc.to_be_bytes();
+        let response = self
+            .raw_request(&[
+                UdsCommand::ReadDTCInformation as u8,
+                REPORT_USER_DEF_MEMORY_DTC_EXT_DATA_RECORD_BY_DTC_NUMBER,
+                high,
+                middle,
+                low,
+                record,
+                memory,
+            ])
+            .await?;
+
+        // sub-function, MemorySelection, DTC (3 bytes), status, then the record
+        let data = positive_response_data(&response, UdsCommand::ReadDTCInformation)?;
+        let [sub_function, received_memory, rest @ ..] = data else {
+            return Err(DiagError::InvalidResponseLength);
+        };
+        if *sub_function != REPORT_USER_DEF_MEMORY_DTC_EXT_DATA_RECORD_BY_DTC_NUMBER
+            || *received_memory != memory
+        {
+            return Err(DiagError::InvalidResponseData);
+        }
+        let [
+            received_high,
+            received_middle,
+            received_low,
+            _status,
+            records @ ..,
+        ] = rest
+        else {
+            return Err(DiagError::InvalidResponseLength);
+        };
+        if [*received_high, *received_middle, *received_low] != [high, middle, low] {
+            return Err(DiagError::InvalidResponseData);
+        }
+        match records {
+            [] => Ok(None),
+            [number, data @ ..] if *number == record => Ok(Some(data.to_vec())),
+            _ => Err(DiagError::InvalidResponseData),
+        }
+    }
+
+    /// Service ID: 0x85 - Control DTC Setting
+    ///     Sub-ID: `setting`
+    /// Description:
+    ///     The function will stop or resume the update of the DTC status bits, e.g. to avoid
+    ///     storing DTCs while flashing. Doesn't wait for a response when positive responses are
+    ///     suppressed.
+    pub async fn control_dtc_setting(&mut self, setting: DtcSettingType) -> Result<(), DiagError> {
+        log_debug!("UDS: DTC setting {:?}", setting);
+        let Some(response) = self
+            .send_sub_function_command(UdsCommand::ControlDTCSetting, setting.into(), &[])
+            .await?
+        else {
+            return Ok(());
+        };
+
+        ControlDtcSettingResponse.parse(&response)
+    }
+
+    /// Request the records of `sub_function` stored with the 3 bytes DTC `dtc`.
+    async fn dtc_record_request(
+        &mut self,
+        sub_function: u8,
+        dtc: u32,
+        record: u8,
+    ) -> Result<Vec<u8>, DiagError> {
+        if dtc > 0xFF_FFFF {
+            return Err(DiagError::ParameterInvalid);
+        }
+        let [_, high, middle, low] = dtc.to_be_bytes();
+        self.raw_request(&[
+            UdsCommand::ReadDTCInformation as u8,
+            sub_function,
+            high,
+            middle,
+            low,
+            record,
+        ])
+        .await
+    }
+}
+
+/// Check that `response` answers the DTC count request `sub_function` and return the count.
+fn dtc_count(response: &[u8], sub_function: u8) -> Result<u16, DiagError> {
+    // sub-function, DTCStatusAvailabilityMask, DTCFormatIdentifier, DTCCount (2 bytes)
+    let data = positive_response_data(response, UdsCommand::ReadDTCInformation)?;
+    let &[received, availability_mask, format, high, low, ..] = data else {
+        return Err(DiagError::InvalidResponseLength);
+    };
+    if received != sub_function {
+        return Err(DiagError::InvalidResponseData);
+    }
+    log_debug!(
+        "UDS: DTC availability mask 0x{:02X}, format 0x{:02X}",
+        availability_mask,
+        format
+    );
+    Ok(u16::from_be_bytes([high, low]))
+}
+
+/// Check that `response` answers the severity request `sub_function` and parse its records.
+fn severity_records(
+    response: &[u8],
+    sub_function: u8,
+) -> Result<Vec<DtcSeverityRecord>, DiagError> {
+    let data = positive_response_data(response, UdsCommand::ReadDTCInformation)?;
+    let [received, _availability_mask, records @ ..] = data else {
+        return Err(DiagError::InvalidResponseLength);
+    };
+    if *received != sub_function {
+        return Err(DiagError::InvalidResponseData);
+    }
+    // DTCSeverity, DTCFunctionalUnit, DTC (3 bytes) and status of each record
+    let (records, rest) = records.as_chunks::<6>();
+    if !rest.is_empty() {
+        return Err(DiagError::InvalidResponseLength);
+    }
+    Ok(records
+        .iter()
+        .map(
+            |&[severity, functional_unit, high, middle, low, status]| DtcSeverityRecord {
+                severity,
+                functional_unit,
+                dtc: u32::from_be_bytes([0, high, middle, low]),
+                status,
+            },
+        )
+        .collect())
+}
+
+/// Check that `response` answers `sub_function` for `dtc` and return the records following the
+/// DTC and its status.
+fn dtc_records(response: &[u8], sub_function: u8, dtc: u32) -> Result<&[u8], DiagError> {
+    let data = positive_response_data(response, UdsCommand::ReadDTCInformation)?;
+    let [received, high, middle, low, _status, records @ ..] = data else {
+        return Err(DiagError::InvalidResponseLength);
+    };
+    if *received != sub_function || u32::from_be_bytes([0, *high, *middle, *low]) != dtc {
+        return Err(DiagError::InvalidResponseData);
+    }
+    Ok(records)
+}
+
+/// Split the `identifiers` DID records of a snapshot record, `data` being advanced past them.
+fn split_snapshot(
+    registry: &DidRegistry,
+    data: &mut &[u8],
+    identifiers: u8,
+) -> Result<Vec<(u16, Vec<u8>)>, DiagError> {
+    let mut records = Vec::with_capacity(identifiers as usize);
+    for _ in 0..identifiers {
+        let (did, rest) = data
+            .split_first_chunk::<2>()
+            .ok_or(DiagError::InvalidResponseLength)?;
+        let did = u16::from_be_bytes(*did);
+        let len = registry.length(did).unwrap_or(rest.len());
+        let record = rest.get(..len).ok_or(DiagError::InvalidResponseLength)?;
+        records.push((did, record.to_vec()));
+        *data = &rest[len..];
+    }
+    Ok(records)
+}
@@ -2,21 +2,100 @@
 //!
 
 use crate::{
+    logging::log_debug,
     socket_can::CanSocketTx,
-    uds_client::{DiagError, PciByte, UdsClient},
+    uds_client::{DiagError, UdsClient, UdsResponseParser},
 };
 use automotive_diag::uds::UdsCommand;
 
+/// ECU Reset sub-function (ISO 14229-1)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcuResetType {
+    HardReset = 0x01,                 // Simulated power off/on cycle
+    KeyOffOnReset = 0x02,             // Simulated key off/on cycle
+    SoftReset = 0x03,                 // Restart of the running application
+    EnableRapidPowerShutDown = 0x04,  // Enable rapid power shutdown on key-off
+    DisableRapidPowerShutDown = 0x05, // Disable rapid power shutdown on key-off
+}
+
+impl From<EcuResetType> for u8 {
+    fn from(reset_type: EcuResetType) -> Self {
+        reset_type as u8
+    }
+}
+
+impl TryFrom<u8> for EcuResetType {
+    type Error = DiagError;
+    fn try_from(reset_type: u8) -> Result<Self, Self::Error> {
+        match reset_type {
+            0x01 => Ok(EcuResetType::HardReset),
+            0x02 => Ok(EcuResetType::KeyOffOnReset),
+            0x03 => Ok(EcuResetType::SoftReset),
+            0x04 => Ok(EcuResetType::EnableRapidPowerShutDown),
+            0x05 => Ok(EcuResetType::DisableRapidPowerShutDown),
+            _ => Err(DiagError::ParameterInvalid),
+        }
+    }
+}
+
+/// Parser of the ECUReset response to a reset of type `kind`.
+struct EcuResetResponse {
+    kind: EcuResetType, // The requested reset type
+}
+
+impl UdsResponseParser for EcuResetResponse {
+    type Output = Option<u8>;
+    const COMMAND: UdsCommand = UdsCommand::ECUReset;
+
+    /// The powerDownTime follows the reset type for `EnableRapidPowerShutDown` only.
+    fn parse_params(&self, params: &[u8]) -> Result<Option<u8>, DiagError> {
+        match params.first() {
+            Some(kind) if *kind == u8::from(self.kind) => {}
+            Some(_) => return Err(DiagError::InvalidResponseData),
+            None => return Err(DiagError::InvalidResponseLength),
+        }
+        if self.kind != EcuResetType::EnableRapidPowerShutDown {
+            return Ok(None);
+        }
+        match params.get(1) {
+            Some(0xFF) => Err(DiagError::NotSupported),
+            Some(power_down_time) => Ok(Some(*power_down_time)),
+            None => Err(DiagError::InvalidResponseLength),
+        }
+    }
+}
+
 #[allow(dead_code)]
 impl<T: CanSocketTx> UdsClient<'_, T> {
     /// Service ID: 0x11 - ECU Reset
+    ///     Sub-ID: `kind`
+    /// Description:
+    ///     The function will request an ECU reset of the given type.
+    ///     Doesn't wait for a response when positive responses are suppressed.
+    /// Returns:
+    ///     For `EnableRapidPowerShutDown`, the powerDownTime in seconds reported by the ECU, i.e.
+    ///     how long to wait before expecting the ECU back. A powerDownTime of 0xFF means the
+    ///     information is not available and is returned as `DiagError::NotSupported`.
+    ///     `None` for the other reset types, or when the response is suppressed.
+    pub async fn uds_reset(&mut self, kind: EcuResetType) -> Result<Option<u8>, DiagError> {
+        log_debug!("UDS: send reset ECU {:?}", kind);
+        let Some(response) = self
+            .send_sub_function_command(UdsCommand::ECUReset, kind.into(), &[])
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        EcuResetResponse { kind }.parse(&response)
+    }
+
+    /// Service ID: 0x11 - ECU Reset
+    ///     Sub-ID: 0x01 (hardReset)
     /// Description:
-    ///     The function will request an ECU reset event.
+    ///     The function will request an ECU hard reset, see `uds_reset`.
     pub async fn uds_reset_ecu(&mut self) -> Result<(), DiagError> {
-        dbg!("UDS: send reset ECU");
-        let pci_byte = PciByte::new(crate::uds_client::PciType::SingleFrame, 2);
-        self.send_command_with_response(pci_byte, UdsCommand::ECUReset, &[])
-            .await?;
+        self.uds_reset(EcuResetType::HardReset).await?;
         Ok(())
     }
 }
@@ -0,0 +1,134 @@
+//!  Provides the addressAndLengthFormatIdentifier encoding shared by the memory services:
+//!  ReadMemoryByAddress (0x23), WriteMemoryByAddress (0x3D), RequestDownload (0x34) and RequestUpload (0x35),
+//!  and methods to read the ECU memory: ReadMemoryByAddress and `read_memory_range` streaming
+//!  large regions.
+//!
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient, client::positive_response_data},
+};
+use automotive_diag::uds::UdsCommand;
+use std::io::Write;
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x23 - Read Memory By Address
+    /// Description:
+    ///     The function will read `size` bytes from the memory address `addr`. The address and
+    ///     the size are both encoded on 4 bytes.
+    /// Returns:
+    ///     The dataRecord. A record of another length than `size` returns
+    ///     `DiagError::InvalidResponseLength`.
+    pub async fn read_memory_by_address(
+        &mut self,
+        addr: u32,
+        size: u32,
+    ) -> Result<Vec<u8>, DiagError> {
+        let mut request = vec![UdsCommand::ReadMemoryByAddress as u8];
+        request.extend_from_slice(&encode_addr_and_length(addr.into(), size.into(), 4, 4)?);
+        let response = self.raw_request(&request).await?;
+
+        let data = positive_response_data(&response, UdsCommand::ReadMemoryByAddress)?;
+        if data.len() != size as usize {
+            return Err(DiagError::InvalidResponseLength);
+        }
+        Ok(data.to_vec())
+    }
+
+    /// Service ID: 0x23 - Read Memory By Address, repeated
+    /// Description:
+    ///     The function will read the `len` bytes starting at the memory address `start` with
+    ///     ReadMemoryByAddress requests of `chunk` bytes, the last one reading the remaining
+    ///     bytes, and write each record to `sink` as it arrives. The read stops at the first
+    ///     error. A `chunk` of 0 or a range beyond the 4 bytes address space returns
+    ///     `DiagError::ParameterInvalid`, a failing `sink` `DiagError::WriteError`.
+    pub async fn read_memory_range(
+        &mut self,
+        start: u32,
+        len: u32,
+        chunk: u32,
+        sink: &mut impl Write,
+    ) -> Result<(), DiagError> {
+        if chunk == 0 || start.checked_add(len.saturating_sub(1)).is_none() {
+            return Err(DiagError::ParameterInvalid);
+        }
+        log_debug!(
+            "UDS: read of {} bytes from 0x{:08X}, {} bytes per request",
+            len,
+            start,
+            chunk
+        );
+
+        let mut offset = 0;
+        while offset < len {
+            let size = chunk.min(len - offset);
+            let data = self.read_memory_by_address(start + offset, size).await?;
+            sink.write_all(&data)
+                .map_err(|e| DiagError::WriteError(e.to_string()))?;
+            offset += size;
+        }
+        Ok(())
+    }
+}
+
+/// Encode the addressAndLengthFormatIdentifier followed by the memoryAddress and memorySize fields.
+///
+/// # Parameters:
+/// - `addr`: The memory address.
+/// - `size`: The memory size.
+/// - `addr_len`: Number of bytes used to encode `addr` (1-8), stored in the low nibble.
+/// - `size_len`: Number of bytes used to encode `size` (1-8), stored in the high nibble.
+///
+/// # Returns:
+/// - `Ok(Vec<u8>)`: `[format, addr (big-endian, addr_len bytes), size (big-endian, size_len bytes)]`.
+/// - `Err(DiagError::ParameterInvalid)`: If a length is out of range or a value doesn't fit its length.
+pub fn encode_addr_and_length(
+    addr: u64,
+    size: u64,
+    addr_len: u8,
+    size_len: u8,
+) -> Result<Vec<u8>, DiagError> {
+    let addr_bytes = be_bytes(addr, addr_len)?;
+    let size_bytes = be_bytes(size, size_len)?;
+
+    let mut data = vec![(size_len << 4) | addr_len];
+    data.extend_from_slice(&addr_bytes);
+    data.extend_from_slice(&size_bytes);
+    Ok(data)
+}
+
+/// Decode an addressAndLengthFormatIdentifier followed by the memoryAddress and memorySize fields.
+///
+/// # Returns:
+/// - `Ok((addr, size, len))`: The decoded address and size, and the number of bytes consumed from `data`.
+/// - `Err(DiagError::ParameterInvalid)`: If the format identifier uses a length of 0 or more than 8 bytes.
+/// - `Err(DiagError::InvalidResponseLength)`: If `data` is shorter than the format identifier announces.
+pub fn decode_addr_and_length(data: &[u8]) -> Result<(u64, u64, usize), DiagError> {
+    let format = *data.first().ok_or(DiagError::InvalidResponseLength)?;
+    let (size_len, addr_len) = ((format >> 4) as usize, (format & 0x0F) as usize);
+    if !(1..=8).contains(&addr_len) || !(1..=8).contains(&size_len) {
+        return Err(DiagError::ParameterInvalid);
+    }
+
+    let len = 1 + addr_len + size_len;
+    let fields = data.get(1..len).ok_or(DiagError::InvalidResponseLength)?;
+    let (addr, size) = fields.split_at(addr_len);
+    Ok((from_be_bytes(addr), from_be_bytes(size), len))
+}
+
+/// Big-endian encoding of `value` using exactly `len` bytes.
+fn be_bytes(value: u64, len: u8) -> Result<Vec<u8>, DiagError> {
+    if !(1..=8).contains(&len) || (len < 8 && value >> (len * 8) != 0) {
+        return Err(DiagError::ParameterInvalid);
+    }
+    Ok(value.to_be_bytes()[8 - len as usize..].to_vec())
+}
+
+/// Big-endian decoding of up to 8 bytes.
+fn from_be_bytes(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0, |value, byte| (value << 8) | *byte as u64)
+}
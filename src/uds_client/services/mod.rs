@@ -1,3 +1,34 @@
+mod command;
+mod communication;
+mod data_identifier;
+mod dtc;
 mod ecu_reset;
+mod memory;
+mod probe;
 mod realtime;
-pub use realtime::RealTimeType;
+mod routine;
+mod security;
+mod session;
+mod tester_present;
+mod timing;
+mod transfer;
+pub use command::CommandOutput;
+pub use communication::{
+    CommunicationControlType, NETWORK_MANAGEMENT_COMMUNICATION_MESSAGES,
+    NORMAL_COMMUNICATION_MESSAGES,
+};
+pub use data_identifier::{
+    DID_ACTIVE_DIAGNOSTIC_SESSION, DID_ECU_HW_VERSION, DID_ECU_SW_VERSION,
+    DID_SUPPLIER_ECU_HW_NUMBER, DID_VIN, DidRegistry, FromUdsBytes, IntoUdsBytes,
+};
+pub use dtc::{Dtc, DtcExtendedDataRecord, DtcSettingType, DtcSeverityRecord, DtcSnapshotRecord};
+pub use ecu_reset::EcuResetType;
+pub use memory::{decode_addr_and_length, encode_addr_and_length};
+pub use realtime::{PeriodicParsers, PeriodicSample, RealTimeType};
+pub use routine::{
+    ROUTINE_CHECK_PROGRAMMING_DEPENDENCIES, ROUTINE_ERASE_MEMORY, RoutineControlType, RoutineResult,
+};
+pub use session::{DiagnosticSessionType, ProgrammingOptions, SessionTiming};
+pub use tester_present::FUNCTIONAL_REQUEST_ID;
+pub use timing::TimingParameterAccessType;
+pub use transfer::DataFormat;
@@ -0,0 +1,72 @@
+//!  Provides a capability probe listing the services supported by the ECU, for the bring-up of
+//!  unknown ECUs.
+//!
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient},
+};
+use automotive_diag::uds::{UdsCommand, UdsError};
+
+/// The services probed by `probe_services`, with their probe request.
+///
+/// The requests hold the SID alone: they are incomplete for every service but TesterPresent,
+/// an ECU supporting the service rejects them (e.g. NRC 0x13 incorrectMessageLength) without
+/// running it. ECUReset and RequestTransferExit (complete without parameters) are never sent.
+const PROBES: &[(UdsCommand, &[u8])] = &[
+    (UdsCommand::DiagnosticSessionControl, &[0x10]),
+    (UdsCommand::ClearDiagnosticInformation, &[0x14]),
+    (UdsCommand::ReadDTCInformation, &[0x19]),
+    (UdsCommand::ReadDataByIdentifier, &[0x22]),
+    (UdsCommand::ReadMemoryByAddress, &[0x23]),
+    (UdsCommand::ReadScalingDataByIdentifier, &[0x24]),
+    (UdsCommand::SecurityAccess, &[0x27]),
+    (UdsCommand::CommunicationControl, &[0x28]),
+    (UdsCommand::Authentication, &[0x29]),
+    (UdsCommand::ReadDataByPeriodicIdentifier, &[0x2A]),
+    (UdsCommand::DynamicallyDefineDataIdentifier, &[0x2C]),
+    (UdsCommand::WriteDataByIdentifier, &[0x2E]),
+    (UdsCommand::InputOutputControlByIdentifier, &[0x2F]),
+    (UdsCommand::RoutineControl, &[0x31]),
+    (UdsCommand::RequestDownload, &[0x34]),
+    (UdsCommand::RequestUpload, &[0x35]),
+    (UdsCommand::TransferData, &[0x36]),
+    (UdsCommand::RequestFileTransfer, &[0x38]),
+    (UdsCommand::WriteMemoryByAddress, &[0x3D]),
+    (UdsCommand::TesterPresent, &[0x3E, 0x00]),
+    (UdsCommand::SecuredDataTransmission, &[0x84]),
+    (UdsCommand::ControlDTCSetting, &[0x85]),
+    (UdsCommand::ResponseOnEvent, &[0x86]),
+    (UdsCommand::LinkControl, &[0x87]),
+];
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service IDs: all but 0x11 and 0x37 - Probe the supported services
+    /// Description:
+    ///     The function will send a harmless request to each service (see `PROBES`) and
+    ///     classify the response on its raw NRC: only serviceNotSupported (NRC 0x11) means
+    ///     the service isn't supported, any other response means it is, subFunctionNotSupported
+    ///     (NRC 0x12) included. A service supported in another session only (NRC 0x7F) is
+    ///     listed as supported. Services whose request fails otherwise (e.g. a timeout) aren't
+    ///     listed.
+    /// Returns:
+    ///     The supported services, in SID order.
+    pub async fn probe_services(&mut self) -> Vec<UdsCommand> {
+        let mut supported = Vec::new();
+        for (service, request) in PROBES {
+            match self.raw_request(request).await {
+                // NRC 0x11 and 0x12 are both returned as `NotSupported`, see `ResponseSlot`
+                Err(DiagError::NotSupported)
+                    if self.not_supported_nrc() == u8::from(UdsError::ServiceNotSupported) => {}
+                Ok(_) | Err(DiagError::ECUError { .. } | DiagError::NotSupported) => {
+                    supported.push(*service)
+                }
+                Err(e) => log_debug!("UDS: probe of {:?} failed: {}", service, e),
+            }
+        }
+        log_debug!("UDS: supported services {:?}", supported);
+        supported
+    }
+}
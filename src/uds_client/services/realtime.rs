@@ -1,16 +1,25 @@
-//!  Provides methods to reset the ECU that includes soft-reset, hard-reset, ...
+//!  Provides methods to read data identifiers periodically: ReadDataByPeriodicIdentifier (0x2A),
+//!  decoding the periodic messages of each DID with the `PeriodicParsers`.
 //!
 
+use std::collections::HashMap;
+
+use crate::logging::{log_debug, log_warn};
 use crate::{
     socket_can::CanSocketTx,
     uds_client::{
         DiagError, PciByte, Response, UdsClient,
-        frame::{UdsFlowControlFrame, UdsFrame},
+        client::positive_response_params,
+        frame::{UdsFrame, hex_dump},
     },
 };
 use automotive_diag::uds::UdsCommand;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Positive response SID of the periodic messages (0x2A + 0x40).
+const PERIODIC_RESPONSE_SID: u8 = 0x6A;
 
-/// Reset ECU subcommand
+/// ReadDataByPeriodicIdentifier transmission mode (ISO 14229-1)
 #[repr(u8)]
 #[derive(Debug)]
 pub enum RealTimeType {
@@ -39,6 +48,72 @@ impl TryFrom<i32> for RealTimeType {
     }
 }
 
+/// Parser turning the data of a periodic message into a typed sample.
+type PeriodicParser<M> = Box<dyn Fn(&[u8]) -> Result<M, DiagError> + Send + Sync>;
+
+/// A typed sample of periodic data, see `UdsClient::read_periodic_data`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodicSample<M> {
+    pub did: u8,  // Periodic DID of the message
+    pub value: M, // Value returned by the parser of the DID
+}
+
+/// Registry of the parser of each periodic DID.
+///
+/// Each periodic DID has a known data layout: the registered parser turns the data of its
+/// periodic messages (after the DID byte) into a value of type `M`, usually an enum with a
+/// variant per periodic DID.
+pub struct PeriodicParsers<M> {
+    parsers: HashMap<u8, PeriodicParser<M>>,
+}
+
+impl<M> Default for PeriodicParsers<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> PeriodicParsers<M> {
+    /// Create a registry without any parser.
+    pub fn new() -> Self {
+        Self {
+            parsers: HashMap::new(),
+        }
+    }
+
+    /// Register (or replace) the parser of the periodic DID `did`.
+    pub fn register<F>(&mut self, did: u8, parser: F) -> &mut Self
+    where
+        F: Fn(&[u8]) -> Result<M, DiagError> + Send + Sync + 'static,
+    {
+        self.parsers.insert(did, Box::new(parser));
+        self
+    }
+
+    /// Remove the parser of `did` from the registry.
+    pub fn unregister(&mut self, did: u8) -> &mut Self {
+        self.parsers.remove(&did);
+        self
+    }
+
+    /// Returns whether a parser is registered for `did`.
+    pub fn contains(&self, did: u8) -> bool {
+        self.parsers.contains_key(&did)
+    }
+
+    /// Parse a periodic message: `0x6A`, the periodic DID and its data.
+    ///
+    /// Returns `None` for a message of a DID without parser, `DiagError::InvalidResponseData`
+    /// for a message that isn't periodic data, or the error returned by the parser.
+    pub fn parse(&self, message: &[u8]) -> Option<Result<PeriodicSample<M>, DiagError>> {
+        let [PERIODIC_RESPONSE_SID, did, data @ ..] = message else {
+            return Some(Err(DiagError::InvalidResponseData));
+        };
+        let parser = self.parsers.get(did)?;
+        Some(parser(data).map(|value| PeriodicSample { did: *did, value }))
+    }
+}
+
 #[allow(dead_code)]
 impl<T: CanSocketTx> UdsClient<'_, T> {
     /// Service ID: 0x2A - Data Transmission
@@ -46,7 +121,7 @@ impl<T: CanSocketTx> UdsClient<'_, T> {
     /// Description:
     ///     The function will request an Realtime data sent from ECU with slow rate.
     pub async fn uds_real_time_data_slow(&mut self) -> Result<(), DiagError> {
-        dbg!("UDS: send realtime data request (slow mode)");
+        log_debug!("UDS: send realtime data request (slow mode)");
         let pci_byte = PciByte::new(crate::uds_client::PciType::SingleFrame, 3);
         let re = self
             .send_command_with_response(
@@ -64,7 +139,7 @@ impl<T: CanSocketTx> UdsClient<'_, T> {
     /// Description:
     ///     The function will request an Realtime data sent from ECU with medium rate.
     pub async fn uds_real_time_data_medium(&mut self) -> Result<(), DiagError> {
-        dbg!("UDS: send realtime data request (medium mode)");
+        log_debug!("UDS: send realtime data request (medium mode)");
         let pci_byte = PciByte::new(crate::uds_client::PciType::SingleFrame, 3);
         let re = self
             .send_command_with_response(
@@ -82,7 +157,7 @@ impl<T: CanSocketTx> UdsClient<'_, T> {
     /// Description:
     ///     The function will request an Realtime data sent from ECU with fast rate.
     pub async fn uds_real_time_data_fast(&mut self) -> Result<(), DiagError> {
-        dbg!("UDS: send realtime data request (fast mode)");
+        log_debug!("UDS: send realtime data request (fast mode)");
         let pci_byte = PciByte::new(crate::uds_client::PciType::SingleFrame, 3);
         let re = self
             .send_command_with_response(
@@ -95,12 +170,62 @@ impl<T: CanSocketTx> UdsClient<'_, T> {
         Ok(())
     }
 
+    /// Service ID: 0x2A - Data Transmission
+    ///     Sub-ID: `rate`
+    /// Description:
+    ///     The function will request the periodic DIDs `dids` at `rate`, and send a
+    ///     `PeriodicSample` parsed with `parsers` for each periodic message received.
+    ///     Messages of a DID without parser, or that the parser rejects, are logged and
+    ///     skipped. The stream ends as `uds_real_time_data_slow` does, or when the receiver
+    ///     of `samples` is dropped. `RealTimeType::Stop` or empty `dids` return
+    ///     `DiagError::ParameterInvalid`.
+    pub async fn read_periodic_data<M>(
+        &mut self,
+        rate: RealTimeType,
+        dids: &[u8],
+        parsers: &PeriodicParsers<M>,
+        samples: UnboundedSender<PeriodicSample<M>>,
+    ) -> Result<(), DiagError> {
+        if matches!(rate, RealTimeType::Stop) || dids.is_empty() {
+            return Err(DiagError::ParameterInvalid);
+        }
+        log_debug!(
+            "UDS: send periodic data request {:?} of {:02X?}",
+            rate,
+            dids
+        );
+        let mut args = vec![rate.into()];
+        args.extend_from_slice(dids);
+        let pci_byte = PciByte::new(
+            crate::uds_client::PciType::SingleFrame,
+            args.len() as u8 + 1,
+        );
+        let re = self
+            .send_command_with_response(pci_byte, UdsCommand::ReadDataByPeriodicIdentifier, &args)
+            .await?;
+        self.periodic_data_stream(re, |message| match parsers.parse(message) {
+            Some(Ok(sample)) => samples.send(sample).is_ok(),
+            Some(Err(_e)) => {
+                log_warn!("UDS: invalid periodic data {}: {:?}", hex_dump(message), _e);
+                true
+            }
+            None => {
+                log_debug!("UDS: periodic data without parser: {}", hex_dump(message));
+                true
+            }
+        })
+        .await
+    }
+
     /// Service ID: 0x2A - Data Transmission
     ///     Sub-ID: 0x04
     /// Description:
     ///     The function will send a stop event for realtime data from ECU.
+    ///     Periodic frames still queued in the response slot are flushed before and after
+    ///     the stop request so they are not mistaken for the next response.
     pub async fn uds_real_time_data_stop(&mut self) -> Result<(), DiagError> {
-        dbg!("UDS: stop realtime data");
+        log_debug!("UDS: stop realtime data");
+        self.flush_responses().await;
         let pci_byte = PciByte::new(crate::uds_client::PciType::SingleFrame, 3);
         self.send_command_with_response(
             pci_byte,
@@ -108,48 +233,105 @@ impl<T: CanSocketTx> UdsClient<'_, T> {
             &[0x04, 0xB0],
         )
         .await?;
+        self.flush_responses().await;
+        Ok(())
+    }
+
+    /// Service ID: 0x2A - Data Transmission
+    ///     Sub-ID: 0x04
+    /// Description:
+    ///     The function will stop the transmission of all the periodic DIDs: the stop request
+    ///     is sent without any DID. Periodic messages received before the positive response
+    ///     are skipped, and those still queued in the response slot are flushed before and
+    ///     after the request.
+    pub async fn stop_all_periodic(&mut self) -> Result<(), DiagError> {
+        log_debug!("UDS: stop all periodic data");
+        self.flush_responses().await;
+        let pci_byte = PciByte::new(crate::uds_client::PciType::SingleFrame, 2);
+        let mut response = self
+            .send_command_with_response(
+                pci_byte,
+                UdsCommand::ReadDataByPeriodicIdentifier,
+                &[RealTimeType::Stop.into()],
+            )
+            .await?;
+        // A periodic message sent before the ECU handled the request carries its DID and data
+        while matches!(response, UdsFrame::First(_))
+            || !positive_response_params(&response, UdsCommand::ReadDataByPeriodicIdentifier)?
+                .is_empty()
+        {
+            response = match self.receive().await {
+                Response::Ok(frame, _) => self.complete_response(frame).await?,
+                Response::Error(e) => return Err(e),
+            };
+        }
+        self.flush_responses().await;
         Ok(())
     }
 
     /// Process the realtime data transfer from ECU
     async fn real_time_data_process(&mut self, response: UdsFrame) -> Result<(), DiagError> {
-        let mut remain;
-        if let UdsFrame::First(frame) = response {
-            let flow_ctrl = UdsFlowControlFrame::new(0x00, 0x00, 0x7F, Vec::new()).unwrap();
-            self.send_frame(UdsFrame::FlowControl(flow_ctrl)).await?;
-
-            remain = frame.size as usize - frame.payload.len();
-            let mut pre_idx = 0;
-            while let Response::Ok(uds_frame) = self.receive().await {
-                match uds_frame {
-                    UdsFrame::Consecutive(frame) => {
-                        remain -= frame.payload.len();
-                        if frame.seq_num != if pre_idx == 15 { 0 } else { pre_idx + 1 } {
-                            return Err(DiagError::InvalidResponseLength);
-                        }
-                        pre_idx = frame.seq_num;
-                    }
-                    UdsFrame::First(frame) => {
-                        let flow_ctrl =
-                            UdsFlowControlFrame::new(0x00, 0x00, 0x7F, Vec::new()).unwrap();
-                        self.send_frame(UdsFrame::FlowControl(flow_ctrl)).await?;
-                        remain = frame.size as usize - frame.payload.len();
-                        pre_idx = 0;
-                    }
-                    _ => {}
-                }
-            }
-        } else {
+        if !matches!(response, UdsFrame::First(_)) {
             return Err(DiagError::WrongPciType {
                 want: crate::PciType::FirstFrame,
                 received: response.pci_type(),
             });
         }
+        self.periodic_data_stream(response, |_message| {
+            log_debug!("UDS: periodic data: {}", hex_dump(_message));
+            true
+        })
+        .await
+    }
 
-        if remain == 0 {
-            Ok(())
-        } else {
-            Err(DiagError::InvalidResponseLength)
+    /// Internal function: give each periodic message (`0x6A`, the periodic DID and its data)
+    /// to `on_message`, until it returns `false`.
+    ///
+    /// `response` is the first periodic message, already completed by
+    /// `send_command_with_response`. Each following message is completed with
+    /// `receive_payload`: the Flow Control frames sent back to the ECU use the block size and
+    /// separation time configured with `set_flow_control`. The loop ends when the ECU stops
+    /// sending, or immediately when the token from `periodic_stop_token` is cancelled: the ECU
+    /// is then told to stop with `stop_all_periodic`.
+    async fn periodic_data_stream(
+        &mut self,
+        response: UdsFrame,
+        mut on_message: impl FnMut(&[u8]) -> bool,
+    ) -> Result<(), DiagError> {
+        let first = match response {
+            UdsFrame::First(frame) => {
+                let mut message = vec![frame.sid];
+                message.extend_from_slice(&frame.payload);
+                message
+            }
+            frame => self.receive_payload(frame).await?,
+        };
+        if !on_message(&first) {
+            return Ok(());
         }
+
+        let cancel = self.periodic_stop_token();
+        // The periodic frames don't answer a request, accept all of them while streaming
+        self.clear_request();
+        loop {
+            let uds_frame = tokio::select! {
+                _ = cancel.cancelled() => {
+                    log_debug!("UDS: periodic data read cancelled");
+                    self.reset_periodic_token();
+                    return self.stop_all_periodic().await;
+                }
+                response = self.receive() => match response {
+                    Response::Ok(uds_frame, _) => uds_frame,
+                    Response::Error(_) => break,
+                },
+            };
+            if matches!(uds_frame, UdsFrame::First(_) | UdsFrame::Single(_)) {
+                let message = self.receive_payload(uds_frame).await?;
+                if !on_message(&message) {
+                    break;
+                }
+            }
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,135 @@
+//!  Provides methods to start, stop and get the results of ECU routines: RoutineControl (0x31),
+//!  and the decoding of the results of the common routines (ISO 14229-1 Annex F).
+//!
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient, client::positive_response_data},
+};
+use automotive_diag::uds::UdsCommand;
+
+/// Routine identifier: eraseMemory
+pub const ROUTINE_ERASE_MEMORY: u16 = 0xFF00;
+/// Routine identifier: checkProgrammingDependencies
+pub const ROUTINE_CHECK_PROGRAMMING_DEPENDENCIES: u16 = 0xFF01;
+
+/// Routine Control sub-function (ISO 14229-1)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoutineControlType {
+    StartRoutine = 0x01,          // Start the routine
+    StopRoutine = 0x02,           // Stop the running routine
+    RequestRoutineResults = 0x03, // Get the results of the routine
+}
+
+impl From<RoutineControlType> for u8 {
+    fn from(kind: RoutineControlType) -> Self {
+        kind as u8
+    }
+}
+
+impl TryFrom<u8> for RoutineControlType {
+    type Error = DiagError;
+    fn try_from(kind: u8) -> Result<Self, Self::Error> {
+        match kind {
+            0x01 => Ok(RoutineControlType::StartRoutine),
+            0x02 => Ok(RoutineControlType::StopRoutine),
+            0x03 => Ok(RoutineControlType::RequestRoutineResults),
+            _ => Err(DiagError::ParameterInvalid),
+        }
+    }
+}
+
+/// The typed outcome of a routine, decoded from its routineStatusRecord.
+///
+/// The common routines report their outcome in the first byte of the record: 0x00 when the
+/// routine succeeded, a manufacturer specific failure code otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoutineResult {
+    /// eraseMemory (0xFF00): memory erased, or the failure code
+    EraseMemory(Result<(), u8>),
+    /// checkProgrammingDependencies (0xFF01): dependencies valid, or the failure code
+    CheckProgrammingDependencies(Result<(), u8>),
+    /// A routine without a known decoder: the raw routineStatusRecord
+    Other { routine_id: u16, status: Vec<u8> },
+}
+
+impl RoutineResult {
+    /// Decode the routineStatusRecord `status` of `routine_id`.
+    ///
+    /// The record of a known routine must hold at least its outcome byte, trailing
+    /// manufacturer specific bytes are ignored: `DiagError::InvalidResponseLength` is returned
+    /// for an empty record.
+    pub fn decode(routine_id: u16, status: &[u8]) -> Result<Self, DiagError> {
+        let outcome = || match status.first() {
+            Some(0x00) => Ok(Ok(())),
+            Some(code) => Ok(Err(*code)),
+            None => Err(DiagError::InvalidResponseLength),
+        };
+        Ok(match routine_id {
+            ROUTINE_ERASE_MEMORY => RoutineResult::EraseMemory(outcome()?),
+            ROUTINE_CHECK_PROGRAMMING_DEPENDENCIES => {
+                RoutineResult::CheckProgrammingDependencies(outcome()?)
+            }
+            _ => RoutineResult::Other {
+                routine_id,
+                status: status.to_vec(),
+            },
+        })
+    }
+}
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x31 - Routine Control
+    ///     Sub-ID: `kind`
+    /// Description:
+    ///     The function will start, stop or get the results of the routine `routine_id`, with
+    ///     the routineControlOptionRecord `options` (e.g. the memory area to erase). The response
+    ///     is always requested, whatever `set_suppress_positive_response`.
+    /// Returns:
+    ///     The routineStatusRecord, possibly empty. A response echoing another sub-function
+    ///     returns `DiagError::InvalidResponseData`, another routine
+    ///     `DiagError::MismatchedIdentResponse`.
+    pub async fn routine_control(
+        &mut self,
+        kind: RoutineControlType,
+        routine_id: u16,
+        options: &[u8],
+    ) -> Result<Vec<u8>, DiagError> {
+        log_debug!("UDS: routine control {:?} of 0x{:04X}", kind, routine_id);
+        let mut request = vec![UdsCommand::RoutineControl as u8, kind.into()];
+        request.extend_from_slice(&routine_id.to_be_bytes());
+        request.extend_from_slice(options);
+        let response = self.raw_request(&request).await?;
+
+        let data = positive_response_data(&response, UdsCommand::RoutineControl)?;
+        let [sub_function, high, low, status @ ..] = data else {
+            return Err(DiagError::InvalidResponseLength);
+        };
+        if *sub_function != u8::from(kind) {
+            return Err(DiagError::InvalidResponseData);
+        }
+        let received = u16::from_be_bytes([*high, *low]);
+        if received != routine_id {
+            return Err(DiagError::MismatchedIdentResponse {
+                want: routine_id,
+                received,
+            });
+        }
+        Ok(status.to_vec())
+    }
+
+    /// Service ID: 0x31 - Routine Control
+    ///     Sub-ID: 0x03 (requestRoutineResults)
+    /// Description:
+    ///     The function will get the results of the routine `routine_id` and decode them, see
+    ///     `RoutineResult::decode`.
+    pub async fn routine_results(&mut self, routine_id: u16) -> Result<RoutineResult, DiagError> {
+        let status = self
+            .routine_control(RoutineControlType::RequestRoutineResults, routine_id, &[])
+            .await?;
+        RoutineResult::decode(routine_id, &status)
+    }
+}
@@ -0,0 +1,81 @@
+//!  Provides methods to unlock the secured services of the ECU: SecurityAccess (0x27).
+//!
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient, client::positive_response_data},
+};
+use automotive_diag::uds::UdsCommand;
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x27 - Security Access
+    ///     Sub-ID: `level` (requestSeed, an odd value)
+    /// Description:
+    ///     The function will request the seed of the security level `level`. An even `level`
+    ///     returns `DiagError::ParameterInvalid`.
+    /// Returns:
+    ///     The seed. A seed of zeros means the level is already unlocked.
+    pub async fn security_access_request_seed(&mut self, level: u8) -> Result<Vec<u8>, DiagError> {
+        if level & 0x01 == 0 {
+            return Err(DiagError::ParameterInvalid);
+        }
+        let response = self
+            .raw_request(&[UdsCommand::SecurityAccess as u8, level])
+            .await?;
+        security_access_record(&response, level).map(<[u8]>::to_vec)
+    }
+
+    /// Service ID: 0x27 - Security Access
+    ///     Sub-ID: `level` + 1 (sendKey)
+    /// Description:
+    ///     The function will send the `key` computed from the seed of the security level `level`
+    ///     (the requestSeed sub-function). A wrong key is rejected by the ECU with NRC 0x35
+    ///     invalidKey, returned as `DiagError::ECUError`.
+    pub async fn security_access_send_key(
+        &mut self,
+        level: u8,
+        key: &[u8],
+    ) -> Result<(), DiagError> {
+        if level & 0x01 == 0 || level == 0x7F {
+            return Err(DiagError::ParameterInvalid);
+        }
+        let mut request = vec![UdsCommand::SecurityAccess as u8, level + 1];
+        request.extend_from_slice(key);
+        let response = self.raw_request(&request).await?;
+        security_access_record(&response, level + 1)?;
+        Ok(())
+    }
+
+    /// Service ID: 0x27 - Security Access
+    ///     Sub-ID: `level`, then `level` + 1
+    /// Description:
+    ///     The function will unlock the security level `level`: the seed is requested and the
+    ///     key computed by `key_fn(seed)` is sent. A seed of zeros means the level is already
+    ///     unlocked, no key is sent then.
+    pub async fn security_access(
+        &mut self,
+        level: u8,
+        key_fn: impl FnOnce(&[u8]) -> Vec<u8>,
+    ) -> Result<(), DiagError> {
+        let seed = self.security_access_request_seed(level).await?;
+        if seed.iter().all(|byte| *byte == 0) {
+            log_debug!("UDS: security level 0x{:02X} already unlocked", level);
+            return Ok(());
+        }
+        let key = key_fn(&seed);
+        self.security_access_send_key(level, &key).await
+    }
+}
+
+/// Check that `response` is the positive response to the security access `sub_function` and
+/// return the record following it (the seed of a requestSeed).
+fn security_access_record(response: &[u8], sub_function: u8) -> Result<&[u8], DiagError> {
+    let data = positive_response_data(response, UdsCommand::SecurityAccess)?;
+    match data.split_first() {
+        Some((received, record)) if *received == sub_function => Ok(record),
+        Some(_) => Err(DiagError::InvalidResponseData),
+        None => Err(DiagError::InvalidResponseLength),
+    }
+}
@@ -0,0 +1,236 @@
+//!  Provides methods to switch the diagnostic session of the ECU: DiagnosticSessionControl (0x10),
+//!  optionally applying the session timing of the ECU, and `enter_programming_session` running the sequence required before flashing.
+//!
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{
+        DiagError, Response, UdsClient,
+        client::{positive_response_data, positive_response_params},
+        services::communication::{CommunicationControlType, NORMAL_COMMUNICATION_MESSAGES},
+        services::dtc::DtcSettingType,
+    },
+};
+use automotive_diag::uds::UdsCommand;
+use std::time::Duration;
+
+/// Margin added to the P2 timing of the ECU for the bus and the tester latencies
+/// (ISO 14229-2 P2_client_max = P2_server_max + ΔP2).
+pub(crate) const P2_CLIENT_MARGIN: Duration = Duration::from_millis(50);
+
+/// Time to wait for the response an ECU may send despite the suppressPosRspMsgIndicationBit:
+/// the default P2_server_max.
+const SUPPRESSED_RESPONSE_GRACE: Duration = Duration::from_millis(50);
+
+/// Diagnostic Session Control sub-function (ISO 14229-1)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticSessionType {
+    DefaultSession = 0x01,                // Session active after power on
+    ProgrammingSession = 0x02,            // Session used to flash the ECU
+    ExtendedDiagnosticSession = 0x03,     // Session unlocking the extended services
+    SafetySystemDiagnosticSession = 0x04, // Session testing the safety systems
+}
+
+impl From<DiagnosticSessionType> for u8 {
+    fn from(session: DiagnosticSessionType) -> Self {
+        session as u8
+    }
+}
+
+impl TryFrom<u8> for DiagnosticSessionType {
+    type Error = DiagError;
+    fn try_from(session: u8) -> Result<Self, Self::Error> {
+        match session {
+            0x01 => Ok(DiagnosticSessionType::DefaultSession),
+            0x02 => Ok(DiagnosticSessionType::ProgrammingSession),
+            0x03 => Ok(DiagnosticSessionType::ExtendedDiagnosticSession),
+            0x04 => Ok(DiagnosticSessionType::SafetySystemDiagnosticSession),
+            _ => Err(DiagError::ParameterInvalid),
+        }
+    }
+}
+
+/// The timing parameters of a diagnostic session, sent by the ECU in its positive response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionTiming {
+    pub p2: Duration,      // P2_server_max: maximum time until the response
+    pub p2_star: Duration, // P2*_server_max: maximum time until the response after a 0x78
+}
+
+impl SessionTiming {
+    /// Decode a timing record: P2 with a 1ms resolution and P2* with a 10ms resolution, both
+    /// on 2 bytes. Bytes following the record are ignored.
+    pub(crate) fn decode(record: &[u8]) -> Result<Self, DiagError> {
+        let &[p2_high, p2_low, p2_star_high, p2_star_low, ..] = record else {
+            return Err(DiagError::InvalidResponseLength);
+        };
+        Ok(Self {
+            p2: Duration::from_millis(u16::from_be_bytes([p2_high, p2_low]) as u64),
+            p2_star: Duration::from_millis(
+                u16::from_be_bytes([p2_star_high, p2_star_low]) as u64 * 10,
+            ),
+        })
+    }
+
+    /// Encode the timing record read by `decode`, P2* being rounded down to 10ms. A P2 over
+    /// 65535ms or a P2* over 655350ms returns `DiagError::ParameterInvalid`.
+    pub(crate) fn encode(&self) -> Result<[u8; 4], DiagError> {
+        let p2 = u16::try_from(self.p2.as_millis()).map_err(|_| DiagError::ParameterInvalid)?;
+        let p2_star = u16::try_from(self.p2_star.as_millis() / 10)
+            .map_err(|_| DiagError::ParameterInvalid)?;
+        let [p2_high, p2_low] = p2.to_be_bytes();
+        let [p2_star_high, p2_star_low] = p2_star.to_be_bytes();
+        Ok([p2_high, p2_low, p2_star_high, p2_star_low])
+    }
+}
+
+/// The optional steps of `enter_programming_session_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgrammingOptions {
+    pub security_level: u8,          // requestSeed sub-function to unlock
+    pub disable_dtc_setting: bool,   // ControlDTCSetting off
+    pub disable_communication: bool, // CommunicationControl: Rx and Tx off
+}
+
+impl Default for ProgrammingOptions {
+    fn default() -> Self {
+        Self {
+            security_level: 0x01,
+            disable_dtc_setting: false,
+            disable_communication: false,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x10 - Diagnostic Session Control
+    ///     Sub-ID: `session`
+    /// Description:
+    ///     The function will switch the ECU to the diagnostic session `session`.
+    ///     A response for another session returns `DiagError::InvalidResponseData`.
+    ///     When positive responses are suppressed, some ECUs ignore the
+    ///     suppressPosRspMsgIndicationBit on this service and answer anyway. The response is
+    ///     then optional: it is awaited for a short grace period only (50ms, the default P2),
+    ///     and no response is not an error. A response received in time is checked and
+    ///     consumed, so it can't be taken for the response to the next request. A negative
+    ///     response is always sent, and returned as `DiagError::ECUError`.
+    pub async fn diagnostic_session_control(
+        &mut self,
+        session: DiagnosticSessionType,
+    ) -> Result<(), DiagError> {
+        log_debug!("UDS: switch to {:?}", session);
+        let response = match self
+            .send_sub_function_command(UdsCommand::DiagnosticSessionControl, session.into(), &[])
+            .await?
+        {
+            Some(response) => response,
+            None => match self
+                .receive_matching(
+                    UdsCommand::DiagnosticSessionControl as u8,
+                    SUPPRESSED_RESPONSE_GRACE,
+                )
+                .await
+            {
+                Response::Ok(response, _) => {
+                    log_debug!("UDS: the ECU answered a suppressed session control");
+                    response
+                }
+                Response::Error(DiagError::Timeout) => return Ok(()),
+                Response::Error(e) => return Err(e),
+            },
+        };
+
+        let params = positive_response_params(&response, UdsCommand::DiagnosticSessionControl)?;
+        // The echo of a suppressed request may keep the suppressPosRspMsgIndicationBit
+        match params.first() {
+            Some(received) if *received & 0x7F == u8::from(session) => Ok(()),
+            Some(_) => Err(DiagError::InvalidResponseData),
+            None => Err(DiagError::InvalidResponseLength),
+        }
+    }
+
+    /// Service ID: 0x10 - Diagnostic Session Control
+    ///     Sub-ID: `session`
+    /// Description:
+    ///     The function will switch the ECU to the diagnostic session `session` and decode the
+    ///     session timing of its response (the response is always requested, whatever
+    ///     `set_suppress_positive_response`).
+    ///     The response slot is reconfigured to match: the response timeout to P2 and the
+    ///     timeout following a responsePending to P2*, each plus a 50ms margin. With
+    ///     `keep_timeouts`, the timeouts configured on the slot (e.g. a user override) are kept.
+    /// Returns:
+    ///     The session timing sent by the ECU, without the margin.
+    pub async fn session_with_timing(
+        &mut self,
+        session: DiagnosticSessionType,
+        keep_timeouts: bool,
+    ) -> Result<SessionTiming, DiagError> {
+        log_debug!("UDS: switch to {:?}", session);
+        let response = self
+            .raw_request(&[UdsCommand::DiagnosticSessionControl as u8, session.into()])
+            .await?;
+
+        let data = positive_response_data(&response, UdsCommand::DiagnosticSessionControl)?;
+        let (received, record) = data.split_first().ok_or(DiagError::InvalidResponseLength)?;
+        let timing = SessionTiming::decode(record)?;
+        if *received != u8::from(session) {
+            return Err(DiagError::InvalidResponseData);
+        }
+        log_debug!("UDS: {:?} timing {:?}", session, timing);
+
+        if !keep_timeouts {
+            self.set_response_timeouts(
+                timing.p2 + P2_CLIENT_MARGIN,
+                timing.p2_star + P2_CLIENT_MARGIN,
+            );
+        }
+        Ok(timing)
+    }
+
+    /// Service IDs: 0x10, 0x27 - Enter the programming session
+    /// Description:
+    ///     The function will run `enter_programming_session_with` with the default
+    ///     `ProgrammingOptions`: security level 0x01, DTC setting and communication untouched.
+    pub async fn enter_programming_session(
+        &mut self,
+        key_fn: impl FnOnce(&[u8]) -> Vec<u8>,
+    ) -> Result<(), DiagError> {
+        self.enter_programming_session_with(ProgrammingOptions::default(), key_fn)
+            .await
+    }
+
+    /// Service IDs: 0x10, 0x27, 0x85, 0x28 - Enter the programming session
+    /// Description:
+    ///     The function will prepare the ECU for flashing:
+    ///     - switch to the extended diagnostic session,
+    ///     - unlock the security level `options.security_level`, `key_fn` computing the key of
+    ///       the seed (see `security_access`),
+    ///     - if enabled, stop the DTC setting and disable the transmission and reception of the
+    ///       normal communication messages,
+    ///     - switch to the programming session.
+    ///     The sequence stops at the first error, which is returned.
+    pub async fn enter_programming_session_with(
+        &mut self,
+        options: ProgrammingOptions,
+        key_fn: impl FnOnce(&[u8]) -> Vec<u8>,
+    ) -> Result<(), DiagError> {
+        self.diagnostic_session_control(DiagnosticSessionType::ExtendedDiagnosticSession)
+            .await?;
+        self.security_access(options.security_level, key_fn).await?;
+        if options.disable_dtc_setting {
+            self.control_dtc_setting(DtcSettingType::Off).await?;
+        }
+        if options.disable_communication {
+            self.communication_control(
+                CommunicationControlType::DisableRxAndTx,
+                NORMAL_COMMUNICATION_MESSAGES,
+            )
+            .await?;
+        }
+        self.diagnostic_session_control(DiagnosticSessionType::ProgrammingSession)
+            .await
+    }
+}
@@ -0,0 +1,101 @@
+//!  Provides methods to keep the ECU aware that a tester is connected: TesterPresent (0x3E).
+//!
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    logging::{log_debug, log_warn},
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient, UdsResponseParser},
+};
+use automotive_diag::uds::UdsCommand;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+/// TesterPresent sub-function: zeroSubFunction
+const ZERO_SUB_FUNCTION: u8 = 0x00;
+
+/// Functional request ID of the ECUs with 11 bits normal addressing (ISO 15765-4), to send as a
+/// standard CAN ID: see `UdsClient::with_id`.
+pub const FUNCTIONAL_REQUEST_ID: u16 = 0x7DF;
+
+/// Parser of the TesterPresent response, which must echo the zeroSubFunction.
+struct TesterPresentResponse;
+
+impl UdsResponseParser for TesterPresentResponse {
+    type Output = ();
+    const COMMAND: UdsCommand = UdsCommand::TesterPresent;
+
+    fn parse_params(&self, params: &[u8]) -> Result<(), DiagError> {
+        match params {
+            [ZERO_SUB_FUNCTION, ..] => Ok(()),
+            [] => Err(DiagError::InvalidResponseLength),
+            _ => Err(DiagError::InvalidResponseData),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x3E - Tester Present
+    ///     Sub-ID: 0x00 (zeroSubFunction)
+    /// Description:
+    ///     The function will check that the bus and the ECU are alive, e.g. before running a
+    ///     sequence. The positive response is always requested, whatever
+    ///     `set_suppress_positive_response`. An ECU that doesn't answer returns
+    ///     `DiagError::Timeout`.
+    /// Returns:
+    ///     The round-trip time of the request.
+    pub async fn ping(&mut self) -> Result<Duration, DiagError> {
+        let start = Instant::now();
+        self.request_parsed(&TesterPresentResponse, &[ZERO_SUB_FUNCTION])
+            .await?;
+        let rtt = start.elapsed();
+        log_debug!("UDS: ECU answered the ping in {:?}", rtt);
+        Ok(rtt)
+    }
+}
+
+impl<T> UdsClient<'static, T>
+where
+    T: CanSocketTx + Send + Sync + 'static,
+    T::Frame: Send + Sync,
+    T::Error: Send,
+{
+    /// Service ID: 0x3E - Tester Present, functionally addressed
+    ///     Sub-ID: 0x80 (zeroSubFunction, suppressPosRspMsgIndicationBit set)
+    /// Description:
+    ///     The function will spawn a task sending a TesterPresent with the client every
+    ///     `period`, keeping every ECU in its non-default session. The client is created for
+    ///     the functional request ID, e.g. `UdsClient::with_id` with
+    ///     `StandardId::new(FUNCTIONAL_REQUEST_ID)`, on a TX channel of its own (e.g. a clone of
+    ///     the `UdsSocketTx` of the physical client). No ECU responds: the task doesn't wait,
+    ///     and a failed transmit is only logged. The task returns once `cancel` is cancelled,
+    ///     e.g. the token of `UdsRuntime::cancellation_token`.
+    /// Returns:
+    ///     The handle of the task, e.g. for `UdsRuntime::track`.
+    pub fn functional_tester_present_all(
+        mut self,
+        period: Duration,
+        cancel: CancellationToken,
+    ) -> JoinHandle<()> {
+        self.set_suppress_positive_response(true);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = interval.tick() => {}
+                }
+                if let Err(_e) = self
+                    .send_sub_function_command(UdsCommand::TesterPresent, ZERO_SUB_FUNCTION, &[])
+                    .await
+                {
+                    log_warn!("UDS: functional tester present failed: {}", _e);
+                }
+            }
+            log_debug!("UDS: functional tester present stopped");
+        })
+    }
+}
@@ -0,0 +1,127 @@
+//!  Provides methods to read and set the timing parameters of the ECU: AccessTimingParameter
+//!  (0x83).
+//!
+//!  The service is missing from `UdsCommand`: a negative response can't be turned into a
+//!  `DiagError::ECUError` and returns `FrameError::InvalidSid`.
+//!
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient, services::session::P2_CLIENT_MARGIN},
+};
+
+use super::SessionTiming;
+
+/// Service ID of AccessTimingParameter, missing from `UdsCommand`.
+const ACCESS_TIMING_PARAMETER: u8 = 0x83;
+
+/// Access Timing Parameter sub-function (ISO 14229-1)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingParameterAccessType {
+    ReadExtendedTimingParameterSet = 0x01, // Read the extended timing the ECU supports
+    SetTimingParametersToDefaultValues = 0x02, // Go back to the default timing
+    ReadCurrentlyActiveTimingParameters = 0x03, // Read the timing in use
+    SetTimingParametersToGivenValues = 0x04, // Use the timing of the request
+}
+
+impl From<TimingParameterAccessType> for u8 {
+    fn from(access: TimingParameterAccessType) -> Self {
+        access as u8
+    }
+}
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x83 - Access Timing Parameter
+    ///     Sub-ID: 0x03 (readCurrentlyActiveTimingParameters)
+    /// Description:
+    ///     The function will read the P2 and P2* limits the ECU currently uses. The record is
+    ///     expected in the DiagnosticSessionControl layout: P2 (1ms resolution) and P2* (10ms
+    ///     resolution), both on 2 bytes.
+    /// Returns:
+    ///     The active timing of the ECU.
+    pub async fn get_timing_parameters(&mut self) -> Result<SessionTiming, DiagError> {
+        let record = self
+            .access_timing_parameter(
+                TimingParameterAccessType::ReadCurrentlyActiveTimingParameters,
+                &[],
+            )
+            .await?;
+        SessionTiming::decode(&record)
+    }
+
+    /// Service ID: 0x83 - Access Timing Parameter
+    ///     Sub-ID: 0x01 (readExtendedTimingParameterSet)
+    /// Description:
+    ///     The function will read the extended timing the ECU supports, e.g. the fastest P2 to
+    ///     request with `set_timing_parameters`. Same record layout as `get_timing_parameters`.
+    pub async fn read_extended_timing_parameters(&mut self) -> Result<SessionTiming, DiagError> {
+        let record = self
+            .access_timing_parameter(
+                TimingParameterAccessType::ReadExtendedTimingParameterSet,
+                &[],
+            )
+            .await?;
+        SessionTiming::decode(&record)
+    }
+
+    /// Service ID: 0x83 - Access Timing Parameter
+    ///     Sub-ID: 0x04 (setTimingParametersToGivenValues)
+    /// Description:
+    ///     The function will request the ECU to use the timing `timing`. Once accepted, the
+    ///     response slot is reconfigured as by `session_with_timing`: the response timeout to
+    ///     P2 and the timeout following a responsePending to P2*, each plus a 50ms margin.
+    ///     A timing that can't be encoded returns `DiagError::ParameterInvalid`.
+    pub async fn set_timing_parameters(&mut self, timing: SessionTiming) -> Result<(), DiagError> {
+        let record = timing.encode()?;
+        log_debug!("UDS: set timing {:?}", timing);
+        self.access_timing_parameter(
+            TimingParameterAccessType::SetTimingParametersToGivenValues,
+            &record,
+        )
+        .await?;
+        self.set_response_timeouts(
+            timing.p2 + P2_CLIENT_MARGIN,
+            timing.p2_star + P2_CLIENT_MARGIN,
+        );
+        Ok(())
+    }
+
+    /// Service ID: 0x83 - Access Timing Parameter
+    ///     Sub-ID: 0x02 (setTimingParametersToDefaultValues)
+    /// Description:
+    ///     The function will request the ECU to go back to its default timing. The timeouts of
+    ///     the response slot are left unchanged.
+    pub async fn reset_timing_parameters(&mut self) -> Result<(), DiagError> {
+        self.access_timing_parameter(
+            TimingParameterAccessType::SetTimingParametersToDefaultValues,
+            &[],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Send the AccessTimingParameter request `access` with the timing record `record`, and
+    /// return the timing record of the response.
+    async fn access_timing_parameter(
+        &mut self,
+        access: TimingParameterAccessType,
+        record: &[u8],
+    ) -> Result<Vec<u8>, DiagError> {
+        let mut request = vec![ACCESS_TIMING_PARAMETER, access.into()];
+        request.extend_from_slice(record);
+        let response = self.raw_request(&request).await?;
+
+        match response.as_slice() {
+            [sid, received, record @ ..]
+                if *sid == ACCESS_TIMING_PARAMETER | 0x40 && *received == u8::from(access) =>
+            {
+                Ok(record.to_vec())
+            }
+            [_, _, ..] => Err(DiagError::InvalidResponseData),
+            _ => Err(DiagError::InvalidResponseLength),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//!  Provides methods to download data to the ECU and upload data from it: RequestDownload (0x34),
+//!  RequestUpload (0x35), TransferData (0x36), RequestTransferExit (0x37), `transfer_file`
+//!  running the whole flash sequence, and `transfer_data_stream` transferring the data of a
+//!  reader.
+//!
+
+use crate::{
+    logging::log_debug,
+    socket_can::CanSocketTx,
+    uds_client::{DiagError, UdsClient, client::positive_response_data},
+};
+use automotive_diag::uds::UdsCommand;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::encode_addr_and_length;
+
+/// The dataFormatIdentifier of RequestDownload: the compression method (high nibble) and the
+/// encryption method (low nibble) of the transferred data, 0 meaning none. The methods are
+/// vehicle manufacturer specific, each is a 4 bits value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DataFormat {
+    pub compression: u8, // Compression method (0 = not compressed)
+    pub encryption: u8,  // Encryption method (0 = not encrypted)
+}
+
+impl DataFormat {
+    /// Neither compressed nor encrypted.
+    pub const RAW: DataFormat = DataFormat::new(0, 0);
+
+    /// Create a dataFormatIdentifier, the methods being truncated to 4 bits.
+    pub const fn new(compression: u8, encryption: u8) -> Self {
+        Self {
+            compression: compression & 0x0F,
+            encryption: encryption & 0x0F,
+        }
+    }
+}
+
+impl From<DataFormat> for u8 {
+    fn from(format: DataFormat) -> Self {
+        (format.compression & 0x0F) << 4 | (format.encryption & 0x0F)
+    }
+}
+
+impl From<u8> for DataFormat {
+    fn from(format: u8) -> Self {
+        Self::new(format >> 4, format)
+    }
+}
+
+#[allow(dead_code)]
+impl<T: CanSocketTx> UdsClient<'_, T> {
+    /// Service ID: 0x34 - Request Download
+    /// Description:
+    ///     The function will request the download of `size` bytes to the memory address `addr`.
+    ///     `fmt` is the dataFormatIdentifier, a `DataFormat` or its raw byte (0x00: neither
+    ///     compressed nor encrypted). The address and the size are both encoded on 4 bytes.
+    ///     An ECU that doesn't support the format rejects the request (NRC 0x31
+    ///     requestOutOfRange), returned as `DiagError::ECUError`.
+    /// Returns:
+    ///     The maxNumberOfBlockLength advertised by the ECU: the maximum length of a TransferData
+    ///     request, SID and blockSequenceCounter included. A malformed lengthFormatIdentifier
+    ///     returns `DiagError::InvalidResponseData`.
+    pub async fn request_download(
+        &mut self,
+        addr: u32,
+        size: u32,
+        fmt: impl Into<u8>,
+    ) -> Result<usize, DiagError> {
+        let mut request = vec![UdsCommand::RequestDownload as u8, fmt.into()];
+        request.extend_from_slice(&encode_addr_and_length(addr.into(), size.into(), 4, 4)?);
+        let response = self.raw_request(&request).await?;
+
+        let data = positive_response_data(&response, UdsCommand::RequestDownload)?;
+        max_number_of_block_length(data)
+    }
+
+    /// Service ID: 0x35 - Request Upload
+    /// Description:
+    ///     The function will request the upload of `size` bytes from the memory address `addr`,
+    ///     then read with `transfer_data` (each response record holds the next block) and
+    ///     terminated with `request_transfer_exit`. `fmt` is the dataFormatIdentifier, as for
+    ///     `request_download`. The address and the size are both encoded on 4 bytes.
+    /// Returns:
+    ///     The maxNumberOfBlockLength advertised by the ECU: the maximum length of a TransferData
+    ///     response, SID and blockSequenceCounter included. A malformed lengthFormatIdentifier
+    ///     returns `DiagError::InvalidResponseData`.
+    pub async fn request_upload(
+        &mut self,
+        addr: u32,
+        size: u32,
+        fmt: impl Into<u8>,
+    ) -> Result<usize, DiagError> {
+        let mut request = vec![UdsCommand::RequestUpload as u8, fmt.into()];
+        request.extend_from_slice(&encode_addr_and_length(addr.into(), size.into(), 4, 4)?);
+        let response = self.raw_request(&request).await?;
+
+        let data = positive_response_data(&response, UdsCommand::RequestUpload)?;
+        max_number_of_block_length(data)
+    }
+
+    /// Service ID: 0x36 - Transfer Data
+    /// Description:
+    ///     The function will transfer the block `data`, numbered with `block_counter`. The ECU
+    ///     must echo the blockSequenceCounter, `InvalidResponseData` is returned otherwise.
+    /// Returns:
+    ///     The transferResponseParameterRecord, often empty.
+    pub async fn transfer_data(
+        &mut self,
+        block_counter: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, DiagError> {
+        let mut request = vec![UdsCommand::TransferData as u8, block_counter];
+        request.extend_from_slice(data);
+        let response = self.raw_request(&request).await?;
+
+        let data = positive_response_data(&response, UdsCommand::TransferData)?;
+        let (received, record) = data.split_first().ok_or(DiagError::InvalidResponseLength)?;
+        if *received != block_counter {
+            return Err(DiagError::InvalidResponseData);
+        }
+        Ok(record.to_vec())
+    }
+
+    /// Service ID: 0x37 - Request Transfer Exit
+    /// Description:
+    ///     The function will terminate the data transfer.
+    /// Returns:
+    ///     The transferResponseParameterRecord, e.g. a checksum computed by the ECU.
+    ///     Compare it with `Crc::verify`.
+    pub async fn request_transfer_exit(&mut self) -> Result<Vec<u8>, DiagError> {
+        let response = self
+            .raw_request(&[UdsCommand::RequestTransferExit as u8])
+            .await?;
+        positive_response_data(&response, UdsCommand::RequestTransferExit).map(<[u8]>::to_vec)
+    }
+
+    /// Service IDs: 0x34, 0x36, 0x37 - Download `data` to the memory address `addr`
+    /// Description:
+    ///     The function will run the whole flash sequence: RequestDownload with the
+    ///     dataFormatIdentifier `fmt` (a `DataFormat` or its raw byte, `data` being already
+    ///     compressed and encrypted accordingly), TransferData of `data` in blocks as long as
+    ///     the ECU accepts (the blockSequenceCounter starts at 1 and wraps from 0xFF to 0x00), then
+    ///     RequestTransferExit. The transfer stops at the first error, or with
+    ///     `DiagError::TransferAborted` when the token from `transfer_stop_token` is cancelled
+    ///     (see `abort_current_transfer`). The checksum the checkMemory routine of the ECU
+    ///     expects can be computed with `Crc::checksum_bytes`.
+    ///     `progress(bytes_done, bytes_total)` is called before the first block and after
+    ///     each block.
+    pub async fn transfer_file(
+        &mut self,
+        addr: u32,
+        data: &[u8],
+        fmt: impl Into<u8>,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), DiagError> {
+        let size = u32::try_from(data.len()).map_err(|_| DiagError::ParameterInvalid)?;
+        let max_block_length = self.request_download(addr, size, fmt).await?;
+
+        // The SID and the blockSequenceCounter are part of the block length
+        let block_len = max_block_length
+            .checked_sub(2)
+            .filter(|len| *len > 0)
+            .ok_or(DiagError::InvalidResponseData)?;
+        log_debug!(
+            "UDS: download of {} bytes to 0x{:08X}, {} bytes per block",
+            data.len(),
+            addr,
+            block_len
+        );
+
+        progress(0, data.len());
+        let mut done = 0;
+        for (idx, block) in data.chunks(block_len).enumerate() {
+            self.check_transfer_aborted()?;
+            let block_counter = (idx + 1) as u8;
+            self.transfer_data(block_counter, block).await?;
+            done += block.len();
+            progress(done, data.len());
+        }
+        self.request_transfer_exit().await?;
+        Ok(())
+    }
+
+    /// Service ID: 0x36 - Transfer Data, of the data read from `reader`
+    /// Description:
+    ///     The function will transfer the data of `reader` in blocks of `block_len` bytes
+    ///     (maxNumberOfBlockLength minus the SID and the blockSequenceCounter), without
+    ///     holding more than a block in memory: the last block holds the remaining bytes. The
+    ///     blockSequenceCounter starts at 1 and wraps from 0xFF to 0x00. Only the TransferData
+    ///     requests are sent: the caller must send RequestDownload (`request_download`) before
+    ///     and RequestTransferExit (`request_transfer_exit`) after. It stops at the first error,
+    ///     or with `DiagError::TransferAborted` when the token from `transfer_stop_token` is
+    ///     cancelled. A read error returns `DiagError::ReadError` and a `block_len` of 0
+    ///     `DiagError::ParameterInvalid`. `progress(bytes_done)` is called after each block.
+    /// Returns:
+    ///     The number of bytes transferred.
+    pub async fn transfer_data_stream(
+        &mut self,
+        mut reader: impl AsyncRead + Unpin,
+        block_len: usize,
+        mut progress: impl FnMut(usize),
+    ) -> Result<usize, DiagError> {
+        if block_len == 0 {
+            return Err(DiagError::ParameterInvalid);
+        }
+        let mut block = vec![0; block_len];
+        let mut block_counter: u8 = 1;
+        let mut done = 0;
+        loop {
+            // A read may return less than a block before the end of the data
+            let mut len = 0;
+            while len < block_len {
+                match reader.read(&mut block[len..]).await {
+                    Ok(0) => break,
+                    Ok(read) => len += read,
+                    Err(e) => return Err(DiagError::ReadError(e.to_string())),
+                }
+            }
+            if len == 0 {
+                break;
+            }
+            self.check_transfer_aborted()?;
+            self.transfer_data(block_counter, &block[..len]).await?;
+            block_counter = block_counter.wrapping_add(1);
+            done += len;
+            progress(done);
+            if len < block_len {
+                break;
+            }
+        }
+        log_debug!("UDS: transferred {} bytes", done);
+        Ok(done)
+    }
+}
+
+/// Decode the lengthFormatIdentifier and maxNumberOfBlockLength of a RequestDownload or
+/// RequestUpload response. The high nibble of the lengthFormatIdentifier is the length of
+/// maxNumberOfBlockLength, the low nibble is reserved.
+fn max_number_of_block_length(data: &[u8]) -> Result<usize, DiagError> {
+    let (format, data) = data.split_first().ok_or(DiagError::InvalidResponseLength)?;
+    let len = (format >> 4) as usize;
+    if !(1..=8).contains(&len) || format & 0x0F != 0 {
+        return Err(DiagError::InvalidResponseData);
+    }
+    let max_block_length = data
+        .get(..len)
+        .ok_or(DiagError::InvalidResponseLength)?
+        .iter()
+        .fold(0, |length, byte| (length << 8) | *byte as usize);
+    Ok(max_block_length)
+}
@@ -0,0 +1,31 @@
+//! Decoding of the SocketCAN error frames into `CanBusError`.
+#![cfg(target_os = "linux")]
+
+use socketcan::CanErrorFrame;
+use uds_client::CanBusError;
+
+fn decode(error_bits: u32, data: &[u8]) -> CanBusError {
+    CanBusError::from(&CanErrorFrame::new_error(error_bits, data).unwrap())
+}
+
+#[test]
+fn error_classes_are_decoded() {
+    assert_eq!(decode(0x0001, &[]), CanBusError::TransmitTimeout);
+    assert_eq!(decode(0x0002, &[12]), CanBusError::ArbitrationLost(12));
+    assert_eq!(decode(0x0004, &[0, 0x01]), CanBusError::ControllerOverrun);
+    assert_eq!(decode(0x0004, &[0, 0x02]), CanBusError::ControllerOverrun);
+    assert_eq!(decode(0x0004, &[0, 0x10]), CanBusError::ControllerProblem);
+    assert_eq!(decode(0x0020, &[]), CanBusError::NoAck);
+    assert_eq!(decode(0x0040, &[]), CanBusError::BusOff);
+    assert_eq!(decode(0x0100, &[]), CanBusError::Restarted);
+}
+
+#[test]
+fn most_severe_error_class_is_kept() {
+    // Bus-off is usually reported together with a controller problem and a bus error
+    assert_eq!(
+        decode(0x0004 | 0x0040 | 0x0080, &[0, 0x20]),
+        CanBusError::BusOff
+    );
+    assert_eq!(decode(0x0008 | 0x0080, &[]), CanBusError::ProtocolViolation);
+}
@@ -0,0 +1,74 @@
+//! Bus-off detection on transmit and the optional controller restart.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockError, MockFrame};
+use uds_client::{CanSocketTx, DiagError, ResponseSlot, UdsClient};
+
+/// A channel whose controller is bus-off until it is restarted.
+struct BusOffChannel {
+    bus_off: bool,
+    can_restart: bool,
+}
+
+impl BusOffChannel {
+    fn new(can_restart: bool) -> Self {
+        Self {
+            bus_off: true,
+            can_restart,
+        }
+    }
+}
+
+impl CanSocketTx for BusOffChannel {
+    type Frame = MockFrame;
+    type Error = MockError;
+
+    async fn transmit(&mut self, _frame: &MockFrame) -> nb::Result<Option<MockFrame>, MockError> {
+        if self.bus_off {
+            return Err(nb::Error::Other(MockError));
+        }
+        Ok(None)
+    }
+
+    fn is_bus_off(_error: &MockError) -> bool {
+        true
+    }
+
+    fn restart(&mut self) -> bool {
+        if self.can_restart {
+            self.bus_off = false;
+        }
+        self.can_restart
+    }
+}
+
+#[tokio::test]
+async fn bus_off_is_reported() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(BusOffChannel::new(true), 0x7E0, &slot);
+
+    let error = client.send_command(0x02, 0x3E, &[0x00]).await.unwrap_err();
+    assert!(matches!(error, DiagError::BusOff));
+}
+
+#[tokio::test]
+async fn recovery_restarts_the_controller_and_transmits_again() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(BusOffChannel::new(true), 0x7E0, &slot);
+    client.set_bus_off_recovery(true);
+
+    client.send_command(0x02, 0x3E, &[0x00]).await.unwrap();
+}
+
+#[tokio::test]
+async fn failed_restart_is_reported_as_bus_off() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(BusOffChannel::new(false), 0x7E0, &slot);
+    client.set_bus_off_recovery(true);
+
+    let error = client.send_command(0x02, 0x3E, &[0x00]).await.unwrap_err();
+    assert!(matches!(error, DiagError::BusOff));
+}
@@ -0,0 +1,61 @@
+//! Requests repeated while the ECU answers busyRepeatRequest (NRC 0x21).
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use automotive_diag::uds::UdsError;
+use common::{MockChannel, answer};
+use uds_client::{DiagError, ResponseSlot, UdsClient};
+
+/// ReadDataByIdentifier of the DID 0xF186.
+const REQUEST: [u8; 4] = [0x03, 0x22, 0xF1, 0x86];
+
+#[tokio::test]
+async fn busy_request_is_repeated_until_answered() {
+    let mut script = answer(&[0x7F, 0x22, 0x21]);
+    script.extend(answer(&[0x7F, 0x22, 0x21]));
+    script.extend(answer(&[0x62, 0xF1, 0x86, 0x03]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_busy_retry(2, Duration::from_millis(5));
+
+    assert_eq!(
+        client.read_data_by_identifier(0xF186).await.unwrap(),
+        [0x03]
+    );
+    // The request is sent again byte for byte
+    assert_eq!(*sent.lock().unwrap(), vec![REQUEST.to_vec(); 3]);
+}
+
+#[tokio::test]
+async fn busy_error_is_returned_once_the_retries_are_exhausted() {
+    let script = (0..3).flat_map(|_| answer(&[0x7F, 0x22, 0x21])).collect();
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_busy_retry(2, Duration::from_millis(5));
+
+    let error = client.read_data_by_identifier(0xF186).await.unwrap_err();
+    assert!(matches!(
+        error,
+        DiagError::ECUError { code, .. } if code == UdsError::BusyRepeatRequest
+    ));
+    assert_eq!(*sent.lock().unwrap(), vec![REQUEST.to_vec(); 3]);
+}
+
+#[tokio::test]
+async fn busy_request_is_not_repeated_by_default() {
+    let mut script = answer(&[0x7F, 0x22, 0x21]);
+    script.extend(answer(&[0x62, 0xF1, 0x86, 0x03]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    assert!(client.read_data_by_identifier(0xF186).await.is_err());
+    assert_eq!(sent.lock().unwrap().len(), 1);
+}
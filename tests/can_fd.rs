@@ -0,0 +1,99 @@
+//! Segmentation of the requests on CAN FD, packing each frame up to the TX_DL.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::MockChannel;
+use uds_client::{DiagError, ResponseSlot, UdsClient};
+
+/// Flow Control (Continue To Send, no block size, no STmin) answering our First Frame.
+fn continue_to_send() -> Vec<Vec<Vec<u8>>> {
+    vec![vec![vec![0x30, 0x00, 0x00]]]
+}
+
+/// TransferData request arguments of `len` bytes.
+fn block(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[tokio::test]
+async fn transfer_of_5000_bytes_uses_the_escaped_size_and_full_frames() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), continue_to_send());
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_tx_data_length(64).unwrap();
+
+    let args = block(4999);
+    client.send_command(0x00, 0x36, &args).await.unwrap();
+
+    let sent = sent.lock().unwrap();
+    let (first, consecutive) = sent.split_first().unwrap();
+    assert_eq!(first.len(), 64);
+    assert_eq!(first[..7], [0x10, 0x00, 0x00, 0x00, 0x13, 0x88, 0x36]);
+
+    // 4942 bytes left after the First Frame: 78 full Consecutive Frames and 28 bytes
+    assert_eq!(consecutive.len(), 79);
+    let mut payload = first[6..].to_vec();
+    for (idx, frame) in consecutive.iter().enumerate() {
+        assert_eq!(frame[0], 0x20 | ((idx + 1) & 0x0F) as u8);
+        if idx < 78 {
+            assert_eq!(frame.len(), 64);
+            payload.extend_from_slice(&frame[1..]);
+        } else {
+            // Padded to the next valid CAN FD data length
+            assert_eq!(frame.len(), 32);
+            assert!(frame[29..].iter().all(|&byte| byte == 0xCC));
+            payload.extend_from_slice(&frame[1..29]);
+        }
+    }
+    assert_eq!(payload[0], 0x36);
+    assert_eq!(payload[1..], args[..]);
+}
+
+#[tokio::test]
+async fn short_request_fits_an_escaped_single_frame() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_tx_data_length(64).unwrap();
+
+    let args = block(40);
+    client.send_command(0x00, 0x36, &args).await.unwrap();
+
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].len(), 48);
+    assert_eq!(sent[0][..3], [0x00, 41, 0x36]);
+    assert_eq!(sent[0][3..43], args[..]);
+}
+
+#[tokio::test]
+async fn classic_can_keeps_8_bytes_frames() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), continue_to_send());
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client.send_command(0x00, 0x36, &block(40)).await.unwrap();
+
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent[0][..3], [0x10, 41, 0x36]);
+    assert!(sent.iter().all(|frame| frame.len() <= 8));
+    assert_eq!(sent.len(), 1 + 5);
+}
+
+#[test]
+fn invalid_tx_data_length_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), Vec::new()), 0x7E0, &slot);
+    for tx_dl in [0, 7, 9, 63, 128] {
+        assert!(matches!(
+            client.set_tx_data_length(tx_dl),
+            Err(DiagError::ParameterInvalid)
+        ));
+    }
+    assert_eq!(client.tx_data_length(), 8);
+}
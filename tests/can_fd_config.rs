@@ -0,0 +1,31 @@
+//! Bit timing of the CAN FD configurations of the PCAN-USB FD adapters.
+
+use uds_client::CanFdConfig;
+
+#[test]
+fn common_bitrates_have_a_pcan_timing() {
+    assert_eq!(
+        CanFdConfig::BITRATE_500K_2M.pcan_bitrate().unwrap(),
+        "f_clock_mhz=80, nom_brp=1, nom_tseg1=127, nom_tseg2=32, nom_sjw=32, \
+         data_brp=1, data_tseg1=31, data_tseg2=8, data_sjw=8"
+    );
+    // The data phase TSEG1 is limited to 32: 1 Mbit/s needs a prescaler of 2
+    assert_eq!(
+        CanFdConfig::new(1_000_000, 1_000_000)
+            .pcan_bitrate()
+            .unwrap(),
+        "f_clock_mhz=80, nom_brp=1, nom_tseg1=63, nom_tseg2=16, nom_sjw=16, \
+         data_brp=2, data_tseg1=31, data_tseg2=8, data_sjw=8"
+    );
+    assert!(
+        CanFdConfig::new(500_000, 5_000_000)
+            .pcan_bitrate()
+            .is_some()
+    );
+}
+
+#[test]
+fn unreachable_bitrates_have_no_timing() {
+    assert_eq!(CanFdConfig::new(500_000, 3_000_000).pcan_bitrate(), None);
+    assert_eq!(CanFdConfig::new(0, 2_000_000).pcan_bitrate(), None);
+}
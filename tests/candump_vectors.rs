@@ -0,0 +1,150 @@
+//! Test vectors in the candump log format of tester/ECU exchanges (tester 0x7E0, ECU 0x7E8,
+//! frames padded with 0xAA), replayed through the frame decoding and the reassembly of the client.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::MockChannel;
+use uds_client::{DiagError, PaddingPolicy, ResponseSlot, UdsClient, UdsFrame};
+
+const TESTER_ID: u32 = 0x7E0;
+const ECU_ID: u32 = 0x7E8;
+
+/// The decoded result expected for a request.
+enum Expected {
+    Payload(&'static [u8]), // Positive response payload (SID + parameters)
+    Nrc(u8),                // Negative response code
+}
+
+/// A capture, the request it holds and its expected result.
+struct Vector {
+    name: &'static str,
+    capture: &'static str,
+    request: &'static [u8],
+    expected: Expected,
+}
+
+const VECTORS: &[Vector] = &[
+    Vector {
+        name: "session control",
+        capture: include_str!("fixtures/candump/session_control.log"),
+        request: &[0x10, 0x03],
+        expected: Expected::Payload(&[0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]),
+    },
+    Vector {
+        name: "read DID",
+        capture: include_str!("fixtures/candump/read_did_vin.log"),
+        request: &[0x22, 0xF1, 0x90],
+        expected: Expected::Payload(b"\x62\xF1\x90WDB1234567890ABCD"),
+    },
+    Vector {
+        name: "multi-frame DTC read",
+        capture: include_str!("fixtures/candump/read_dtc_by_status_mask.log"),
+        request: &[0x19, 0x02, 0xFF],
+        expected: Expected::Payload(&[
+            0x59, 0x02, 0xFF, 0x01, 0x23, 0x45, 0x2F, 0xC1, 0x00, 0x01, 0x09, 0x9A, 0x12, 0x34,
+            0x08,
+        ]),
+    },
+    Vector {
+        name: "negative response",
+        capture: include_str!("fixtures/candump/negative_response.log"),
+        request: &[0x22, 0xF1, 0x99],
+        expected: Expected::Nrc(0x31),
+    },
+    Vector {
+        name: "pending then final",
+        capture: include_str!("fixtures/candump/pending_then_final.log"),
+        request: &[0x31, 0x01, 0xFF, 0x00],
+        expected: Expected::Payload(&[0x71, 0x01, 0xFF, 0x00, 0x00]),
+    },
+];
+
+/// Parse the candump log lines `(timestamp) interface ID#DATA` into `(ID, data)` pairs.
+fn parse(capture: &str) -> Vec<(u32, Vec<u8>)> {
+    capture
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let frame = line.split_whitespace().nth(2).expect("candump line");
+            let (id, data) = frame.split_once('#').expect("ID#DATA");
+            let data = (0..data.len())
+                .step_by(2)
+                .map(|idx| u8::from_str_radix(&data[idx..idx + 2], 16).unwrap())
+                .collect();
+            (u32::from_str_radix(id, 16).unwrap(), data)
+        })
+        .collect()
+}
+
+/// Split a capture into the tester frames and the script of the ECU frames answering each.
+fn split(frames: &[(u32, Vec<u8>)]) -> (Vec<Vec<u8>>, Vec<Vec<Vec<u8>>>) {
+    let mut tester = Vec::new();
+    let mut script: Vec<Vec<Vec<u8>>> = Vec::new();
+    for (id, data) in frames {
+        match *id {
+            TESTER_ID => {
+                tester.push(data.clone());
+                script.push(Vec::new());
+            }
+            ECU_ID => script
+                .last_mut()
+                .expect("ECU frame before a request")
+                .push(data.clone()),
+            id => panic!("unexpected CAN ID 0x{id:03X}"),
+        }
+    }
+    (tester, script)
+}
+
+#[test]
+fn captured_ecu_frames_are_decoded() {
+    for vector in VECTORS {
+        for (id, data) in parse(vector.capture) {
+            let frame = UdsFrame::from_vec(data.clone())
+                .unwrap_or_else(|e| panic!("{}: {:02X?} not decoded: {e}", vector.name, data));
+            if id != ECU_ID {
+                continue;
+            }
+            let kind = match frame {
+                UdsFrame::Single(_) | UdsFrame::Negative(_) => 0x0,
+                UdsFrame::First(frame) => {
+                    // The First Frame size is the one of the complete response
+                    if let Expected::Payload(payload) = vector.expected {
+                        assert_eq!(frame.size as usize, payload.len(), "{}", vector.name);
+                    }
+                    0x1
+                }
+                UdsFrame::Consecutive(_) => 0x2,
+                frame => panic!("{}: unexpected frame {frame}", vector.name),
+            };
+            assert_eq!(kind, data[0] >> 4, "{}", vector.name);
+        }
+    }
+}
+
+#[tokio::test]
+async fn captured_exchanges_are_replayed() {
+    for vector in VECTORS {
+        let (tester, script) = split(&parse(vector.capture));
+        let slot = Arc::new(ResponseSlot::new(Some(100)));
+        let channel = MockChannel::new(slot.clone(), script);
+        let sent = channel.sent.clone();
+        let mut client = UdsClient::new(channel, TESTER_ID, &slot);
+        client.set_padding(PaddingPolicy::Pad(0xAA));
+
+        let result = client.raw_request(vector.request).await;
+        match (&vector.expected, result) {
+            (Expected::Payload(payload), Ok(response)) => {
+                assert_eq!(response, *payload, "{}", vector.name)
+            }
+            (Expected::Nrc(nrc), Err(DiagError::ECUError { code, .. })) => {
+                assert_eq!(u8::from(code), *nrc, "{}", vector.name)
+            }
+            (_, result) => panic!("{}: unexpected result {result:?}", vector.name),
+        }
+        // The request and Flow Control frames match the tester frames of the capture
+        assert_eq!(*sent.lock().unwrap(), tester, "{}", vector.name);
+    }
+}
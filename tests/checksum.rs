@@ -0,0 +1,25 @@
+//! Checksums of the transferred data, against the check values of the CRC catalogue.
+
+use uds_client::Crc;
+
+const CHECK_INPUT: &[u8] = b"123456789";
+
+#[test]
+fn check_values_match_the_catalogue() {
+    assert_eq!(Crc::Crc16CcittFalse.checksum(CHECK_INPUT), 0x29B1);
+    assert_eq!(Crc::Crc16Xmodem.checksum(CHECK_INPUT), 0x31C3);
+    assert_eq!(Crc::Crc32.checksum(CHECK_INPUT), 0xCBF4_3926);
+    assert_eq!(Crc::Crc32Mpeg2.checksum(CHECK_INPUT), 0x0376_E6E7);
+    assert_eq!(Crc::Crc32Autosar.checksum(CHECK_INPUT), 0x1697_D06A);
+}
+
+#[test]
+fn checksum_is_verified_big_endian() {
+    assert_eq!(
+        Crc::Crc16CcittFalse.checksum_bytes(CHECK_INPUT),
+        vec![0x29, 0xB1]
+    );
+    assert!(Crc::Crc32.verify(CHECK_INPUT, &[0xCB, 0xF4, 0x39, 0x26]));
+    assert!(!Crc::Crc32.verify(CHECK_INPUT, &[0x39, 0x26]));
+    assert!(!Crc::Crc32.verify(b"123456780", &[0xCB, 0xF4, 0x39, 0x26]));
+}
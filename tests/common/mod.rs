@@ -0,0 +1,152 @@
+//! Scripted CAN channel used by the integration tests in place of a real interface.
+
+#![allow(dead_code)]
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use embedded_can::{Frame, Id};
+use uds_client::{CanSocketTx, ResponseSlot};
+
+/// A CAN frame as seen by the mock channel.
+#[derive(Debug, Clone)]
+pub struct MockFrame {
+    id: Id,
+    data: Vec<u8>,
+}
+
+impl Frame for MockFrame {
+    fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+        Some(Self {
+            id: id.into(),
+            data: data.to_vec(),
+        })
+    }
+
+    fn new_remote(_id: impl Into<Id>, _dlc: usize) -> Option<Self> {
+        None
+    }
+
+    fn is_extended(&self) -> bool {
+        matches!(self.id, Id::Extended(_))
+    }
+
+    fn is_remote_frame(&self) -> bool {
+        false
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn dlc(&self) -> usize {
+        self.data.len()
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[derive(Debug)]
+pub struct MockError;
+
+impl embedded_can::Error for MockError {
+    fn kind(&self) -> embedded_can::ErrorKind {
+        embedded_can::ErrorKind::Other
+    }
+}
+
+/// A CAN channel answering each transmitted frame with the next scripted list of frames.
+///
+/// The answers are delivered to `slot` by a spawned task, as the RX task of a real interface
+/// would, one frame every millisecond. Every transmitted frame is recorded in `sent`.
+pub struct MockChannel {
+    slot: Arc<ResponseSlot>,
+    response_id: Option<u32>,
+    script: VecDeque<Vec<Vec<u8>>>,
+    pub sent: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl MockChannel {
+    pub fn new(slot: Arc<ResponseSlot>, script: Vec<Vec<Vec<u8>>>) -> Self {
+        Self {
+            slot,
+            response_id: None,
+            script: script.into(),
+            sent: Arc::default(),
+        }
+    }
+
+    /// Same as `new`, the answers are received with the CAN ID `response_id`: the slot drops
+    /// them unless it is the response ID of the client.
+    pub fn with_response_id(
+        slot: Arc<ResponseSlot>,
+        response_id: u32,
+        script: Vec<Vec<Vec<u8>>>,
+    ) -> Self {
+        Self {
+            response_id: Some(response_id),
+            ..Self::new(slot, script)
+        }
+    }
+}
+
+impl CanSocketTx for MockChannel {
+    type Frame = MockFrame;
+    type Error = MockError;
+
+    async fn transmit(&mut self, frame: &MockFrame) -> nb::Result<Option<MockFrame>, MockError> {
+        self.sent.lock().unwrap().push(frame.data.clone());
+        if let Some(answer) = self.script.pop_front() {
+            let slot = self.slot.clone();
+            let response_id = self.response_id;
+            tokio::spawn(async move {
+                for data in answer {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    match response_id {
+                        Some(id) => slot.update_response_from(id, data).await,
+                        None => slot.update_response(data).await,
+                    }
+                }
+            });
+        }
+        Ok(None)
+    }
+}
+
+/// Script the ECU answer `payload` (SID + parameters) to a single request.
+///
+/// A payload longer than 7 bytes is segmented: the First Frame answers the request, the
+/// Consecutive Frames answer our Flow Control frame. Over 4095 bytes, the First Frame size is
+/// escaped.
+pub fn answer(payload: &[u8]) -> Vec<Vec<Vec<u8>>> {
+    if payload.len() <= 7 {
+        let mut frame = vec![payload.len() as u8];
+        frame.extend_from_slice(payload);
+        return vec![vec![frame]];
+    }
+
+    let mut first = if payload.len() > 0xFFF {
+        let mut pci = vec![0x10, 0x00];
+        pci.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        pci
+    } else {
+        vec![0x10 | (payload.len() >> 8) as u8, payload.len() as u8]
+    };
+    let first_len = 8 - first.len();
+    first.extend_from_slice(&payload[..first_len]);
+    let consecutive = payload[first_len..]
+        .chunks(7)
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let mut frame = vec![0x20 | ((idx + 1) & 0x0F) as u8];
+            frame.extend_from_slice(chunk);
+            frame
+        })
+        .collect();
+    vec![vec![first], consecutive]
+}
@@ -0,0 +1,76 @@
+//! CommunicationControl requests, with and without the nodeIdentificationNumber.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{CommunicationControlType, DiagError, ResponseSlot, UdsClient};
+
+#[tokio::test]
+async fn communication_is_controlled() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x68, 0x03]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client
+        .communication_control(CommunicationControlType::DisableRxAndTx, 0x01)
+        .await
+        .unwrap();
+    assert_eq!(sent.lock().unwrap()[0], vec![0x03, 0x28, 0x03, 0x01]);
+}
+
+#[tokio::test]
+async fn node_is_addressed_by_its_identification_number() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x68, 0x04]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client
+        .communication_control_node(
+            CommunicationControlType::EnableRxAndDisableTxWithEnhancedAddressInformation,
+            0x01,
+            Some(0x0A12),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        sent.lock().unwrap()[0],
+        vec![0x05, 0x28, 0x04, 0x01, 0x0A, 0x12]
+    );
+}
+
+#[tokio::test]
+async fn node_id_must_match_the_control_type() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let missing = client
+        .communication_control(
+            CommunicationControlType::EnableRxAndTxWithEnhancedAddressInformation,
+            0x01,
+        )
+        .await;
+    assert!(matches!(missing, Err(DiagError::ParameterInvalid)));
+    let unexpected = client
+        .communication_control_node(CommunicationControlType::EnableRxAndTx, 0x01, Some(1))
+        .await;
+    assert!(matches!(unexpected, Err(DiagError::ParameterInvalid)));
+    assert!(sent.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn echo_of_another_control_type_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x68, 0x00]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client
+        .communication_control(CommunicationControlType::DisableRxAndTx, 0x01)
+        .await;
+    assert!(matches!(result, Err(DiagError::InvalidResponseData)));
+}
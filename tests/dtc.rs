@@ -0,0 +1,393 @@
+//! ReadDTCInformation requests and the parsing of their responses.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use tokio::sync::mpsc::unbounded_channel;
+use uds_client::{
+    DiagError, Dtc, DtcExtendedDataRecord, DtcSeverityRecord, DtcSnapshotRecord, ResponseSlot,
+    UdsClient,
+};
+
+#[tokio::test]
+async fn dtc_count_is_read() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x59, 0x01, 0xFF, 0x01, 0x01, 0x02]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    assert_eq!(client.read_dtc_count(0x08).await.unwrap(), 0x0102);
+    assert_eq!(sent.lock().unwrap()[0], vec![0x03, 0x19, 0x01, 0x08]);
+}
+
+#[tokio::test]
+async fn dtc_count_of_another_sub_function_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x02, 0xFF, 0x01, 0x00, 0x00]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client.read_dtc_count(0xFF).await.unwrap_err();
+    assert!(matches!(error, DiagError::InvalidResponseData));
+}
+
+#[tokio::test]
+async fn truncated_dtc_count_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x01, 0xFF, 0x01, 0x00]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client.read_dtc_count(0xFF).await.unwrap_err();
+    assert!(matches!(error, DiagError::InvalidResponseLength));
+}
+
+#[tokio::test]
+async fn snapshot_records_are_split_by_did() {
+    let mut payload = vec![0x59, 0x04, 0x12, 0x34, 0x56, 0x2F];
+    // Record 0x01: active session and VIN
+    payload.extend_from_slice(&[0x01, 0x02, 0xF1, 0x86, 0x03, 0xF1, 0x90]);
+    payload.extend_from_slice(b"WVWZZZ1JZXW000001");
+    // Record 0x02: active session, then an unregistered DID
+    payload.extend_from_slice(&[0x02, 0x02, 0xF1, 0x86, 0x01, 0xDD, 0x01, 0x0C, 0x80]);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&payload));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let snapshots = client
+        .read_dtc_snapshot_records(0x123456, 0xFF)
+        .await
+        .unwrap();
+    assert_eq!(
+        sent.lock().unwrap()[0],
+        vec![0x06, 0x19, 0x04, 0x12, 0x34, 0x56, 0xFF]
+    );
+    assert_eq!(
+        snapshots,
+        vec![
+            DtcSnapshotRecord {
+                number: 0x01,
+                records: vec![
+                    (0xF186, vec![0x03]),
+                    (0xF190, b"WVWZZZ1JZXW000001".to_vec())
+                ],
+            },
+            DtcSnapshotRecord {
+                number: 0x02,
+                records: vec![(0xF186, vec![0x01]), (0xDD01, vec![0x0C, 0x80])],
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn dtc_without_snapshot_has_no_record() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x04, 0x12, 0x34, 0x56, 0x00]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let snapshots = client.read_dtc_snapshot_records(0x123456, 0x01).await;
+    assert!(snapshots.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn snapshot_of_another_dtc_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x04, 0x12, 0x34, 0x57, 0x00]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client
+        .read_dtc_snapshot_records(0x123456, 0x01)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, DiagError::InvalidResponseData));
+}
+
+#[tokio::test]
+async fn extended_data_record_is_read() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x06, 0x12, 0x34, 0x56, 0x2F, 0x01, 0x05, 0x28]);
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let record = client.read_dtc_extended_data(0x123456, 0x01).await.unwrap();
+    assert_eq!(record, Some(vec![0x05, 0x28]));
+    assert_eq!(
+        sent.lock().unwrap()[0],
+        vec![0x06, 0x19, 0x06, 0x12, 0x34, 0x56, 0x01]
+    );
+}
+
+#[tokio::test]
+async fn missing_extended_data_record_is_none() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x06, 0x12, 0x34, 0x56, 0x2F]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let record = client.read_dtc_extended_data(0x123456, 0x01).await.unwrap();
+    assert_eq!(record, None);
+}
+
+#[tokio::test]
+async fn invalid_dtc_record_parameters_are_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), Vec::new()), 0x7E0, &slot);
+
+    let error = client.read_dtc_extended_data(0x123456, 0xFF).await;
+    assert!(matches!(error, Err(DiagError::ParameterInvalid)));
+    let error = client.read_dtc_snapshot_records(0x1000000, 0x01).await;
+    assert!(matches!(error, Err(DiagError::ParameterInvalid)));
+}
+
+#[tokio::test]
+async fn extended_data_by_record_number_lists_each_dtc() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[
+        0x59, 0x16, 0x01, 0x12, 0x34, 0x56, 0x2F, 0x05, 0x28, 0xAB, 0xCD, 0xEF, 0x08, 0x01, 0x00,
+    ]);
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let records = client
+        .read_dtc_extended_data_record_by_record_number(0x01, 2)
+        .await
+        .unwrap();
+    assert_eq!(
+        records,
+        vec![
+            DtcExtendedDataRecord {
+                dtc: Dtc {
+                    code: 0x123456,
+                    status: 0x2F
+                },
+                number: 0x01,
+                data: vec![0x05, 0x28],
+            },
+            DtcExtendedDataRecord {
+                dtc: Dtc {
+                    code: 0xABCDEF,
+                    status: 0x08
+                },
+                number: 0x01,
+                data: vec![0x01, 0x00],
+            },
+        ]
+    );
+    assert_eq!(sent.lock().unwrap()[0], vec![0x03, 0x19, 0x16, 0x01]);
+}
+
+#[tokio::test]
+async fn extended_data_by_record_number_checks_the_record_length() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x16, 0x01, 0x12, 0x34, 0x56, 0x2F, 0x05]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client
+        .read_dtc_extended_data_record_by_record_number(0x01, 2)
+        .await;
+    assert!(matches!(error, Err(DiagError::InvalidResponseLength)));
+    let error = client
+        .read_dtc_extended_data_record_by_record_number(0xF0, 2)
+        .await;
+    assert!(matches!(error, Err(DiagError::ParameterInvalid)));
+}
+
+#[tokio::test]
+async fn user_memory_extended_data_record_is_read() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut script = answer(&[0x59, 0x19, 0x10, 0x12, 0x34, 0x56, 0x2F, 0x01, 0x05, 0x28]);
+    script.extend(answer(&[0x59, 0x19, 0x11, 0x12, 0x34, 0x56, 0x2F]));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let record = client
+        .read_user_memory_dtc_extended_data(0x123456, 0x01, 0x10)
+        .await
+        .unwrap();
+    assert_eq!(record, Some(vec![0x05, 0x28]));
+    assert_eq!(
+        sent.lock().unwrap()[0],
+        vec![0x07, 0x19, 0x19, 0x12, 0x34, 0x56, 0x01, 0x10]
+    );
+
+    // The response is for another memory
+    let error = client
+        .read_user_memory_dtc_extended_data(0x123456, 0x01, 0x10)
+        .await;
+    assert!(matches!(error, Err(DiagError::InvalidResponseData)));
+}
+
+#[tokio::test]
+async fn dtc_count_by_severity_is_read() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x59, 0x07, 0xFF, 0x01, 0x00, 0x03]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    assert_eq!(
+        client.read_dtc_count_by_severity(0x20, 0x08).await.unwrap(),
+        3
+    );
+    assert_eq!(sent.lock().unwrap()[0], vec![0x04, 0x19, 0x07, 0x20, 0x08]);
+}
+
+#[tokio::test]
+async fn snapshot_identification_lists_the_records() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x03, 0x12, 0x34, 0x56, 0x01, 0x12, 0x34, 0x56, 0x02]);
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    assert_eq!(
+        client.read_dtc_snapshot_identification().await.unwrap(),
+        vec![(0x123456, 0x01), (0x123456, 0x02)]
+    );
+    assert_eq!(sent.lock().unwrap()[0], vec![0x02, 0x19, 0x03]);
+}
+
+#[tokio::test]
+async fn truncated_snapshot_identification_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x03, 0x12, 0x34, 0x56]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client.read_dtc_snapshot_identification().await.unwrap_err();
+    assert!(matches!(error, DiagError::InvalidResponseLength));
+}
+
+#[tokio::test]
+async fn dtcs_by_severity_are_read_with_their_severity() {
+    let mut payload = vec![0x59, 0x08, 0xFF];
+    payload.extend_from_slice(&[0x20, 0x10, 0x12, 0x34, 0x56, 0x2F]);
+    payload.extend_from_slice(&[0x40, 0x33, 0xC1, 0x00, 0x87, 0x09]);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&payload));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let records = client.read_dtc_by_severity(0x60, 0x08).await.unwrap();
+    assert_eq!(sent.lock().unwrap()[0], vec![0x04, 0x19, 0x08, 0x60, 0x08]);
+    assert_eq!(
+        records,
+        vec![
+            DtcSeverityRecord {
+                severity: 0x20,
+                functional_unit: 0x10,
+                dtc: 0x123456,
+                status: 0x2F,
+            },
+            DtcSeverityRecord {
+                severity: 0x40,
+                functional_unit: 0x33,
+                dtc: 0xC10087,
+                status: 0x09,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn truncated_severity_record_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x08, 0xFF, 0x20, 0x10, 0x12, 0x34, 0x56]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client.read_dtc_by_severity(0xFF, 0xFF).await.unwrap_err();
+    assert!(matches!(error, DiagError::InvalidResponseLength));
+}
+
+#[tokio::test]
+async fn severity_of_a_dtc_is_read() {
+    let payload = [0x59, 0x09, 0xFF, 0x20, 0x10, 0x12, 0x34, 0x56, 0x2F];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&payload));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let record = client.read_dtc_severity(0x123456).await.unwrap().unwrap();
+    assert_eq!(
+        sent.lock().unwrap()[0],
+        vec![0x05, 0x19, 0x09, 0x12, 0x34, 0x56]
+    );
+    assert_eq!((record.severity, record.functional_unit), (0x20, 0x10));
+}
+
+#[tokio::test]
+async fn severity_of_an_unknown_dtc_is_none() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x59, 0x09, 0xFF]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    assert_eq!(client.read_dtc_severity(0x123456).await.unwrap(), None);
+    assert!(matches!(
+        client.read_dtc_severity(0x0100_0000).await,
+        Err(DiagError::ParameterInvalid)
+    ));
+}
+
+/// A reportDTCByStatusMask response listing the DTCs 0x010000 to 0x010000 + `count`.
+fn dtc_list(count: u8) -> Vec<u8> {
+    let mut payload = vec![0x59, 0x02, 0xFF];
+    for idx in 0..count {
+        payload.extend_from_slice(&[0x01, 0x00, idx, 0x09]);
+    }
+    payload
+}
+
+#[tokio::test]
+async fn dtcs_are_streamed() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&dtc_list(10)));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let (tx, mut rx) = unbounded_channel();
+    assert_eq!(client.read_dtc_stream(0x08, tx).await.unwrap(), 10);
+    assert_eq!(sent.lock().unwrap()[0], vec![0x03, 0x19, 0x02, 0x08]);
+    let mut dtcs = Vec::new();
+    while let Ok(dtc) = rx.try_recv() {
+        dtcs.push(dtc);
+    }
+    assert_eq!(dtcs.len(), 10);
+    assert_eq!(
+        dtcs[9],
+        Dtc {
+            code: 0x010009,
+            status: 0x09
+        }
+    );
+}
+
+#[tokio::test]
+async fn dtcs_before_a_truncated_record_are_delivered() {
+    let mut payload = dtc_list(5);
+    payload.truncate(payload.len() - 2);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&payload));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let (tx, mut rx) = unbounded_channel();
+    let result = client.read_dtc_stream(0xFF, tx).await;
+    assert!(matches!(result, Err(DiagError::InvalidResponseLength)));
+    let mut count = 0;
+    while rx.try_recv().is_ok() {
+        count += 1;
+    }
+    assert_eq!(count, 4);
+}
+
+#[tokio::test]
+async fn empty_fault_memory_streams_nothing() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&dtc_list(0)));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let (tx, mut rx) = unbounded_channel();
+    assert_eq!(client.read_dtc_stream(0xFF, tx).await.unwrap(), 0);
+    assert!(rx.try_recv().is_err());
+}
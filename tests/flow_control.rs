@@ -0,0 +1,52 @@
+//! Flow control of the multi-frame requests: the ECU block size paces the Consecutive Frames.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, ResponseSlot, UdsClient};
+
+/// A WriteDataByIdentifier request sent as a First Frame and 7 Consecutive Frames.
+fn request() -> Vec<u8> {
+    let mut request = vec![0x2E, 0xF1, 0x90];
+    request.extend([0x55; 52]);
+    request
+}
+
+#[tokio::test]
+async fn consecutive_frames_are_sent_by_block() {
+    let flow_control = vec![vec![0x30, 0x03, 0x00]];
+    // First Frame, then 3 blocks of 3, 3 and 1 Consecutive Frames
+    let mut script = vec![flow_control.clone(), vec![], vec![], flow_control.clone()];
+    script.extend([vec![], vec![], flow_control]);
+    script.extend(answer(&[0x6E, 0xF1, 0x90]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let response = client.raw_request(&request()).await.unwrap();
+    assert_eq!(response, vec![0x6E, 0xF1, 0x90]);
+    let pci: Vec<u8> = sent.lock().unwrap().iter().map(|frame| frame[0]).collect();
+    assert_eq!(pci, vec![0x10, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27]);
+}
+
+#[tokio::test]
+async fn transfer_pauses_after_each_block() {
+    // No Flow Control after the first block
+    let script = vec![vec![vec![0x30, 0x03, 0x00]]];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_isotp_timeouts(
+        std::time::Duration::from_millis(50),
+        std::time::Duration::from_millis(50),
+    );
+
+    let result = client.raw_request(&request()).await;
+    assert!(matches!(result, Err(DiagError::IsoTpTimeout("N_Bs"))));
+    // The First Frame and the 3 Consecutive Frames of the first block
+    assert_eq!(sent.lock().unwrap().len(), 4);
+}
@@ -0,0 +1,384 @@
+//! Round-trip guarantees between `UdsFrame::to_vec` and `UdsFrame::from_vec`.
+
+use std::time::Duration;
+use uds_client::{
+    DiagError, FlowStatus, FrameError, PciByte, PciType, Response, UdsConsecutiveFrame,
+    UdsFirstFrame, UdsFlowControlFrame, UdsFrame, UdsNegativeResponse, UdsSingleFrame,
+};
+
+/// Encode `frame`, decode it again and check nothing was lost on the way.
+fn assert_round_trip(frame: UdsFrame) {
+    let bytes = frame.to_vec().unwrap();
+    let decoded = UdsFrame::from_vec(bytes.clone()).unwrap();
+    assert_eq!(decoded, frame, "bytes: {bytes:02X?}");
+    assert_eq!(decoded.to_vec().unwrap(), bytes);
+}
+
+/// Deterministic payload of `len` bytes.
+fn payload(len: usize, seed: u8) -> Vec<u8> {
+    (0..len as u8)
+        .map(|i| i.wrapping_mul(37).wrapping_add(seed))
+        .collect()
+}
+
+#[test]
+fn single_frame_round_trip() {
+    for sid in [0x10, 0x22, 0x62, 0xFF] {
+        for len in 0..=6 {
+            let frame = UdsSingleFrame::new(sid, payload(len, sid)).unwrap();
+            assert_round_trip(UdsFrame::Single(frame));
+        }
+    }
+}
+
+#[test]
+fn single_frame_parameters_are_opaque() {
+    // Whether the parameters start with a DID is up to the service, the frame keeps them as is
+    let frame = UdsSingleFrame::new(0x62, vec![0x01, 0x02]).unwrap();
+    assert_eq!(frame.params(), vec![0x01, 0x02]);
+    let decoded = UdsFrame::from_vec(vec![0x03, 0x62, 0x01, 0x02]).unwrap();
+    assert_eq!(decoded, UdsFrame::Single(frame));
+}
+
+#[test]
+fn negative_response_sid_is_not_encoded_as_single_or_first_frame() {
+    assert!(matches!(
+        UdsSingleFrame::new(0x7F, vec![0x22, 0x31]),
+        Err(FrameError::InvalidSid)
+    ));
+    assert!(matches!(
+        UdsFirstFrame::new(0x7F, 20, vec![0x22, 0x31]),
+        Err(FrameError::InvalidSid)
+    ));
+
+    let single = UdsSingleFrame {
+        size: 3,
+        sid: 0x7F,
+        payload: vec![0x22, 0x31],
+    };
+    assert!(matches!(single.validate(), Err(FrameError::InvalidSid)));
+    let first = UdsFirstFrame {
+        size: 20,
+        sid: 0x7F,
+        payload: vec![0x22, 0x31, 0x00, 0x00, 0x00],
+    };
+    assert!(UdsFrame::First(first).to_vec().is_err());
+}
+
+#[test]
+fn single_frame_ignores_padding() {
+    let frame = UdsFrame::from_vec(vec![0x02, 0x51, 0x01, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]).unwrap();
+    assert_eq!(
+        frame,
+        UdsFrame::Single(UdsSingleFrame::new(0x51, vec![0x01]).unwrap())
+    );
+    assert_eq!(frame.to_vec().unwrap(), vec![0x02, 0x51, 0x01]);
+}
+
+#[test]
+fn single_frame_shorter_than_its_size_is_rejected() {
+    assert!(UdsFrame::from_vec(vec![0x05, 0x62, 0xF1]).is_err());
+}
+
+#[test]
+fn first_frame_round_trip() {
+    for size in [8u32, 20, 0x100, 0xFFF] {
+        for len in 0..=5 {
+            let frame = UdsFirstFrame::new(0x62, size, payload(len, 1)).unwrap();
+            assert_round_trip(UdsFrame::First(frame));
+        }
+    }
+}
+
+#[test]
+fn first_frame_escaped_size_round_trip() {
+    for size in [0x1000u32, 5000, u32::MAX] {
+        for len in 0..=1 {
+            let frame = UdsFirstFrame::new(0x76, size, payload(len, 4)).unwrap();
+            assert_round_trip(UdsFrame::First(frame));
+        }
+        // A CAN FD First Frame: 64 bytes, 6 of them for the PCI
+        let frame = UdsFirstFrame::new(0x76, size, payload(57, 5)).unwrap();
+        assert_eq!(frame.to_vec().unwrap().len(), 64);
+        assert_round_trip(UdsFrame::First(frame));
+    }
+}
+
+#[test]
+fn first_frame_escaped_size_encoding() {
+    let frame = UdsFirstFrame::new(0x76, 5000, vec![0x01]).unwrap();
+    assert_eq!(
+        frame.to_vec().unwrap(),
+        vec![0x10, 0x00, 0x00, 0x00, 0x13, 0x88, 0x76, 0x01]
+    );
+    let decoded = UdsFrame::from_vec(vec![0x10, 0x00, 0x00, 0x00, 0x13, 0x88, 0x76, 0x01]);
+    assert_eq!(decoded.unwrap(), UdsFrame::First(frame));
+}
+
+#[test]
+fn first_frame_truncated_escaped_size_is_rejected() {
+    assert!(UdsFrame::from_vec(vec![0x10, 0x00, 0x00, 0x13]).is_err());
+}
+
+#[test]
+fn consecutive_frame_round_trip() {
+    for seq_num in 0..=15 {
+        for len in 0..=7 {
+            let frame = UdsConsecutiveFrame::new(seq_num, payload(len, seq_num)).unwrap();
+            assert_round_trip(UdsFrame::Consecutive(frame));
+        }
+    }
+}
+
+#[test]
+fn flow_control_frame_round_trip() {
+    for flag in [
+        FlowStatus::ContinueToSend,
+        FlowStatus::Wait,
+        FlowStatus::Overflow,
+    ] {
+        for (block_size, separation_time) in [(0, 0), (8, 0x0A), (0xFF, 0xF1)] {
+            for len in 0..=5 {
+                let frame =
+                    UdsFlowControlFrame::new(flag, block_size, separation_time, payload(len, 3))
+                        .unwrap();
+                assert_round_trip(UdsFrame::FlowControl(frame));
+            }
+        }
+    }
+}
+
+#[test]
+fn flow_control_reserved_flow_status_is_rejected() {
+    for flag in 0x03..=0x0F {
+        assert!(UdsFrame::from_vec(vec![0x30 | flag, 0x00, 0x0A]).is_err());
+    }
+}
+
+#[test]
+fn flow_control_separation_time_decoding() {
+    for (separation_time, expected) in [
+        (0x00, Duration::ZERO),
+        (0x0A, Duration::from_millis(10)),
+        (0x7F, Duration::from_millis(127)),
+        (0xF1, Duration::from_micros(100)),
+        (0xF9, Duration::from_micros(900)),
+        (0x80, Duration::from_millis(127)),
+        (0xF0, Duration::from_millis(127)),
+        (0xFA, Duration::from_millis(127)),
+    ] {
+        let frame =
+            UdsFlowControlFrame::new(FlowStatus::ContinueToSend, 0, separation_time, vec![])
+                .unwrap();
+        assert_eq!(frame.separation_time_duration(), expected);
+    }
+}
+
+#[test]
+fn flow_control_separation_time_encoding() {
+    for (duration, expected) in [
+        (Duration::ZERO, 0x00),
+        (Duration::from_micros(1), 0xF1),
+        (Duration::from_micros(100), 0xF1),
+        (Duration::from_micros(850), 0xF9),
+        (Duration::from_micros(901), 0x01),
+        (Duration::from_millis(10), 0x0A),
+        (Duration::from_micros(10_001), 0x0B),
+        (Duration::from_millis(127), 0x7F),
+        (Duration::from_secs(1), 0x7F),
+    ] {
+        assert_eq!(
+            UdsFlowControlFrame::separation_time_from_duration(duration),
+            expected,
+            "{duration:?}"
+        );
+    }
+}
+
+#[test]
+fn negative_response_round_trip() {
+    for (rsid, nrc) in [(0x22, 0x31), (0x10, 0x78), (0x11, 0x12)] {
+        let frame = UdsFrame::Negative(UdsNegativeResponse::new(rsid, nrc));
+        assert_round_trip(frame.clone());
+        assert_eq!(frame.to_vec().unwrap(), vec![0x03, 0x7F, rsid, nrc]);
+    }
+}
+
+#[test]
+fn hex_dump_matches_the_encoding() {
+    let frame = UdsFrame::from_vec(vec![0x03, 0x22, 0xF1, 0x90]).unwrap();
+    assert_eq!(frame.hex_dump(), "03 22 F1 90");
+    let frame = UdsFrame::from_vec(vec![0x03, 0x7F, 0x22, 0x31]).unwrap();
+    assert_eq!(frame.hex_dump(), "03 7F 22 31");
+    assert_eq!(Response::Ok(frame, None).hex_dump(), "03 7F 22 31");
+    assert_eq!(
+        Response::Error(DiagError::Timeout).hex_dump(),
+        DiagError::Timeout.to_string()
+    );
+}
+
+#[test]
+fn concrete_frame_types_decode_their_own_pci() {
+    let single = UdsSingleFrame::try_from(&[0x03, 0x22, 0xF1, 0x90, 0xAA][..]).unwrap();
+    assert_eq!(single.to_vec().unwrap(), vec![0x03, 0x22, 0xF1, 0x90]);
+    let first = UdsFirstFrame::try_from(&[0x10, 0x14, 0x62, 0xF1, 0x90, 1, 2, 3][..]).unwrap();
+    assert_eq!(first.size, 0x14);
+    let consecutive = UdsConsecutiveFrame::try_from(&[0x21, 4, 5, 6][..]).unwrap();
+    assert_eq!(
+        consecutive,
+        UdsConsecutiveFrame::new(1, vec![4, 5, 6]).unwrap()
+    );
+    let flow_control = UdsFlowControlFrame::try_from(&[0x30, 0x08, 0xF5][..]).unwrap();
+    assert_eq!(
+        flow_control,
+        UdsFlowControlFrame::new(FlowStatus::ContinueToSend, 0x08, 0xF5, vec![]).unwrap()
+    );
+    let negative = UdsNegativeResponse::try_from(&[0x03, 0x7F, 0x22, 0x31][..]).unwrap();
+    assert_eq!(negative, UdsNegativeResponse::new(0x22, 0x31));
+}
+
+#[test]
+fn concrete_frame_types_reject_another_pci() {
+    let flow_control = [0x30, 0x00, 0x0A];
+    for result in [
+        UdsSingleFrame::try_from(&flow_control[..]).map(|_| ()),
+        UdsFirstFrame::try_from(&flow_control[..]).map(|_| ()),
+        UdsConsecutiveFrame::try_from(&flow_control[..]).map(|_| ()),
+        UdsNegativeResponse::try_from(&flow_control[..]).map(|_| ()),
+    ] {
+        assert!(matches!(
+            result,
+            Err(DiagError::FrameError {
+                error: FrameError::InvalidFrameType
+            })
+        ));
+    }
+    assert!(matches!(
+        UdsFlowControlFrame::try_from(&[0x21, 0x00, 0x0A][..]),
+        Err(DiagError::FrameError {
+            error: FrameError::InvalidFrameType
+        })
+    ));
+}
+
+#[test]
+fn negative_response_is_not_a_single_frame() {
+    let negative = [0x03, 0x7F, 0x22, 0x31];
+    assert!(matches!(
+        UdsSingleFrame::try_from(&negative[..]),
+        Err(DiagError::FrameError {
+            error: FrameError::InvalidSid
+        })
+    ));
+    assert!(matches!(
+        UdsNegativeResponse::try_from(&[0x03, 0x62, 0xF1, 0x90][..]),
+        Err(DiagError::FrameError {
+            error: FrameError::InvalidSid
+        })
+    ));
+}
+
+#[test]
+fn display_shows_the_decoded_fields() {
+    let display = |data: Vec<u8>| UdsFrame::from_vec(data).unwrap().to_string();
+    assert_eq!(
+        display(vec![0x06, 0x62, 0xF1, 0x90, 0x01, 0x02, 0x03]),
+        "SF sid=0x62 len=5"
+    );
+    assert_eq!(display(vec![0x01, 0x7E]), "SF sid=0x7E len=0");
+    assert_eq!(
+        display(vec![0x10, 0x14, 0x62, 0xF1, 0x90, 0x57, 0x30, 0x4C]),
+        "FF sid=0x62 size=20"
+    );
+    assert_eq!(display(vec![0x21, 1, 2, 3, 4, 5, 6, 7]), "CF sn=1 len=7");
+    assert_eq!(display(vec![0x30, 0x00, 0x0A]), "FC flag=CTS bs=0 st=10ms");
+    assert_eq!(display(vec![0x31, 0x08, 0xF5]), "FC flag=WT bs=8 st=500µs");
+    assert_eq!(
+        display(vec![0x03, 0x7F, 0x22, 0x31]),
+        "NR sid=0x22 nrc=0x31 (RequestOutOfRange)"
+    );
+    assert_eq!(
+        display(vec![0x03, 0x7F, 0x22, 0xF0]),
+        "NR sid=0x22 nrc=0xF0"
+    );
+}
+
+#[test]
+fn malformed_frames_are_rejected_before_encoding() {
+    let single = UdsSingleFrame {
+        size: 5,
+        sid: 0x22,
+        payload: vec![0xF1, 0x90],
+    };
+    assert!(matches!(single.validate(), Err(FrameError::InvalidSize)));
+    let first = UdsFirstFrame {
+        size: 4,
+        sid: 0x2E,
+        payload: vec![0x00; 5],
+    };
+    assert!(matches!(first.validate(), Err(FrameError::InvalidSize)));
+    let consecutive = UdsConsecutiveFrame {
+        seq_num: 16,
+        payload: vec![0x00],
+    };
+    assert!(matches!(
+        consecutive.validate(),
+        Err(FrameError::InvalidSequenceNumber)
+    ));
+    let flow_control = UdsFlowControlFrame {
+        flag: FlowStatus::ContinueToSend,
+        block_size: 0,
+        separation_time: 0,
+        padding: vec![0xAA; 6],
+    };
+    assert!(matches!(
+        UdsFrame::FlowControl(flow_control).to_vec(),
+        Err(DiagError::FrameError {
+            error: FrameError::InvalidCanLength
+        })
+    ));
+}
+
+#[test]
+fn pci_byte_round_trip() {
+    for byte in 0x00..=0x3F {
+        let pci = PciByte::from_byte(byte).unwrap();
+        assert_eq!(pci.as_byte(), byte);
+        assert_eq!(u8::from(pci), byte);
+    }
+    let pci = PciByte::try_from(0x21).unwrap();
+    assert_eq!((pci.pci_type, pci.value), (PciType::ConsecutiveFrame, 0x01));
+    assert!(matches!(
+        PciByte::from_byte(0x40),
+        Err(FrameError::InvalidFrameType)
+    ));
+}
+
+#[test]
+fn pci_byte_checked_values() {
+    assert!(PciByte::try_new(PciType::SingleFrame, 7).is_ok());
+    assert!(matches!(
+        PciByte::try_new(PciType::SingleFrame, 8),
+        Err(FrameError::InvalidSize)
+    ));
+    assert_eq!(
+        PciByte::try_new(PciType::FirstFrame, 0x0F)
+            .unwrap()
+            .as_byte(),
+        0x1F
+    );
+    assert!(matches!(
+        PciByte::try_new(PciType::FirstFrame, 20),
+        Err(FrameError::InvalidSize)
+    ));
+    assert!(matches!(
+        PciByte::try_new(PciType::ConsecutiveFrame, 0x10),
+        Err(FrameError::InvalidSequenceNumber)
+    ));
+    assert!(matches!(
+        PciByte::try_new(PciType::FlowControl, 0x10),
+        Err(FrameError::InvalidFlowStatus)
+    ));
+    // The unchecked constructor keeps only the low nibble.
+    assert_eq!(PciByte::new(PciType::FirstFrame, 20).as_byte(), 0x14);
+}
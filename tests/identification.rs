@@ -0,0 +1,40 @@
+//! Identification strings read by DID, with their trailing padding.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, ResponseSlot, UdsClient};
+
+#[tokio::test]
+async fn trailing_padding_is_trimmed() {
+    for (data, expected) in [
+        (&b"V1\x00\xFF"[..], "V1"),
+        (&b"V1 \xFF"[..], "V1"),
+        (&b"V 1\x00"[..], "V 1"),
+        (&b" \x00\xFF"[..], ""),
+    ] {
+        let mut response = vec![0x62, 0xF1, 0x95];
+        response.extend_from_slice(data);
+        let slot = Arc::new(ResponseSlot::new(None));
+        let channel = MockChannel::new(slot.clone(), answer(&response));
+        let sent = channel.sent.clone();
+        let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+        assert_eq!(client.read_ecu_sw_version().await.unwrap(), expected);
+        assert_eq!(sent.lock().unwrap()[0], vec![0x03, 0x22, 0xF1, 0x95]);
+    }
+}
+
+#[tokio::test]
+async fn non_utf8_identification_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x62, 0xF1, 0x91, 0xC3, 0x28, 0x00]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    assert!(matches!(
+        client.read_identification(0xF191).await,
+        Err(DiagError::InvalidResponseData)
+    ));
+}
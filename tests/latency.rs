@@ -0,0 +1,38 @@
+//! Request/response latency statistics of the client.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{LatencyStats, ResponseSlot, UdsClient};
+
+#[tokio::test]
+async fn latency_of_the_requests_is_measured() {
+    let mut script = answer(&[0x62, 0xF1, 0x86, 0x01]);
+    // The second request is extended once with responsePending
+    script.push(vec![
+        vec![0x03, 0x7F, 0x22, 0x78],
+        vec![0x04, 0x62, 0xF1, 0x86, 0x03],
+    ]);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+    assert_eq!(client.latency_stats(), LatencyStats::default());
+    assert_eq!(client.latency_stats().avg(), None);
+
+    client.read_data_by_identifier(0xF186).await.unwrap();
+    client.read_data_by_identifier(0xF186).await.unwrap();
+    let stats = client.latency_stats();
+    assert_eq!(stats.count, 2);
+    assert_eq!(stats.pending_extensions, 1);
+    assert!(stats.min <= stats.max);
+    assert_eq!(stats.total, stats.min + stats.max);
+    assert!(
+        stats
+            .avg()
+            .is_some_and(|avg| stats.min <= avg && avg <= stats.max)
+    );
+
+    client.reset_latency_stats();
+    assert_eq!(client.latency_stats(), LatencyStats::default());
+}
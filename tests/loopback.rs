@@ -0,0 +1,125 @@
+//! UDS exchanges over the in-memory `LoopbackSocket`, without any CAN interface.
+
+use std::sync::Arc;
+
+use embedded_can::{ExtendedId, Frame};
+use uds_client::{
+    CanSocketRx, CanSocketTx, LoopbackFrame, LoopbackSocket, ResponseSlot, UdsClient,
+};
+
+const REQUEST_ID: u32 = 0x7E0;
+const RESPONSE_ID: u32 = 0x7E8;
+
+fn frame(id: u32, data: &[u8]) -> LoopbackFrame {
+    LoopbackFrame::new(ExtendedId::new(id).unwrap(), data).unwrap()
+}
+
+#[tokio::test]
+async fn transmitted_frames_are_received_back() {
+    let (mut tx, mut rx) = LoopbackSocket::new().split();
+    assert!(rx.receive_nonblocking().is_none());
+
+    tx.transmit(&frame(REQUEST_ID, &[0x02, 0x10, 0x03]))
+        .await
+        .unwrap();
+    let echo = rx.receive().await.unwrap();
+    assert_eq!(echo.raw_id(), REQUEST_ID);
+    assert_eq!(echo.data(), &[0x02, 0x10, 0x03]);
+
+    // CAN FD frames carry at most 64 bytes
+    assert!(LoopbackFrame::new(ExtendedId::new(REQUEST_ID).unwrap(), &[0; 65]).is_none());
+
+    drop(rx);
+    assert!(tx.transmit(&frame(REQUEST_ID, &[0x00])).await.is_err());
+}
+
+#[tokio::test]
+async fn nonblocking_receive_returns_the_pending_frames() {
+    let (mut tx, mut rx) = LoopbackSocket::new().split();
+    assert!(rx.receive_nonblocking().is_none());
+
+    tx.transmit(&frame(RESPONSE_ID, &[0x02, 0x7E, 0x00]))
+        .await
+        .unwrap();
+    tx.transmit(&frame(RESPONSE_ID, &[0x02, 0x50, 0x03]))
+        .await
+        .unwrap();
+    let received = rx.receive_nonblocking().unwrap();
+    assert_eq!(received.raw_id(), RESPONSE_ID);
+    assert_eq!(received.data(), &[0x02, 0x7E, 0x00]);
+    assert_eq!(
+        rx.receive_nonblocking().unwrap().data(),
+        &[0x02, 0x50, 0x03]
+    );
+    assert!(rx.receive_nonblocking().is_none());
+}
+
+#[tokio::test]
+async fn client_exchanges_with_an_ecu_stub_on_the_loopback() {
+    let (tx, mut rx) = LoopbackSocket::new().split();
+    let slot = Arc::new(ResponseSlot::new(None));
+
+    // The RX task answers the requests as the ECU and feeds the responses to the slot
+    let mut ecu = tx.clone();
+    let rx_slot = slot.clone();
+    let rx_task = tokio::spawn(async move {
+        while let Ok(received) = rx.receive().await {
+            match (received.raw_id(), received.data()) {
+                (REQUEST_ID, [0x02, 0x10, session, ..]) => {
+                    let answer = [0x06, 0x50, *session, 0x00, 0x32, 0x01, 0xF4];
+                    ecu.transmit(&frame(RESPONSE_ID, &answer)).await.unwrap();
+                }
+                (REQUEST_ID, _) => {}
+                (id, data) => rx_slot.update_response_from(id, data.to_vec()).await,
+            }
+        }
+    });
+
+    let mut client = UdsClient::new(tx, REQUEST_ID, &slot);
+    let response = client.raw_request(&[0x10, 0x03]).await.unwrap();
+    assert_eq!(response, vec![0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]);
+
+    rx_task.abort();
+}
+
+#[tokio::test]
+async fn consecutive_frames_sent_back_to_back_are_reassembled() {
+    let (tx, mut rx) = LoopbackSocket::new().split();
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut payload = vec![0x62, 0xF1, 0x90];
+    payload.extend(0..30u8);
+
+    // The ECU stub sends every Consecutive Frame as soon as it receives the Flow Control, STmin 0
+    let mut ecu = tx.clone();
+    let rx_slot = slot.clone();
+    let response = payload.clone();
+    let rx_task = tokio::spawn(async move {
+        while let Ok(received) = rx.receive().await {
+            match (received.raw_id(), received.data()) {
+                (REQUEST_ID, [0x03, 0x22, 0xF1, 0x90, ..]) => {
+                    let mut first = vec![0x10, response.len() as u8];
+                    first.extend_from_slice(&response[..6]);
+                    ecu.transmit(&frame(RESPONSE_ID, &first)).await.unwrap();
+                }
+                (REQUEST_ID, [0x30, ..]) => {
+                    for (seq_num, chunk) in response[6..].chunks(7).enumerate() {
+                        let mut consecutive = vec![0x21 + seq_num as u8];
+                        consecutive.extend_from_slice(chunk);
+                        consecutive.resize(8, 0xAA);
+                        ecu.transmit(&frame(RESPONSE_ID, &consecutive))
+                            .await
+                            .unwrap();
+                    }
+                }
+                (REQUEST_ID, _) => {}
+                (id, data) => rx_slot.update_response_from(id, data.to_vec()).await,
+            }
+        }
+    });
+
+    let mut client = UdsClient::new(tx, REQUEST_ID, &slot);
+    let received = client.raw_request(&[0x22, 0xF1, 0x90]).await.unwrap();
+    assert_eq!(received, payload);
+
+    rx_task.abort();
+}
@@ -0,0 +1,160 @@
+//! ReadMemoryByAddress requests, and the streaming of large regions.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{
+    DiagError, ResponseSlot, UdsClient, decode_addr_and_length, encode_addr_and_length,
+};
+
+/// The script of a ReadMemoryByAddress request (a First Frame and a Consecutive Frame) answered
+/// with `data`.
+fn read_answered(data: &[u8]) -> Vec<Vec<Vec<u8>>> {
+    let mut response = vec![0x63];
+    response.extend_from_slice(data);
+    let mut script = vec![vec![vec![0x30, 0x00, 0x00]]];
+    script.extend(answer(&response));
+    script
+}
+
+#[tokio::test]
+async fn memory_is_read_by_address() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), read_answered(&[0xDE, 0xAD]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    assert_eq!(
+        client.read_memory_by_address(0x0010_0000, 2).await.unwrap(),
+        vec![0xDE, 0xAD]
+    );
+    let sent = sent.lock().unwrap();
+    assert_eq!(
+        sent[0],
+        vec![0x10, 0x0A, 0x23, 0x44, 0x00, 0x10, 0x00, 0x00]
+    );
+    assert_eq!(sent[1], vec![0x21, 0x00, 0x00, 0x00, 0x02]);
+}
+
+#[tokio::test]
+async fn short_memory_record_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), read_answered(&[0xDE]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let error = client.read_memory_by_address(0x1000, 2).await.unwrap_err();
+    assert!(matches!(error, DiagError::InvalidResponseLength));
+}
+
+#[tokio::test]
+async fn memory_range_is_read_in_chunks() {
+    let mut script = read_answered(&[0, 1, 2, 3]);
+    script.extend(read_answered(&[4, 5, 6, 7]));
+    script.extend(read_answered(&[8, 9]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let mut dump = Vec::new();
+    client
+        .read_memory_range(0x1000, 10, 4, &mut dump)
+        .await
+        .unwrap();
+    assert_eq!(dump, (0..10).collect::<Vec<u8>>());
+    // Address and size of each request: the last one reads the remaining 2 bytes
+    let sent = sent.lock().unwrap();
+    let requests: Vec<(&[u8], &[u8])> = sent
+        .chunks(2)
+        .map(|frames| (&frames[0][6..8], &frames[1][1..5]))
+        .collect();
+    assert_eq!(
+        requests,
+        vec![
+            (&[0x10, 0x00][..], &[0x00, 0x00, 0x00, 0x04][..]),
+            (&[0x10, 0x04][..], &[0x00, 0x00, 0x00, 0x04][..]),
+            (&[0x10, 0x08][..], &[0x00, 0x00, 0x00, 0x02][..]),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn memory_range_beyond_the_address_space_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let mut dump = Vec::new();
+    let error = client
+        .read_memory_range(0xFFFF_FFF0, 0x20, 8, &mut dump)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, DiagError::ParameterInvalid));
+    assert!(sent.lock().unwrap().is_empty());
+}
+
+#[test]
+fn address_and_length_are_encoded() {
+    assert_eq!(
+        encode_addr_and_length(0x1234, 0x10, 4, 2).unwrap(),
+        vec![0x24, 0x00, 0x00, 0x12, 0x34, 0x00, 0x10]
+    );
+}
+
+#[test]
+fn address_and_length_round_trip() {
+    for (addr, size, addr_len, size_len) in [
+        (0x12, 0x01, 1, 1),
+        (0x1234, 0x10, 4, 2),
+        (0x0010_0000, 0xFFFF, 3, 2),
+        (u64::MAX, u64::MAX, 8, 8),
+    ] {
+        let data = encode_addr_and_length(addr, size, addr_len, size_len).unwrap();
+        let len = 1 + (addr_len + size_len) as usize;
+        assert_eq!(decode_addr_and_length(&data).unwrap(), (addr, size, len));
+    }
+}
+
+#[test]
+fn address_and_length_of_0_or_9_bytes_are_rejected() {
+    for (addr_len, size_len) in [(0, 1), (1, 0), (9, 1), (1, 9)] {
+        assert!(matches!(
+            encode_addr_and_length(0x12, 0x01, addr_len, size_len),
+            Err(DiagError::ParameterInvalid)
+        ));
+    }
+    for format in [0x10, 0x01, 0x19, 0x91] {
+        assert!(matches!(
+            decode_addr_and_length(&[format, 0x00, 0x00]),
+            Err(DiagError::ParameterInvalid)
+        ));
+    }
+}
+
+#[test]
+fn value_not_fitting_its_length_is_rejected() {
+    assert!(matches!(
+        encode_addr_and_length(0x1_0000, 0x10, 2, 1),
+        Err(DiagError::ParameterInvalid)
+    ));
+    assert!(matches!(
+        encode_addr_and_length(0x1234, 0x100, 2, 1),
+        Err(DiagError::ParameterInvalid)
+    ));
+}
+
+#[test]
+fn truncated_address_and_length_are_rejected() {
+    // The format announces a 4 bytes address and a 2 bytes size
+    assert!(matches!(
+        decode_addr_and_length(&[0x24, 0x00, 0x00, 0x12, 0x34, 0x00]),
+        Err(DiagError::InvalidResponseLength)
+    ));
+    assert!(matches!(
+        decode_addr_and_length(&[]),
+        Err(DiagError::InvalidResponseLength)
+    ));
+}
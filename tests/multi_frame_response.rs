@@ -0,0 +1,150 @@
+//! Completion of the multi-frame responses on the generic request path.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use automotive_diag::uds::UdsCommand;
+use common::{MockChannel, answer};
+use tokio::time::timeout;
+use uds_client::{DiagError, PciByte, PciType, ResponseSlot, UdsClient, UdsFrame};
+
+#[tokio::test]
+async fn first_frame_response_is_completed() {
+    let mut response = vec![0x62, 0xF1, 0x90];
+    response.extend(b"WDB1234567890ABCD");
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&response));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_flow_control(0, 5).unwrap();
+
+    let pci = PciByte::new(PciType::SingleFrame, 3);
+    let frame = client
+        .send_command_with_response(pci, UdsCommand::ReadDataByIdentifier, &[0xF1, 0x90])
+        .await
+        .unwrap();
+    let UdsFrame::First(frame) = frame else {
+        panic!("expected a First Frame, got {frame:?}");
+    };
+    assert_eq!(frame.sid, 0x62);
+    assert_eq!(frame.payload[..2], [0xF1, 0x90]);
+    assert_eq!(frame.payload[2..], *b"WDB1234567890ABCD");
+    // The request, then our Flow Control frame with the configured separation time
+    assert_eq!(sent.lock().unwrap()[1], vec![0x30, 0x00, 0x05]);
+}
+
+#[tokio::test]
+async fn single_frame_response_is_returned_as_is() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x50, 0x03]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let pci = PciByte::new(PciType::SingleFrame, 2);
+    let frame = client
+        .send_command_with_response(pci, UdsCommand::DiagnosticSessionControl, &[0x03])
+        .await
+        .unwrap();
+    assert!(matches!(frame, UdsFrame::Single(frame) if frame.sid == 0x50));
+    assert_eq!(sent.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn first_frame_carrying_its_whole_size_is_rejected() {
+    // Announces 5 bytes, carries 6
+    let script = vec![vec![vec![0x10, 0x05, 0x62, 0xF1, 0x90, 0x01, 0x02, 0x03]]];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client.raw_request(&[0x22, 0xF1, 0x90]).await;
+    assert!(matches!(
+        result,
+        Err(DiagError::ResponseLengthMismatch {
+            expected: 5,
+            received: 6
+        })
+    ));
+    // No Flow Control frame is sent for an inconsistent First Frame
+    assert_eq!(sent.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn consecutive_frame_running_past_the_size_is_rejected() {
+    // Announces 10 bytes: 4 remain after the First Frame, the CAN FD frame carries 11
+    let mut consecutive = vec![0x21, 0x04, 0x05, 0x06, 0x07];
+    consecutive.extend([0x08; 7]);
+    let script = vec![
+        vec![vec![0x10, 0x0A, 0x62, 0xF1, 0x90, 0x01, 0x02, 0x03]],
+        vec![consecutive],
+    ];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client.raw_request(&[0x22, 0xF1, 0x90]).await;
+    assert!(matches!(
+        result,
+        Err(DiagError::ResponseLengthMismatch {
+            expected: 10,
+            received: 17
+        })
+    ));
+}
+
+#[tokio::test]
+async fn transfer_ending_short_is_rejected() {
+    // Announces 20 bytes: 14 remain after the First Frame, the Consecutive Frame carries 3
+    let script = vec![
+        vec![vec![0x10, 0x14, 0x62, 0xF1, 0x90, 0x01, 0x02, 0x03]],
+        vec![vec![0x21, 0x04, 0x05, 0x06]],
+    ];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client.raw_request(&[0x22, 0xF1, 0x90]).await;
+    assert!(matches!(
+        result,
+        Err(DiagError::ResponseLengthMismatch {
+            expected: 20,
+            received: 9
+        })
+    ));
+}
+
+#[tokio::test]
+async fn request_during_an_interrupted_response_is_rejected() {
+    let mut response = vec![0x62, 0x01, 0x00];
+    response.extend([0xAA; 200]);
+    let mut script = answer(&response);
+    script.extend(answer(&[0x50, 0x03]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_isotp_timeouts(Duration::from_millis(1000), Duration::from_millis(50));
+
+    // The Consecutive Frames keep arriving after the request is given up
+    let interrupted = timeout(
+        Duration::from_millis(10),
+        client.raw_request(&[0x22, 0x01, 0x00]),
+    )
+    .await;
+    assert!(interrupted.is_err());
+    let sent_count = sent.lock().unwrap().len();
+    assert!(matches!(
+        client.raw_request(&[0x10, 0x03]).await,
+        Err(DiagError::Busy)
+    ));
+    assert_eq!(sent.lock().unwrap().len(), sent_count);
+
+    // Once N_Cr elapsed, the reception is abandoned and its leftover frames flushed
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        client.raw_request(&[0x10, 0x03]).await.unwrap(),
+        vec![0x50, 0x03]
+    );
+}
@@ -0,0 +1,83 @@
+//! Several clients, each with its own response slot, running concurrently on several interfaces
+//! or sharing one.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, MockError, MockFrame, answer};
+use embedded_can::{Frame, Id};
+use uds_client::{CanSocketTx, DiagError, ResponseSlot, UdsClient};
+
+#[tokio::test]
+async fn two_clients_on_two_interfaces() {
+    let mut vin0 = vec![0x62, 0xF1, 0x90];
+    vin0.extend_from_slice(b"WVWZZZ1JZXW000001");
+    let mut vin1 = vec![0x62, 0xF1, 0x90];
+    vin1.extend_from_slice(b"VF1AAAAA555555555");
+
+    let slot0 = Arc::new(ResponseSlot::new(None));
+    let slot1 = Arc::new(ResponseSlot::new(None));
+    let can0 = MockChannel::new(slot0.clone(), answer(&vin0));
+    let can1 = MockChannel::new(slot1.clone(), answer(&vin1));
+    let sent0 = can0.sent.clone();
+    let sent1 = can1.sent.clone();
+
+    let mut client0 = UdsClient::new(can0, 0x7E0, &slot0);
+    let mut client1 = UdsClient::new(can1, 0x7E1, &slot1);
+    let (res0, res1) = tokio::join!(client0.read_vin(), client1.read_vin());
+
+    assert_eq!(res0.unwrap(), "WVWZZZ1JZXW000001");
+    assert_eq!(res1.unwrap(), "VF1AAAAA555555555");
+    // Request, then Flow Control for the multi-frame response, on each interface
+    for sent in [sent0, sent1] {
+        assert_eq!(
+            *sent.lock().unwrap(),
+            vec![vec![0x03, 0x22, 0xF1, 0x90], vec![0x30, 0x00, 0x0A]]
+        );
+    }
+}
+
+/// A bus shared by two ECUs answering ECUReset with their request ID + 8: 0x7E8 accepts it,
+/// 0x7E9 rejects it (conditionsNotCorrect). Every answer is delivered to every slot, as an RX
+/// task demuxing the frames of one socket would.
+#[derive(Clone)]
+struct SharedBus {
+    slots: Vec<Arc<ResponseSlot>>,
+}
+
+impl CanSocketTx for SharedBus {
+    type Frame = MockFrame;
+    type Error = MockError;
+
+    async fn transmit(&mut self, frame: &MockFrame) -> nb::Result<Option<MockFrame>, MockError> {
+        let Id::Extended(id) = frame.id() else {
+            return Err(nb::Error::Other(MockError));
+        };
+        let response_id = id.as_raw() + 8;
+        let answer = match response_id {
+            0x7E8 => vec![0x02, 0x51, 0x01],
+            _ => vec![0x03, 0x7F, 0x11, 0x22],
+        };
+        for slot in self.slots.clone() {
+            let answer = answer.clone();
+            tokio::spawn(async move { slot.update_response_from(response_id, answer).await });
+        }
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn two_clients_on_one_bus() {
+    let slot0 = Arc::new(ResponseSlot::new(Some(100)));
+    let slot1 = Arc::new(ResponseSlot::new(Some(100)));
+    let bus = SharedBus {
+        slots: vec![slot0.clone(), slot1.clone()],
+    };
+
+    let mut engine = UdsClient::new(bus.clone(), 0x7E0, &slot0);
+    let mut gearbox = UdsClient::new(bus, 0x7E1, &slot1);
+    let (res0, res1) = tokio::join!(engine.uds_reset_ecu(), gearbox.uds_reset_ecu());
+    assert!(res0.is_ok());
+    assert!(matches!(res1, Err(DiagError::ECUError { .. })));
+}
@@ -0,0 +1,125 @@
+//! Negative responses, however the ECU or a gateway frames them.
+
+mod common;
+
+use std::sync::Arc;
+
+use automotive_diag::uds::{UdsCommand, UdsError};
+use common::MockChannel;
+use uds_client::{DiagError, Nrc, ResponseSlot, UdsClient, UdsFrame, UdsNegativeResponse};
+
+fn negative(rsid: u8, nrc: u8) -> UdsFrame {
+    UdsFrame::Negative(UdsNegativeResponse::new(rsid, nrc))
+}
+
+#[test]
+fn negative_response_with_padding() {
+    let frame = UdsFrame::from_vec(vec![0x03, 0x7F, 0x22, 0x31, 0xAA, 0xAA, 0xAA, 0xAA]).unwrap();
+    assert_eq!(frame, negative(0x22, 0x31));
+}
+
+#[test]
+fn negative_response_with_escaped_size() {
+    let frame = UdsFrame::from_vec(vec![0x00, 0x03, 0x7F, 0x22, 0x31, 0xCC, 0xCC, 0xCC]).unwrap();
+    assert_eq!(frame, negative(0x22, 0x31));
+}
+
+#[test]
+fn negative_response_segmented_by_a_gateway() {
+    let frame = UdsFrame::from_vec(vec![0x10, 0x03, 0x7F, 0x22, 0x31, 0xAA, 0xAA, 0xAA]).unwrap();
+    assert_eq!(frame, negative(0x22, 0x31));
+}
+
+#[tokio::test]
+async fn pending_then_error_resolves_to_the_error() {
+    let script = vec![vec![
+        vec![0x03, 0x7F, 0x22, 0x78],
+        vec![0x10, 0x03, 0x7F, 0x22, 0x78, 0xAA, 0xAA, 0xAA],
+        vec![0x03, 0x7F, 0x22, 0x31],
+    ]];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client.read_data_by_identifier(0xF190).await.unwrap_err();
+    assert!(matches!(
+        error,
+        DiagError::ECUError {
+            code,
+            rsid: UdsCommand::ReadDataByIdentifier,
+            ..
+        } if code == UdsError::RequestOutOfRange
+    ));
+}
+
+#[tokio::test]
+async fn service_not_supported_is_not_supported() {
+    for nrc in [0x11, 0x12] {
+        let script = vec![vec![vec![0x03, 0x7F, 0x22, nrc]]];
+        let slot = Arc::new(ResponseSlot::new(None));
+        let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+        let error = client.read_data_by_identifier(0xF190).await.unwrap_err();
+        assert!(matches!(error, DiagError::NotSupported), "NRC 0x{nrc:02X}");
+        assert!(error.is_not_supported());
+    }
+}
+
+#[tokio::test]
+async fn not_supported_in_active_session_keeps_the_nrc() {
+    for nrc in [0x7E, 0x7F] {
+        let script = vec![vec![vec![0x03, 0x7F, 0x22, nrc]]];
+        let slot = Arc::new(ResponseSlot::new(None));
+        let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+        let error = client.read_data_by_identifier(0xF190).await.unwrap_err();
+        assert!(
+            matches!(&error, DiagError::ECUError { code, .. } if u8::from(*code) == nrc),
+            "NRC 0x{nrc:02X}"
+        );
+        assert!(error.is_not_supported());
+    }
+    assert!(!DiagError::Timeout.is_not_supported());
+}
+
+#[test]
+fn negative_response_converts_to_ecu_error() {
+    let error = DiagError::from_negative_response(&UdsNegativeResponse::new(0x22, 0x31));
+    assert!(matches!(
+        &error,
+        DiagError::ECUError {
+            code,
+            rsid: UdsCommand::ReadDataByIdentifier,
+            def: Some(def),
+        } if *code == UdsError::RequestOutOfRange && def == "request out of range"
+    ));
+    assert_eq!(error.to_string(), "ECU error: 0x31 (request out of range)");
+
+    let error = DiagError::from_negative_response(&UdsNegativeResponse::new(0x22, 0xF0));
+    assert_eq!(
+        error.to_string(),
+        "ECU error: 0xF0 (vehicle manufacturer specific)"
+    );
+    assert!(matches!(
+        DiagError::from_negative_response(&UdsNegativeResponse::new(0x01, 0x31)),
+        DiagError::FrameError { .. }
+    ));
+}
+
+#[tokio::test]
+async fn vendor_specific_nrc_is_kept() {
+    // 0x93 is the last code of ISO 14229 (voltageTooLow), 0x94 and above are not standard
+    for (nrc, code) in [
+        (0x93, Nrc::Standard(UdsError::VoltageTooLow)),
+        (0x94, Nrc::VendorSpecific(0x94)),
+    ] {
+        let script = vec![vec![vec![0x03, 0x7F, 0x22, nrc]]];
+        let slot = Arc::new(ResponseSlot::new(None));
+        let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+        let error = client.read_data_by_identifier(0xF190).await.unwrap_err();
+        assert!(
+            matches!(error, DiagError::ECUError { code: received, .. } if received == code),
+            "NRC 0x{nrc:02X}"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! Padding of the transmitted frames and strict length check of the received frames.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, FrameError, PaddingPolicy, ResponseSlot, UdsClient};
+
+#[tokio::test]
+async fn frames_are_padded_to_8_bytes() {
+    let mut vin = vec![0x62, 0xF1, 0x90];
+    vin.extend_from_slice(b"WVWZZZ1JZXW000001");
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&vin));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_padding(PaddingPolicy::Pad(0xAA));
+
+    client.read_vin().await.unwrap();
+    let sent = sent.lock().unwrap();
+    assert_eq!(
+        sent[0],
+        vec![0x03, 0x22, 0xF1, 0x90, 0xAA, 0xAA, 0xAA, 0xAA]
+    );
+    // Flow Control for the multi-frame response
+    assert_eq!(
+        sent[1],
+        vec![0x30, 0x00, 0x0A, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]
+    );
+}
+
+#[tokio::test]
+async fn unpadded_frames_are_accepted_by_default() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(
+        MockChannel::new(slot.clone(), answer(&[0x7E, 0x00])),
+        0x7E0,
+        &slot,
+    );
+    client.set_padding(PaddingPolicy::Pad(0x00));
+
+    assert!(client.ping().await.is_ok());
+    assert!(!slot.strict_length());
+}
+
+#[tokio::test]
+async fn strict_padding_rejects_short_frames() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut script = answer(&[0x7E, 0x00]);
+    script.push(vec![vec![0x02, 0x7E, 0x00, 0x55, 0x55, 0x55, 0x55, 0x55]]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+    client.set_padding(PaddingPolicy::StrictPad(0x55));
+
+    let error = client.ping().await.unwrap_err();
+    assert!(matches!(
+        error,
+        DiagError::FrameError {
+            error: FrameError::InvalidCanLength
+        }
+    ));
+    assert!(client.ping().await.is_ok());
+
+    client.set_padding(PaddingPolicy::None);
+    assert!(!slot.strict_length());
+}
@@ -0,0 +1,158 @@
+//! Typed periodic data: ReadDataByPeriodicIdentifier messages parsed per periodic DID.
+
+mod common;
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use common::MockChannel;
+use tokio::sync::mpsc::unbounded_channel;
+use uds_client::{
+    DiagError, PeriodicParsers, PeriodicSample, RealTimeType, ResponseSlot, UdsClient,
+};
+
+#[derive(Debug, PartialEq)]
+enum Telemetry {
+    EngineSpeed(u16),
+    CoolantTemperature(i16),
+}
+
+fn parsers() -> PeriodicParsers<Telemetry> {
+    let mut parsers = PeriodicParsers::new();
+    parsers
+        .register(0x01, |data| {
+            let rpm = data
+                .try_into()
+                .map_err(|_| DiagError::InvalidResponseLength)?;
+            Ok(Telemetry::EngineSpeed(u16::from_be_bytes(rpm)))
+        })
+        .register(0x02, |data| match data {
+            [temperature] => Ok(Telemetry::CoolantTemperature(*temperature as i16 - 40)),
+            _ => Err(DiagError::InvalidResponseLength),
+        });
+    parsers
+}
+
+#[test]
+fn message_is_parsed_by_the_parser_of_its_did() {
+    let parsers = parsers();
+    let sample = parsers.parse(&[0x6A, 0x01, 0x0B, 0xB8]).unwrap().unwrap();
+    assert_eq!(
+        sample,
+        PeriodicSample {
+            did: 0x01,
+            value: Telemetry::EngineSpeed(3000)
+        }
+    );
+    assert!(parsers.parse(&[0x6A, 0x03, 0x00]).is_none());
+    assert!(matches!(
+        parsers.parse(&[0x62, 0x01, 0x00]),
+        Some(Err(DiagError::InvalidResponseData))
+    ));
+    assert!(matches!(
+        parsers.parse(&[0x6A, 0x02, 0x00, 0x00]),
+        Some(Err(DiagError::InvalidResponseLength))
+    ));
+}
+
+#[tokio::test]
+async fn periodic_messages_are_delivered_as_samples() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(
+        slot.clone(),
+        vec![vec![
+            vec![0x04, 0x6A, 0x01, 0x0B, 0xB8],
+            vec![0x03, 0x6A, 0x02, 0x82],
+            // Unknown DID and malformed message are skipped
+            vec![0x03, 0x6A, 0x03, 0x00],
+            vec![0x02, 0x6A, 0x02],
+            vec![0x04, 0x6A, 0x01, 0x0C, 0x1C],
+        ]],
+    );
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    slot.set_timeout(std::time::Duration::from_millis(50));
+
+    let (tx, mut rx) = unbounded_channel();
+    client
+        .read_periodic_data(RealTimeType::FastRate, &[0x01, 0x02], &parsers(), tx)
+        .await
+        .unwrap();
+
+    assert_eq!(sent.lock().unwrap()[0], vec![0x04, 0x2A, 0x03, 0x01, 0x02]);
+    let mut values = Vec::new();
+    while let Ok(sample) = rx.try_recv() {
+        values.push(sample.value);
+    }
+    assert_eq!(
+        values,
+        vec![
+            Telemetry::EngineSpeed(3000),
+            Telemetry::CoolantTemperature(90),
+            Telemetry::EngineSpeed(3100),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stop_rate_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let (tx, _rx) = unbounded_channel();
+    let result = client
+        .read_periodic_data(RealTimeType::Stop, &[0x01], &parsers(), tx)
+        .await;
+    assert!(matches!(result, Err(DiagError::ParameterInvalid)));
+}
+
+#[tokio::test]
+async fn all_periodic_dids_are_stopped() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(
+        slot.clone(),
+        vec![vec![vec![0x04, 0x6A, 0x01, 0x0B, 0xB8], vec![0x01, 0x6A]]],
+    );
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client.stop_all_periodic().await.unwrap();
+    assert_eq!(sent.lock().unwrap()[0], vec![0x02, 0x2A, 0x04]);
+}
+
+#[tokio::test]
+async fn cancelled_read_stops_the_periodic_dids() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(
+        slot.clone(),
+        vec![
+            vec![vec![0x04, 0x6A, 0x01, 0x0B, 0xB8]],
+            vec![vec![0x01, 0x6A]],
+        ],
+    );
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let stop = client.periodic_stop_token();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        stop.cancel();
+    });
+    let (tx, mut rx) = unbounded_channel();
+    let start = Instant::now();
+    client
+        .read_periodic_data(RealTimeType::FastRate, &[0x01], &parsers(), tx)
+        .await
+        .unwrap();
+    // The read returned on the cancellation, not on the response timeout
+    assert!(start.elapsed() < slot.timeout());
+    assert!(rx.try_recv().is_ok());
+    assert_eq!(
+        *sent.lock().unwrap(),
+        vec![vec![0x03, 0x2A, 0x03, 0x01], vec![0x02, 0x2A, 0x04]]
+    );
+    assert!(!client.periodic_stop_token().is_cancelled());
+}
@@ -0,0 +1,81 @@
+//! Probe of the services supported by the ECU.
+
+mod common;
+
+use std::sync::Arc;
+
+use automotive_diag::uds::UdsCommand;
+use common::{MockChannel, answer};
+use uds_client::{ResponseSlot, UdsClient};
+
+/// The SIDs sent by the probe, in order.
+const PROBED: [u8; 24] = [
+    0x10, 0x14, 0x19, 0x22, 0x23, 0x24, 0x27, 0x28, 0x29, 0x2A, 0x2C, 0x2E, 0x2F, 0x31, 0x34, 0x35,
+    0x36, 0x38, 0x3D, 0x3E, 0x84, 0x85, 0x86, 0x87,
+];
+
+#[tokio::test]
+async fn supported_services_are_listed() {
+    // The ECU supports 0x10, 0x22 and 0x3E, and 0x27 in another session only
+    let script = PROBED
+        .iter()
+        .flat_map(|sid| match sid {
+            0x10 | 0x22 => answer(&[0x7F, *sid, 0x13]),
+            0x27 => answer(&[0x7F, *sid, 0x7F]),
+            0x3E => answer(&[0x7E, 0x00]),
+            _ => answer(&[0x7F, *sid, 0x11]),
+        })
+        .collect();
+    let slot = Arc::new(ResponseSlot::new(Some(50)));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    assert_eq!(
+        client.probe_services().await,
+        vec![
+            UdsCommand::DiagnosticSessionControl,
+            UdsCommand::ReadDataByIdentifier,
+            UdsCommand::SecurityAccess,
+            UdsCommand::TesterPresent,
+        ]
+    );
+    let sids: Vec<u8> = sent.lock().unwrap().iter().map(|frame| frame[1]).collect();
+    assert_eq!(sids, PROBED);
+}
+
+#[tokio::test]
+async fn sub_function_not_supported_means_supported() {
+    // NRC 0x12 tells the ECU knows the service, only NRC 0x11 means it doesn't
+    let script = PROBED
+        .iter()
+        .flat_map(|sid| match sid {
+            0x19 | 0x31 => answer(&[0x7F, *sid, 0x12]),
+            _ => answer(&[0x7F, *sid, 0x11]),
+        })
+        .collect();
+    let slot = Arc::new(ResponseSlot::new(Some(50)));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    assert_eq!(
+        client.probe_services().await,
+        vec![UdsCommand::ReadDTCInformation, UdsCommand::RoutineControl]
+    );
+}
+
+#[tokio::test]
+async fn destructive_services_are_never_sent() {
+    let slot = Arc::new(ResponseSlot::new(Some(5)));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    // No response at all: nothing is classified as supported
+    assert!(client.probe_services().await.is_empty());
+    assert!(
+        sent.lock()
+            .unwrap()
+            .iter()
+            .all(|frame| !matches!(frame[1], 0x11 | 0x37))
+    );
+}
@@ -0,0 +1,65 @@
+//! Configuration of a client from an ECU profile.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, EcuProfile, PaddingPolicy, ResponseSlot, UdsClient};
+
+#[tokio::test]
+async fn client_is_configured_from_the_profile() {
+    let profile = EcuProfile {
+        response_id: Some(0x7E9),
+        padding: PaddingPolicy::Pad(0xAA),
+        fc_block_size: 4,
+        p2_timeout: Duration::from_millis(50),
+        p2_star_timeout: Some(Duration::from_millis(5000)),
+        busy_retries: 2,
+        ..EcuProfile::new(0x7E1)
+    };
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x50, 0x03]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::from_profile(channel, &profile, &slot).unwrap();
+
+    assert_eq!(client.response_id(), 0x7E9);
+    assert_eq!(client.flow_control(), (4, 0x0A));
+    assert_eq!(client.busy_retry().0, 2);
+    assert_eq!(slot.pending_timeout(), Some(Duration::from_millis(5000)));
+
+    client.raw_request(&[0x10, 0x03]).await.unwrap();
+    assert_eq!(
+        sent.lock().unwrap()[0],
+        vec![0x02, 0x10, 0x03, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]
+    );
+}
+
+#[tokio::test]
+async fn invalid_profile_is_rejected() {
+    let profile = EcuProfile {
+        tx_data_length: 10,
+        ..EcuProfile::default()
+    };
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+
+    let result = UdsClient::from_profile(channel, &profile, &slot);
+    assert!(matches!(result, Err(DiagError::ParameterInvalid)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn profile_is_loaded_with_defaults() {
+    let json = r#"{ "request_id": 2017, "padding": { "Pad": 170 }, "p2_timeout": 50 }"#;
+    let profile: EcuProfile = serde_json::from_str(json).unwrap();
+
+    assert_eq!(
+        profile,
+        EcuProfile {
+            padding: PaddingPolicy::Pad(0xAA),
+            p2_timeout: Duration::from_millis(50),
+            ..EcuProfile::new(0x7E1)
+        }
+    );
+}
@@ -0,0 +1,89 @@
+//! Session control, security access and the sequence entering the programming session.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, ProgrammingOptions, ResponseSlot, UdsClient};
+
+/// XOR the seed with 0xFF: the key algorithm of the scripted ECU.
+fn key(seed: &[u8]) -> Vec<u8> {
+    seed.iter().map(|byte| byte ^ 0xFF).collect()
+}
+
+#[tokio::test]
+async fn programming_session_is_entered() {
+    let mut script = answer(&[0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]);
+    script.extend(answer(&[0x67, 0x11, 0x12, 0x34]));
+    script.extend(answer(&[0x67, 0x12]));
+    script.extend(answer(&[0xC5, 0x02]));
+    script.extend(answer(&[0x68, 0x03]));
+    script.extend(answer(&[0x50, 0x02, 0x00, 0x32, 0x01, 0xF4]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let options = ProgrammingOptions {
+        security_level: 0x11,
+        disable_dtc_setting: true,
+        disable_communication: true,
+    };
+    client
+        .enter_programming_session_with(options, key)
+        .await
+        .unwrap();
+    assert_eq!(
+        *sent.lock().unwrap(),
+        vec![
+            vec![0x02, 0x10, 0x03],
+            vec![0x02, 0x27, 0x11],
+            vec![0x04, 0x27, 0x12, 0xED, 0xCB],
+            vec![0x02, 0x85, 0x02],
+            vec![0x03, 0x28, 0x03, 0x01],
+            vec![0x02, 0x10, 0x02],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unlocked_level_sends_no_key() {
+    let mut script = answer(&[0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]);
+    script.extend(answer(&[0x67, 0x01, 0x00, 0x00]));
+    script.extend(answer(&[0x50, 0x02, 0x00, 0x32, 0x01, 0xF4]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client
+        .enter_programming_session(|_| panic!("no key expected"))
+        .await
+        .unwrap();
+    assert_eq!(sent.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn invalid_key_stops_the_sequence() {
+    let mut script = answer(&[0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]);
+    script.extend(answer(&[0x67, 0x01, 0x12, 0x34]));
+    script.extend(answer(&[0x7F, 0x27, 0x35]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let error = client.enter_programming_session(key).await.unwrap_err();
+    assert!(matches!(error, DiagError::ECUError { .. }));
+    assert_eq!(sent.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn security_level_must_be_a_request_seed() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), Vec::new()), 0x7E0, &slot);
+
+    let error = client.security_access_request_seed(0x02).await;
+    assert!(matches!(error, Err(DiagError::ParameterInvalid)));
+}
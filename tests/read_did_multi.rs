@@ -0,0 +1,123 @@
+//! Batched ReadDataByIdentifier: splitting the `DID || data` records of the response.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, DidRegistry, ResponseSlot, UdsClient};
+
+#[test]
+fn unregistered_did_only_ends_the_response() {
+    let mut registry = DidRegistry::empty();
+    registry.register(0x0101, 2);
+
+    // The last requested DID takes the rest of the response
+    let data = [0x01, 0x01, 0xAA, 0xBB, 0x02, 0x02, 0x01, 0x01, 0x05];
+    let records = registry.split_records(&data, &[0x0101, 0x0202]).unwrap();
+    assert_eq!(
+        records,
+        vec![(0x0101, vec![0xAA, 0xBB]), (0x0202, vec![0x01, 0x01, 0x05])]
+    );
+
+    // Followed by another requested DID, the end of its record is ambiguous
+    let data = [0x02, 0x02, 0x01, 0x01, 0x05, 0x01, 0x01, 0xAA, 0xBB];
+    let result = registry.split_records(&data, &[0x0202, 0x0101]);
+    assert!(matches!(result, Err(DiagError::InvalidResponseLength)));
+}
+
+#[tokio::test]
+async fn multi_frame_response_is_split() {
+    let mut response = vec![0x62, 0xF1, 0x90];
+    response.extend_from_slice(b"WVWZZZ1JZXW000001");
+    response.extend([0x01, 0x01, 0xAA, 0xBB]);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&response));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.did_registry_mut().register(0x0101, 2);
+
+    let records = client
+        .read_data_by_identifier_multi(&[0xF190, 0x0101])
+        .await
+        .unwrap();
+    assert_eq!(
+        records,
+        vec![
+            (0xF190, b"WVWZZZ1JZXW000001".to_vec()),
+            (0x0101, vec![0xAA, 0xBB])
+        ]
+    );
+    assert_eq!(
+        sent.lock().unwrap()[0],
+        vec![0x05, 0x22, 0xF1, 0x90, 0x01, 0x01]
+    );
+}
+
+#[tokio::test]
+async fn skipped_did_is_missing_from_the_records() {
+    // The ECU doesn't support 0x0202 and omits it
+    let response = [0x62, 0x01, 0x01, 0xAA, 0xBB, 0x03, 0x03, 0x07];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&response));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client
+        .did_registry_mut()
+        .register(0x0101, 2)
+        .register(0x0202, 4)
+        .register(0x0303, 1);
+
+    let records = client
+        .read_data_by_identifier_multi(&[0x0101, 0x0202, 0x0303])
+        .await
+        .unwrap();
+    assert_eq!(
+        records,
+        vec![(0x0101, vec![0xAA, 0xBB]), (0x0303, vec![0x07])]
+    );
+}
+
+#[tokio::test]
+async fn out_of_order_did_is_rejected() {
+    // 0x0101 answered after 0x0202: only 0x0303 may follow
+    let response = [
+        0x62, 0x02, 0x02, 0x01, 0x02, 0x03, 0x04, 0x01, 0x01, 0xAA, 0xBB,
+    ];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&response));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client
+        .did_registry_mut()
+        .register(0x0101, 2)
+        .register(0x0202, 4)
+        .register(0x0303, 1);
+
+    let result = client
+        .read_data_by_identifier_multi(&[0x0101, 0x0202, 0x0303])
+        .await;
+    assert!(matches!(
+        result,
+        Err(DiagError::MismatchedIdentResponse {
+            want: 0x0303,
+            received: 0x0101
+        })
+    ));
+}
+
+#[tokio::test]
+async fn truncated_record_is_rejected() {
+    // 0x0202 holds 4 bytes, the response ends after 2
+    let response = [0x62, 0x01, 0x01, 0xAA, 0xBB, 0x02, 0x02, 0x01, 0x02];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&response));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client
+        .did_registry_mut()
+        .register(0x0101, 2)
+        .register(0x0202, 4);
+
+    let result = client
+        .read_data_by_identifier_multi(&[0x0101, 0x0202])
+        .await;
+    assert!(matches!(result, Err(DiagError::InvalidResponseLength)));
+}
@@ -0,0 +1,51 @@
+//! Receiving the response to a service while unrelated frames interleave.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use common::MockChannel;
+use uds_client::{DiagError, Response, ResponseSlot, UdsClient, UdsFrame};
+
+#[tokio::test]
+async fn unrelated_frames_are_discarded() {
+    let script = vec![vec![
+        // Event notification of a ResponseOnEvent, then a stray Consecutive Frame
+        vec![0x04, 0xC6, 0x01, 0x00, 0x02],
+        vec![0x21, 0x01, 0x02, 0x03],
+        vec![0x04, 0x62, 0xF1, 0x86, 0x01],
+    ]];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    client
+        .send_command(0x03, 0x22, &[0xF1, 0x86])
+        .await
+        .unwrap();
+    let response = client
+        .receive_matching(0x22, Duration::from_millis(100))
+        .await;
+    match response {
+        Response::Ok(UdsFrame::Single(frame), _) => {
+            assert_eq!(frame.sid, 0x62);
+            assert_eq!(frame.params()[..2], [0xF1, 0x86]);
+        }
+        other => panic!("unexpected response: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn times_out_without_a_matching_response() {
+    let script = vec![vec![vec![0x04, 0xC6, 0x01, 0x00, 0x02]]];
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    client
+        .send_command(0x03, 0x22, &[0xF1, 0x86])
+        .await
+        .unwrap();
+    let response = client
+        .receive_matching(0x22, Duration::from_millis(50))
+        .await;
+    assert!(matches!(response, Response::Error(DiagError::Timeout)));
+}
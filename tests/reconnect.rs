@@ -0,0 +1,38 @@
+//! Backoff between the re-open attempts of an unplugged CAN adapter.
+
+use std::time::{Duration, Instant};
+
+use uds_client::ReconnectBackoff;
+
+#[test]
+fn first_attempt_is_due_right_away() {
+    assert!(ReconnectBackoff::default().is_due(Instant::now()));
+}
+
+#[test]
+fn delay_doubles_up_to_the_maximum() {
+    let mut backoff = ReconnectBackoff::new(Duration::from_millis(100), Duration::from_millis(300));
+    let now = Instant::now();
+
+    backoff.failed(now);
+    assert!(!backoff.is_due(now + Duration::from_millis(99)));
+    assert!(backoff.is_due(now + Duration::from_millis(100)));
+    assert_eq!(backoff.delay(), Duration::from_millis(200));
+
+    backoff.failed(now);
+    backoff.failed(now);
+    assert_eq!(backoff.delay(), Duration::from_millis(300));
+    assert!(!backoff.is_due(now + Duration::from_millis(299)));
+}
+
+#[test]
+fn success_resets_the_backoff() {
+    let mut backoff = ReconnectBackoff::default();
+    let now = Instant::now();
+    backoff.failed(now);
+    backoff.failed(now);
+
+    backoff.reset();
+    assert!(backoff.is_due(now));
+    assert_eq!(backoff.delay(), Duration::from_millis(100));
+}
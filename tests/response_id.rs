@@ -0,0 +1,134 @@
+//! Correlation of the responses with the CAN ID the ECU responds with.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{
+    DiagError, Response, ResponseSlot, UdsClient, default_response_id, normal_fixed_response_id,
+};
+
+#[test]
+fn response_id_defaults_to_request_id_plus_8() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let client = UdsClient::new(MockChannel::new(slot.clone(), Vec::new()), 0x7E0, &slot);
+
+    assert_eq!(client.response_id(), 0x7E8);
+    assert_eq!(slot.response_id(), Some(0x7E8));
+}
+
+#[test]
+fn normal_fixed_addressing_swaps_the_addresses() {
+    assert_eq!(normal_fixed_response_id(0x18DA10F1), Some(0x18DAF110));
+    assert_eq!(normal_fixed_response_id(0x1CDA00F9), Some(0x1CDAF900));
+    // Functional requests are answered by each ECU with its own address
+    assert_eq!(normal_fixed_response_id(0x18DB33F1), None);
+    assert_eq!(normal_fixed_response_id(0x7E0), None);
+    assert_eq!(default_response_id(0x18DB33F1), 0x18DB33F9);
+    assert_eq!(default_response_id(0x7E0), 0x7E8);
+}
+
+#[tokio::test]
+async fn normal_fixed_addressing_response_id_is_correlated() {
+    let slot = Arc::new(ResponseSlot::new(Some(50)));
+    let client = UdsClient::new(
+        MockChannel::new(slot.clone(), Vec::new()),
+        0x18DA10F1,
+        &slot,
+    );
+    assert_eq!(client.response_id(), 0x18DAF110);
+
+    // The request + 8 isn't the ECU under this addressing
+    slot.update_response_from(0x18DA10F9, vec![0x02, 0x50, 0x01])
+        .await;
+    assert!(slot.wait_timeout(slot.timeout()).await.is_none());
+
+    slot.update_response_from(0x18DAF110, vec![0x02, 0x50, 0x01])
+        .await;
+    assert!(matches!(
+        slot.wait_timeout(slot.timeout()).await,
+        Some(Response::Ok(_, _))
+    ));
+}
+
+#[tokio::test]
+async fn frames_from_other_ids_are_dropped() {
+    let slot = Arc::new(ResponseSlot::new(Some(50)));
+    let client = UdsClient::with_response_id(
+        MockChannel::new(slot.clone(), Vec::new()),
+        0x7E0,
+        Some(0x7F0),
+        &slot,
+    );
+    assert_eq!(client.response_id(), 0x7F0);
+
+    // Another ECU on the bus answers with request ID + 8
+    slot.update_response_from(0x7E8, vec![0x02, 0x51, 0x01])
+        .await;
+    assert!(slot.wait_timeout(slot.timeout()).await.is_none());
+
+    slot.update_response_from(0x7F0, vec![0x02, 0x51, 0x01])
+        .await;
+    assert!(matches!(
+        slot.wait_timeout(slot.timeout()).await,
+        Some(Response::Ok(_, _))
+    ));
+}
+
+#[tokio::test]
+async fn any_id_is_accepted_without_response_id() {
+    let slot = ResponseSlot::new(Some(50));
+    slot.update_response_from(0x123, vec![0x02, 0x51, 0x01])
+        .await;
+    assert!(matches!(
+        slot.wait_timeout(slot.timeout()).await,
+        Some(Response::Ok(_, Some(0x123)))
+    ));
+}
+
+#[tokio::test]
+async fn responses_to_a_functional_request_tell_their_source() {
+    let slot = ResponseSlot::new(Some(50));
+    let mut sources = Vec::new();
+    for id in [0x7E8, 0x7E9] {
+        slot.update_response_from(id, vec![0x02, 0x7E, 0x00]).await;
+        let response = slot.wait_timeout(slot.timeout()).await.unwrap();
+        sources.push(response.source_id());
+    }
+    assert_eq!(sources, vec![Some(0x7E8), Some(0x7E9)]);
+
+    // Without its CAN ID, the source is unknown
+    slot.update_response(vec![0x02, 0x7E, 0x00]).await;
+    let response = slot.wait_timeout(slot.timeout()).await.unwrap();
+    assert!(response.as_frame().is_some());
+    assert_eq!(response.source_id(), None);
+}
+
+#[tokio::test]
+async fn read_data_by_identifier_round_trip_with_explicit_response_id() {
+    let mut vin = vec![0x62, 0xF1, 0x90];
+    vin.extend_from_slice(b"WVWZZZ1JZXW000001");
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::with_response_id(slot.clone(), 0x7F0, answer(&vin));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::with_response_id(channel, 0x784, Some(0x7F0), &slot);
+
+    let record = client.read_data_by_identifier(0xF190).await.unwrap();
+    assert_eq!(record, b"WVWZZZ1JZXW000001");
+    assert_eq!(
+        *sent.lock().unwrap(),
+        vec![vec![0x03, 0x22, 0xF1, 0x90], vec![0x30, 0x00, 0x0A]]
+    );
+}
+
+#[tokio::test]
+async fn answers_from_another_ecu_are_not_received() {
+    let slot = Arc::new(ResponseSlot::new(Some(50)));
+    let channel =
+        MockChannel::with_response_id(slot.clone(), 0x7E8, answer(&[0x62, 0xF1, 0x86, 0x01]));
+    let mut client = UdsClient::with_response_id(channel, 0x784, Some(0x7F0), &slot);
+
+    let result = client.read_data_by_identifier(0xF186).await;
+    assert!(matches!(result, Err(DiagError::Timeout)));
+}
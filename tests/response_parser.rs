@@ -0,0 +1,80 @@
+//! Decoding of the responses with a `UdsResponseParser`.
+
+mod common;
+
+use std::sync::Arc;
+
+use automotive_diag::uds::UdsCommand;
+use common::{MockChannel, answer};
+use uds_client::{DiagError, ResponseSlot, UdsClient, UdsFrame, UdsResponseParser};
+
+/// A user defined parser reading a 16 bits DID value.
+struct U16Did(u16);
+
+impl UdsResponseParser for U16Did {
+    type Output = u16;
+    const COMMAND: UdsCommand = UdsCommand::ReadDataByIdentifier;
+
+    fn parse_params(&self, params: &[u8]) -> Result<u16, DiagError> {
+        match params {
+            [high, low, value_high, value_low] if [*high, *low] == self.0.to_be_bytes() => {
+                Ok(u16::from_be_bytes([*value_high, *value_low]))
+            }
+            _ => Err(DiagError::InvalidResponseData),
+        }
+    }
+}
+
+#[tokio::test]
+async fn request_is_decoded_by_the_parser() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x62, 0x01, 0x02, 0x30, 0x39]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let value = client
+        .request_parsed(&U16Did(0x0102), &[0x01, 0x02])
+        .await
+        .unwrap();
+    assert_eq!(value, 12345);
+    assert_eq!(sent.lock().unwrap()[0], vec![0x03, 0x22, 0x01, 0x02]);
+}
+
+#[tokio::test]
+async fn negative_response_is_mapped_to_its_nrc() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x7F, 0x22, 0x31]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client.request_parsed(&U16Did(0x0102), &[0x01, 0x02]).await;
+    assert!(matches!(
+        result,
+        Err(DiagError::ECUError {
+            rsid: UdsCommand::ReadDataByIdentifier,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn frames_are_validated_before_decoding() {
+    let parser = U16Did(0x0102);
+    let negative = UdsFrame::from_vec(vec![0x03, 0x7F, 0x22, 0x31]).unwrap();
+    assert!(matches!(
+        parser.parse(&negative),
+        Err(DiagError::ECUError { .. })
+    ));
+    let other_service = UdsFrame::from_vec(vec![0x03, 0x6E, 0x01, 0x02]).unwrap();
+    assert!(matches!(
+        parser.parse(&other_service),
+        Err(DiagError::WrongMessage {
+            want: UdsCommand::ReadDataByIdentifier,
+            received: UdsCommand::WriteDataByIdentifier
+        })
+    ));
+    let consecutive = UdsFrame::from_vec(vec![0x21, 0x00]).unwrap();
+    assert!(matches!(
+        parser.parse(&consecutive),
+        Err(DiagError::WrongPciType { .. })
+    ));
+}
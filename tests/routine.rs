@@ -0,0 +1,89 @@
+//! RoutineControl requests and the decoding of the routine results.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{
+    DiagError, ROUTINE_CHECK_PROGRAMMING_DEPENDENCIES, ROUTINE_ERASE_MEMORY, ResponseSlot,
+    RoutineControlType, RoutineResult, UdsClient,
+};
+
+#[tokio::test]
+async fn routine_is_started_with_its_options() {
+    // eraseMemory of 0x1000 bytes at 0x08000000: the request is segmented
+    let mut script = vec![vec![vec![0x30, 0x00, 0x00]]];
+    script.extend(answer(&[0x71, 0x01, 0xFF, 0x00]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let options = [0x44, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00];
+    let status = client
+        .routine_control(
+            RoutineControlType::StartRoutine,
+            ROUTINE_ERASE_MEMORY,
+            &options,
+        )
+        .await
+        .unwrap();
+    assert!(status.is_empty());
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent[0], vec![0x10, 13, 0x31, 0x01, 0xFF, 0x00, 0x44, 0x08]);
+}
+
+#[tokio::test]
+async fn routine_results_are_decoded() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut script = answer(&[0x71, 0x03, 0xFF, 0x00, 0x00]);
+    script.extend(answer(&[0x71, 0x03, 0xFF, 0x01, 0x02, 0xAA]));
+    script.extend(answer(&[0x71, 0x03, 0x02, 0x10, 0x05, 0x06]));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let result = client.routine_results(ROUTINE_ERASE_MEMORY).await.unwrap();
+    assert_eq!(result, RoutineResult::EraseMemory(Ok(())));
+    let result = client
+        .routine_results(ROUTINE_CHECK_PROGRAMMING_DEPENDENCIES)
+        .await
+        .unwrap();
+    assert_eq!(
+        result,
+        RoutineResult::CheckProgrammingDependencies(Err(0x02))
+    );
+    let result = client.routine_results(0x0210).await.unwrap();
+    assert_eq!(
+        result,
+        RoutineResult::Other {
+            routine_id: 0x0210,
+            status: vec![0x05, 0x06]
+        }
+    );
+}
+
+#[tokio::test]
+async fn empty_status_of_a_known_routine_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x71, 0x03, 0xFF, 0x00]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client.routine_results(ROUTINE_ERASE_MEMORY).await;
+    assert!(matches!(error, Err(DiagError::InvalidResponseLength)));
+}
+
+#[tokio::test]
+async fn results_of_another_routine_are_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = answer(&[0x71, 0x03, 0xFF, 0x01, 0x00]);
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client.routine_results(ROUTINE_ERASE_MEMORY).await;
+    assert!(matches!(
+        error,
+        Err(DiagError::MismatchedIdentResponse {
+            want: 0xFF00,
+            received: 0xFF01
+        })
+    ));
+}
@@ -0,0 +1,129 @@
+//! Running the implemented services through `UdsClient::run_command`.
+
+mod common;
+
+use std::sync::Arc;
+
+use automotive_diag::uds::UdsCommand;
+use common::{MockChannel, answer};
+use uds_client::{CommandOutput, DiagError, Dtc, ResponseSlot, UdsClient};
+
+#[tokio::test]
+async fn implemented_command_returns_the_decoded_response() {
+    let mut vin = vec![0x62, 0xF1, 0x90];
+    vin.extend_from_slice(b"WVWZZZ1JZXW000001");
+    let mut script = answer(&[0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]);
+    script.extend(answer(&vin));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let output = client
+        .run_command(UdsCommand::DiagnosticSessionControl, &[0x03])
+        .await
+        .unwrap();
+    assert_eq!(output, CommandOutput::Done);
+    assert_eq!(sent.lock().unwrap()[0], vec![0x02, 0x10, 0x03]);
+
+    // Segmented responses are reassembled
+    let output = client
+        .run_command(UdsCommand::ReadDataByIdentifier, &[0xF1, 0x90])
+        .await
+        .unwrap();
+    assert_eq!(output, CommandOutput::Data(b"WVWZZZ1JZXW000001".to_vec()));
+}
+
+#[tokio::test]
+async fn ecu_reset_decodes_the_power_down_time() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x51, 0x04, 0x0A]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let output = client.run_command(UdsCommand::ECUReset, &[0x04]).await;
+    assert_eq!(output.unwrap(), CommandOutput::PowerDownTime(Some(0x0A)));
+}
+
+#[tokio::test]
+async fn service_parameters_are_decoded() {
+    let mut script = answer(&[0x59, 0x02, 0xFF, 0x12, 0x34, 0x56, 0x08]);
+    // The 11 bytes RequestDownload is segmented: a Flow Control answers its First Frame
+    script.push(vec![vec![0x30, 0x00, 0x00]]);
+    script.extend(answer(&[0x74, 0x20, 0x01, 0x02]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let output = client
+        .run_command(UdsCommand::ReadDTCInformation, &[0x02, 0x08])
+        .await;
+    let dtc = Dtc {
+        code: 0x123456,
+        status: 0x08,
+    };
+    assert_eq!(output.unwrap(), CommandOutput::Dtcs(vec![dtc]));
+
+    // The memory range of the request is decoded then encoded again by `request_download`
+    let args = [0x00, 0x44, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00];
+    let output = client.run_command(UdsCommand::RequestDownload, &args).await;
+    assert_eq!(output.unwrap(), CommandOutput::MaxBlockLength(0x0102));
+    assert_eq!(sent.lock().unwrap()[1][2..5], [0x34, 0x00, 0x44]);
+}
+
+#[tokio::test]
+async fn periodic_data_is_only_stopped() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x6A]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    // The periodic messages need the parsers of `read_periodic_data`
+    let result = client
+        .run_command(UdsCommand::ReadDataByPeriodicIdentifier, &[0x03, 0x01])
+        .await;
+    assert!(
+        matches!(result, Err(DiagError::NotImplemented(name)) if name == "ReadDataByPeriodicIdentifier")
+    );
+    let output = client
+        .run_command(UdsCommand::ReadDataByPeriodicIdentifier, &[0x04])
+        .await;
+    assert_eq!(output.unwrap(), CommandOutput::Done);
+}
+
+#[tokio::test]
+async fn unimplemented_command_is_named() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), Vec::new()), 0x7E0, &slot);
+
+    let result = client.run_command(UdsCommand::LinkControl, &[0x01]).await;
+    assert!(matches!(result, Err(DiagError::NotImplemented(name)) if name == "LinkControl"));
+}
+
+#[tokio::test]
+async fn sub_function_is_required_and_echoed() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x51, 0x03]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client.run_command(UdsCommand::ECUReset, &[]).await;
+    assert!(matches!(result, Err(DiagError::ParameterInvalid)));
+    let result = client.run_command(UdsCommand::ECUReset, &[0x81]).await;
+    assert!(matches!(result, Err(DiagError::ParameterInvalid)));
+
+    // A response echoing another sub-function
+    let result = client.run_command(UdsCommand::ECUReset, &[0x01]).await;
+    assert!(matches!(result, Err(DiagError::InvalidResponseData)));
+}
+
+#[tokio::test]
+async fn response_to_another_service_is_ignored() {
+    let slot = Arc::new(ResponseSlot::new(Some(50)));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x6E, 0xF1, 0x90]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    // The slot drops the frames that don't answer the request
+    let result = client
+        .run_command(UdsCommand::ReadDataByIdentifier, &[0xF1, 0x90])
+        .await;
+    assert!(matches!(result, Err(DiagError::Timeout)));
+}
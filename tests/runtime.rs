@@ -0,0 +1,72 @@
+//! Shutdown of the background tasks owned by a `UdsRuntime`.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use uds_client::{RxWatchdog, UdsRuntime};
+
+/// Sets its flag when dropped: tells that a task ended, whether it returned or was aborted.
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+#[tokio::test]
+async fn cancelled_task_returns_on_shutdown() {
+    let returned = Arc::new(AtomicBool::new(false));
+    let flag = returned.clone();
+    let mut runtime = UdsRuntime::new();
+    runtime.spawn(|cancel| async move {
+        cancel.cancelled().await;
+        flag.store(true, Ordering::Relaxed);
+    });
+
+    runtime.shutdown_timeout(Duration::from_secs(5)).await;
+    assert!(returned.load(Ordering::Relaxed));
+}
+
+#[tokio::test]
+async fn stuck_task_is_aborted_after_the_grace_period() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let guard = DropFlag(dropped.clone());
+    let mut runtime = UdsRuntime::new();
+    runtime.spawn(|_cancel| async move {
+        let _guard = guard;
+        std::future::pending::<()>().await;
+    });
+
+    runtime.shutdown_timeout(Duration::from_millis(20)).await;
+    assert!(dropped.load(Ordering::Relaxed));
+}
+
+#[tokio::test]
+async fn supervised_rx_task_is_stopped_with_its_watchdog() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let flag = dropped.clone();
+    let mut runtime = UdsRuntime::new();
+    runtime.track(
+        RxWatchdog::new(Duration::from_secs(1)).spawn(move |heartbeat| {
+            let guard = DropFlag(flag.clone());
+            tokio::spawn(async move {
+                let _guard = guard;
+                loop {
+                    heartbeat.beat();
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            })
+        }),
+    );
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    runtime.shutdown_timeout(Duration::from_millis(20)).await;
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(dropped.load(Ordering::Relaxed));
+}
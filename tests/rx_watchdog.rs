@@ -0,0 +1,61 @@
+//! Restart of a dead or stalled RX task by the `RxWatchdog`.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use uds_client::{Heartbeat, RxWatchdog};
+
+const GRACE: Duration = Duration::from_millis(20);
+
+/// Supervise the RX task `task` for `duration`, returns how many times it was started.
+async fn starts<F>(duration: Duration, task: F) -> usize
+where
+    F: Fn(Heartbeat) -> tokio::task::JoinHandle<()> + Send + 'static,
+{
+    let count = Arc::new(AtomicUsize::new(0));
+    let started = count.clone();
+    let supervisor = RxWatchdog::new(GRACE).spawn(move |heartbeat| {
+        started.fetch_add(1, Ordering::Relaxed);
+        task(heartbeat)
+    });
+    tokio::time::sleep(duration).await;
+    supervisor.abort();
+    count.load(Ordering::Relaxed)
+}
+
+#[tokio::test]
+async fn beating_task_is_not_restarted() {
+    let count = starts(GRACE * 10, |heartbeat| {
+        tokio::spawn(async move {
+            loop {
+                heartbeat.beat();
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+    })
+    .await;
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn stalled_task_is_restarted() {
+    let count = starts(GRACE * 10, |_heartbeat| {
+        tokio::spawn(std::future::pending::<()>())
+    })
+    .await;
+    assert!(count > 1, "started {count} times");
+}
+
+#[tokio::test]
+async fn dead_task_is_restarted() {
+    let count = starts(GRACE * 10, |_heartbeat| {
+        tokio::spawn(async { panic!("poisoned mutex") })
+    })
+    .await;
+    assert!(count > 1, "started {count} times");
+}
@@ -0,0 +1,79 @@
+//! Segmentation of a payload into its ISO-TP frames, on CAN and CAN FD.
+
+use uds_client::{DiagError, IsoTpSegmenter, UdsFrame};
+
+/// The encoded frames of `payload` for the data length `max_dlc`.
+fn encoded(payload: &[u8], max_dlc: usize) -> Vec<Vec<u8>> {
+    IsoTpSegmenter::new(payload, max_dlc)
+        .unwrap()
+        .map(|frame| frame.to_vec().unwrap())
+        .collect()
+}
+
+#[test]
+fn short_payload_is_a_single_frame() {
+    assert_eq!(
+        encoded(&[0x22, 0xF1, 0x90], 8),
+        vec![vec![0x03, 0x22, 0xF1, 0x90]]
+    );
+}
+
+#[test]
+fn long_payload_is_a_first_frame_and_consecutive_frames() {
+    let payload: Vec<u8> = (0..20).collect();
+    assert_eq!(
+        encoded(&payload, 8),
+        vec![
+            vec![0x10, 0x14, 0, 1, 2, 3, 4, 5],
+            vec![0x21, 6, 7, 8, 9, 10, 11, 12],
+            vec![0x22, 13, 14, 15, 16, 17, 18, 19],
+        ]
+    );
+}
+
+#[test]
+fn sequence_number_wraps_after_15() {
+    let payload = vec![0x36; 6 + 7 * 17];
+    let seq_nums: Vec<u8> = IsoTpSegmenter::new(&payload, 8)
+        .unwrap()
+        .filter_map(|frame| match frame {
+            UdsFrame::Consecutive(frame) => Some(frame.seq_num),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(seq_nums.len(), 17);
+    assert_eq!(&seq_nums[13..], &[14, 15, 0, 1]);
+}
+
+#[test]
+fn can_fd_frames_use_the_whole_data_length() {
+    // Escaped Single Frame up to TX_DL - 2 bytes
+    let frames = encoded(&[0x2E; 62], 64);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(&frames[0][..2], &[0x00, 62]);
+
+    let frames = encoded(&[0x2E; 100], 64);
+    assert_eq!(
+        frames.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![64, 39]
+    );
+}
+
+#[test]
+fn escaped_size_over_4095_bytes() {
+    let payload = vec![0x36; 5000];
+    let first = encoded(&payload, 8).remove(0);
+    assert_eq!(first, vec![0x10, 0x00, 0x00, 0x00, 0x13, 0x88, 0x36, 0x36]);
+}
+
+#[test]
+fn invalid_segmentation_is_rejected() {
+    assert!(matches!(
+        IsoTpSegmenter::new(&[], 8),
+        Err(DiagError::ParameterInvalid)
+    ));
+    assert!(matches!(
+        IsoTpSegmenter::new(&[0x3E, 0x00], 10),
+        Err(DiagError::ParameterInvalid)
+    ));
+}
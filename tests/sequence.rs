@@ -0,0 +1,98 @@
+//! Consecutive Frame sequence number validation, alone and during reassembly.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, ResponseSlot, UdsClient, UdsConsecutiveFrame};
+
+fn consecutive(seq_num: u8) -> UdsConsecutiveFrame {
+    UdsConsecutiveFrame::new(seq_num, vec![0x00; 7]).unwrap()
+}
+
+#[test]
+fn first_consecutive_frame_follows_the_first_frame() {
+    // The First Frame has the implied sequence number 0
+    assert!(consecutive(1).check_sequence(0).is_ok());
+    assert!(matches!(
+        consecutive(0).check_sequence(0),
+        Err(DiagError::SequenceError {
+            expected: 1,
+            received: 0
+        })
+    ));
+}
+
+#[test]
+fn sequence_number_wraps_from_15_to_0() {
+    assert!(consecutive(0).check_sequence(15).is_ok());
+    assert!(consecutive(1).check_sequence(0).is_ok());
+    assert!(matches!(
+        consecutive(0).check_sequence(14),
+        Err(DiagError::SequenceError {
+            expected: 15,
+            received: 0
+        })
+    ));
+}
+
+#[test]
+fn lost_or_repeated_frame_is_reported() {
+    for previous in 0..=15u8 {
+        let expected = (previous + 1) & 0x0F;
+        for received in (0..=15u8).filter(|seq_num| *seq_num != expected) {
+            let error = consecutive(received).check_sequence(previous).unwrap_err();
+            assert!(matches!(
+                error,
+                DiagError::SequenceError { expected: e, received: r } if e == expected && r == received
+            ));
+        }
+    }
+}
+
+/// Read a DID whose record is `len` bytes long, scripting the ECU answer with `tamper` applied
+/// to its Consecutive Frames.
+async fn read_did(
+    len: usize,
+    tamper: impl FnOnce(&mut Vec<Vec<u8>>),
+) -> Result<Vec<u8>, DiagError> {
+    let mut response = vec![0x62, 0x01, 0x00];
+    response.extend((0..len).map(|i| i as u8));
+    let mut script = answer(&response);
+    tamper(&mut script[1]);
+
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+    client.read_data_by_identifier(0x0100).await
+}
+
+#[tokio::test]
+async fn reassembly_wraps_the_sequence_number() {
+    // 3 + 150 bytes: 6 in the First Frame, then 21 Consecutive Frames (1..=15, 0..=5)
+    let record = read_did(150, |_| {}).await.unwrap();
+    assert_eq!(record, (0..150).map(|i| i as u8).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn reassembly_of_an_escaped_size_response() {
+    // 3 + 5000 bytes: over 4095 bytes, the First Frame announces the size on 32 bits
+    let record = read_did(5000, |_| {}).await.unwrap();
+    assert_eq!(record, (0..5000).map(|i| i as u8).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn reassembly_rejects_a_lost_consecutive_frame() {
+    let error = read_did(150, |frames| {
+        frames.remove(3);
+    })
+    .await
+    .unwrap_err();
+    assert!(matches!(
+        error,
+        DiagError::SequenceError {
+            expected: 4,
+            received: 5
+        }
+    ));
+}
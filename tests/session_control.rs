@@ -0,0 +1,71 @@
+//! DiagnosticSessionControl with the suppressPosRspMsgIndicationBit, answered or not.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, DiagnosticSessionType, ResponseSlot, UdsClient};
+
+#[tokio::test]
+async fn suppressed_session_control_without_response() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_suppress_positive_response(true);
+
+    let start = std::time::Instant::now();
+    client
+        .diagnostic_session_control(DiagnosticSessionType::ExtendedDiagnosticSession)
+        .await
+        .unwrap();
+    // Only the grace period is awaited, not the response timeout
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert_eq!(sent.lock().unwrap()[0], vec![0x02, 0x10, 0x83]);
+}
+
+#[tokio::test]
+async fn suppressed_session_control_answered_anyway() {
+    let mut script = answer(&[0x50, 0x83, 0x00, 0x32, 0x01, 0xF4]);
+    script.extend(answer(&[0x50, 0x01, 0x00, 0x32, 0x01, 0xF4]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+    client.set_suppress_positive_response(true);
+
+    client
+        .diagnostic_session_control(DiagnosticSessionType::ExtendedDiagnosticSession)
+        .await
+        .unwrap();
+    // The unexpected response was consumed, the next request gets its own
+    client.set_suppress_positive_response(false);
+    client
+        .diagnostic_session_control(DiagnosticSessionType::DefaultSession)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn suppressed_session_control_reports_negative_response() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x7F, 0x10, 0x22]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_suppress_positive_response(true);
+
+    let result = client
+        .diagnostic_session_control(DiagnosticSessionType::ProgrammingSession)
+        .await;
+    assert!(matches!(result, Err(DiagError::ECUError { .. })));
+}
+
+#[tokio::test]
+async fn session_control_checks_the_echoed_session() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x50, 0x01, 0x00, 0x32, 0x01, 0xF4]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client
+        .diagnostic_session_control(DiagnosticSessionType::ExtendedDiagnosticSession)
+        .await;
+    assert!(matches!(result, Err(DiagError::InvalidResponseData)));
+}
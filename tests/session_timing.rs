@@ -0,0 +1,68 @@
+//! Session timing (P2/P2*) sent by the ECU and applied to the response slot.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, DiagnosticSessionType, Response, ResponseSlot, UdsClient};
+
+#[tokio::test]
+async fn session_timing_reconfigures_the_slot() {
+    // P2 = 50ms, P2* = 500 * 10ms
+    let script = answer(&[0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let timing = client
+        .session_with_timing(DiagnosticSessionType::ExtendedDiagnosticSession, false)
+        .await
+        .unwrap();
+    assert_eq!(timing.p2, Duration::from_millis(50));
+    assert_eq!(timing.p2_star, Duration::from_secs(5));
+    assert_eq!(slot.timeout(), Duration::from_millis(100));
+    assert_eq!(slot.pending_timeout(), Some(Duration::from_millis(5050)));
+}
+
+#[tokio::test]
+async fn configured_timeouts_can_be_kept() {
+    let script = answer(&[0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]);
+    let slot = Arc::new(ResponseSlot::new(Some(2000)));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let timing = client
+        .session_with_timing(DiagnosticSessionType::ExtendedDiagnosticSession, true)
+        .await
+        .unwrap();
+    assert_eq!(timing.p2, Duration::from_millis(50));
+    assert_eq!(slot.timeout(), Duration::from_millis(2000));
+    assert_eq!(slot.pending_timeout(), None);
+}
+
+#[tokio::test]
+async fn session_without_timing_is_rejected() {
+    let script = answer(&[0x50, 0x03]);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let result = client
+        .session_with_timing(DiagnosticSessionType::ExtendedDiagnosticSession, false)
+        .await;
+    assert!(matches!(result, Err(DiagError::InvalidResponseLength)));
+    assert_eq!(slot.timeout(), Duration::from_millis(1000));
+}
+
+#[tokio::test]
+async fn pending_timeout_applies_after_response_pending() {
+    let slot = Arc::new(ResponseSlot::new(Some(20)));
+    slot.set_pending_timeout(Some(Duration::from_millis(500)));
+
+    let rx = slot.clone();
+    tokio::spawn(async move {
+        rx.update_response(vec![0x03, 0x7F, 0x31, 0x78]).await;
+        // Longer than the response timeout, within the pending timeout
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        rx.update_response(vec![0x04, 0x71, 0x01, 0xFF, 0x00]).await;
+    });
+    assert!(matches!(slot.wait_for_response().await, Response::Ok(_, _)));
+}
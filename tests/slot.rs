@@ -0,0 +1,49 @@
+//! Non-blocking access to the response slot, and the `Response` helpers.
+
+use uds_client::{Response, ResponseSlot, UdsFrame};
+
+#[tokio::test]
+async fn try_get_returns_a_ready_response_once() {
+    let slot = ResponseSlot::new(None);
+    assert!(slot.try_get().is_none());
+
+    slot.update_response(vec![0x02, 0x50, 0x03]).await;
+    assert!(matches!(
+        slot.try_get(),
+        Some(Response::Ok(UdsFrame::Single(frame), _)) if frame.sid == 0x50
+    ));
+    // Consumed by the first peek
+    assert!(slot.try_get().is_none());
+}
+
+#[tokio::test]
+async fn try_get_does_not_consume_while_locked() {
+    let slot = ResponseSlot::new(None);
+    slot.update_response(vec![0x02, 0x50, 0x03]).await;
+
+    let guard = slot.lock().await;
+    assert!(slot.try_get().is_none());
+    drop(guard);
+    assert!(matches!(slot.try_get(), Some(Response::Ok(_, _))));
+}
+
+#[tokio::test]
+async fn response_helpers_classify_the_response() {
+    let slot = ResponseSlot::new(None);
+    slot.update_response(vec![0x03, 0x7F, 0x22, 0x78]).await;
+    let pending = slot.try_get().unwrap();
+    assert!(pending.is_pending());
+    assert!(pending.as_frame().is_none());
+    assert!(pending.as_error().is_some());
+
+    slot.update_response(vec![0x03, 0x7F, 0x22, 0x31]).await;
+    let negative = slot.try_get().unwrap();
+    assert!(!negative.is_pending());
+    assert!(negative.as_error().is_some());
+
+    slot.update_response(vec![0x02, 0x50, 0x03]).await;
+    let positive = slot.try_get().unwrap();
+    assert!(!positive.is_pending());
+    assert!(matches!(positive.as_frame(), Some(UdsFrame::Single(frame)) if frame.sid == 0x50));
+    assert!(positive.as_error().is_none());
+}
@@ -0,0 +1,77 @@
+//! TesterPresent used as a ping of the ECU, and sent functionally to keep every ECU alive.
+
+mod common;
+
+use std::{
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use common::{MockChannel, answer};
+use embedded_can::{Frame, StandardId};
+use tokio_util::sync::CancellationToken;
+use uds_client::{
+    CanSocketRx, DiagError, FUNCTIONAL_REQUEST_ID, LoopbackSocket, ResponseSlot, UdsClient,
+};
+
+/// The functional client runs in a task of its own: its slot is a `static`.
+static FUNCTIONAL_SLOT: LazyLock<Arc<ResponseSlot>> =
+    LazyLock::new(|| Arc::new(ResponseSlot::new(None)));
+
+#[tokio::test]
+async fn ping_measures_the_round_trip() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x7E, 0x00]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_suppress_positive_response(true);
+
+    let rtt = client.ping().await.unwrap();
+    assert!(!rtt.is_zero());
+    assert_eq!(sent.lock().unwrap()[0], vec![0x02, 0x3E, 0x00]);
+}
+
+#[tokio::test]
+async fn silent_ecu_times_out() {
+    let slot = Arc::new(ResponseSlot::new(Some(50)));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), Vec::new()), 0x7E0, &slot);
+
+    assert!(matches!(client.ping().await, Err(DiagError::Timeout)));
+}
+
+#[tokio::test]
+async fn functional_tester_present_is_sent_periodically() {
+    let channel = MockChannel::new(FUNCTIONAL_SLOT.clone(), Vec::new());
+    let sent = channel.sent.clone();
+    let cancel = CancellationToken::new();
+
+    let id = StandardId::new(FUNCTIONAL_REQUEST_ID).unwrap();
+    let client = UdsClient::with_id(channel, id, &FUNCTIONAL_SLOT);
+    let task = client.functional_tester_present_all(Duration::from_millis(20), cancel.clone());
+    tokio::time::sleep(Duration::from_millis(70)).await;
+    cancel.cancel();
+    task.await.unwrap();
+
+    let sent = sent.lock().unwrap();
+    assert!(sent.len() >= 3);
+    // Suppressed positive response: nobody answers
+    assert!(sent.iter().all(|frame| *frame == vec![0x02, 0x3E, 0x80]));
+}
+
+#[tokio::test]
+async fn functional_tester_present_uses_a_standard_id() {
+    static SLOT: LazyLock<Arc<ResponseSlot>> = LazyLock::new(|| Arc::new(ResponseSlot::new(None)));
+    let (tx, mut rx) = LoopbackSocket::new().split();
+    let cancel = CancellationToken::new();
+
+    let id = StandardId::new(FUNCTIONAL_REQUEST_ID).unwrap();
+    let task = UdsClient::with_id(tx, id, &SLOT)
+        .functional_tester_present_all(Duration::from_millis(20), cancel.clone());
+    let received = rx.receive().await.unwrap();
+    cancel.cancel();
+    task.await.unwrap();
+
+    assert!(received.is_standard());
+    assert_eq!(received.raw_id(), FUNCTIONAL_REQUEST_ID as u32);
+    assert_eq!(received.data(), &[0x02, 0x3E, 0x80]);
+}
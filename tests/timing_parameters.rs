@@ -0,0 +1,88 @@
+//! AccessTimingParameter: reading and setting the P2/P2* timing of the ECU.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use common::{MockChannel, answer};
+use uds_client::{DiagError, FrameError, ResponseSlot, SessionTiming, UdsClient};
+
+#[tokio::test]
+async fn active_timing_is_read() {
+    // P2 = 25ms, P2* = 200 * 10ms
+    let script = answer(&[0xC3, 0x03, 0x00, 0x19, 0x00, 0xC8]);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let timing = client.get_timing_parameters().await.unwrap();
+    assert_eq!(sent.lock().unwrap()[0], vec![0x02, 0x83, 0x03]);
+    assert_eq!(timing.p2, Duration::from_millis(25));
+    assert_eq!(timing.p2_star, Duration::from_secs(2));
+    // Reading doesn't change the slot timeouts
+    assert_eq!(slot.timeout(), Duration::from_millis(1000));
+}
+
+#[tokio::test]
+async fn given_timing_is_set_and_applied() {
+    let script = answer(&[0xC3, 0x04]);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let timing = SessionTiming {
+        p2: Duration::from_millis(20),
+        p2_star: Duration::from_millis(1000),
+    };
+    client.set_timing_parameters(timing).await.unwrap();
+    assert_eq!(
+        sent.lock().unwrap()[0],
+        vec![0x06, 0x83, 0x04, 0x00, 0x14, 0x00, 0x64]
+    );
+    assert_eq!(slot.timeout(), Duration::from_millis(70));
+    assert_eq!(slot.pending_timeout(), Some(Duration::from_millis(1050)));
+}
+
+#[tokio::test]
+async fn timing_out_of_range_is_rejected() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let timing = SessionTiming {
+        p2: Duration::from_secs(70),
+        p2_star: Duration::from_secs(5),
+    };
+    let result = client.set_timing_parameters(timing).await;
+    assert!(matches!(result, Err(DiagError::ParameterInvalid)));
+    assert!(sent.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn response_of_another_sub_function_is_rejected() {
+    let script = answer(&[0xC3, 0x03, 0x00, 0x19, 0x00, 0xC8]);
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let result = client.read_extended_timing_parameters().await;
+    assert!(matches!(result, Err(DiagError::InvalidResponseData)));
+}
+
+#[tokio::test]
+async fn rejected_request_is_an_error() {
+    let script = answer(&[0x7F, 0x83, 0x31]);
+    let slot = Arc::new(ResponseSlot::new(Some(100)));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    // The ECUError of a negative response needs a `UdsCommand`, which lacks 0x83
+    let result = client.reset_timing_parameters().await;
+    assert!(matches!(
+        result,
+        Err(DiagError::FrameError {
+            error: FrameError::InvalidSid
+        })
+    ));
+}
@@ -0,0 +1,220 @@
+//! Download sequence: RequestDownload, TransferData blocks and RequestTransferExit.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use tokio::io::AsyncReadExt;
+use uds_client::{DataFormat, DiagError, ResponseSlot, UdsClient};
+
+/// Script the answer to the (segmented) RequestDownload, the ECU accepting TransferData requests
+/// of `max_block_length` bytes.
+fn request_download_script(max_block_length: u8) -> Vec<Vec<Vec<u8>>> {
+    let mut script = vec![vec![vec![0x30, 0x00, 0x00]]];
+    script.extend(answer(&[0x74, 0x10, max_block_length]));
+    script
+}
+
+/// Script a download of `blocks` blocks, the ECU accepting requests of `max_block_length` bytes.
+fn download_script(max_block_length: u8, blocks: usize) -> Vec<Vec<Vec<u8>>> {
+    let mut script = request_download_script(max_block_length);
+    for idx in 0..blocks {
+        script.extend(answer(&[0x76, (idx + 1) as u8]));
+    }
+    script.extend(answer(&[0x77]));
+    script
+}
+
+#[tokio::test]
+async fn file_is_transferred_in_blocks() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), download_script(7, 3));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let data: Vec<u8> = (1..=12).collect();
+    let mut progress = Vec::new();
+    client
+        .transfer_file(0x0800_0000, &data, 0x00, |done, total| {
+            progress.push((done, total))
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(progress, vec![(0, 12), (5, 12), (10, 12), (12, 12)]);
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent.len(), 2 + 3 + 1);
+    // RequestDownload: 11 bytes, segmented
+    assert_eq!(sent[0], vec![0x10, 11, 0x34, 0x00, 0x44, 0x08, 0x00, 0x00]);
+    assert_eq!(sent[1], vec![0x21, 0x00, 0x00, 0x00, 0x00, 0x0C]);
+    assert_eq!(sent[2], vec![0x07, 0x36, 0x01, 1, 2, 3, 4, 5]);
+    assert_eq!(sent[3], vec![0x07, 0x36, 0x02, 6, 7, 8, 9, 10]);
+    assert_eq!(sent[4], vec![0x04, 0x36, 0x03, 11, 12]);
+    assert_eq!(sent[5], vec![0x01, 0x37]);
+}
+
+#[tokio::test]
+async fn block_sequence_counter_wraps() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), download_script(3, 257));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client
+        .transfer_file(0x1000, &[0xAA; 257], 0x00, |_, _| {})
+        .await
+        .unwrap();
+
+    let sent = sent.lock().unwrap();
+    let counters: Vec<u8> = sent.iter().filter(|f| f[1] == 0x36).map(|f| f[2]).collect();
+    assert_eq!(counters.len(), 257);
+    assert_eq!(counters[..2], [0x01, 0x02]);
+    assert_eq!(counters[254..], [0xFF, 0x00, 0x01]);
+}
+
+#[tokio::test]
+async fn wrong_block_counter_stops_the_transfer() {
+    let mut script = request_download_script(7);
+    script.extend(answer(&[0x76, 0x02]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client
+        .transfer_file(0x1000, &[0xAA; 12], 0x00, |_, _| {})
+        .await
+        .unwrap_err();
+    assert!(matches!(error, DiagError::InvalidResponseData));
+}
+
+#[test]
+fn data_format_packs_into_a_byte() {
+    assert_eq!(u8::from(DataFormat::RAW), 0x00);
+    assert_eq!(u8::from(DataFormat::new(0x1, 0x2)), 0x12);
+    assert_eq!(DataFormat::from(0xA3), DataFormat::new(0xA, 0x3));
+}
+
+#[tokio::test]
+async fn data_format_is_sent_in_the_request_download() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), download_script(7, 1));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client
+        .transfer_file(0x1000, &[0xAA; 4], DataFormat::new(0x1, 0x2), |_, _| {})
+        .await
+        .unwrap();
+    assert_eq!(sent.lock().unwrap()[0][..4], [0x10, 11, 0x34, 0x12]);
+}
+
+#[tokio::test]
+async fn malformed_length_format_identifier_is_rejected() {
+    let mut script = vec![vec![vec![0x30, 0x00, 0x00]]];
+    script.extend(answer(&[0x74, 0x11, 7]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let error = client
+        .request_download(0x1000, 4, DataFormat::RAW)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, DiagError::InvalidResponseData));
+}
+
+#[tokio::test]
+async fn upload_is_requested() {
+    let mut script = vec![vec![vec![0x30, 0x00, 0x00]]];
+    script.extend(answer(&[0x75, 0x20, 0x01, 0x02]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let max_block_length = client
+        .request_upload(0x0800_0000, 0x100, DataFormat::RAW)
+        .await
+        .unwrap();
+    assert_eq!(max_block_length, 0x0102);
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent[0], vec![0x10, 11, 0x35, 0x00, 0x44, 0x08, 0x00, 0x00]);
+    assert_eq!(sent[1], vec![0x21, 0x00, 0x00, 0x00, 0x01, 0x00]);
+}
+
+#[tokio::test]
+async fn aborted_transfer_stops_and_exits() {
+    let mut script = request_download_script(7);
+    script.extend(answer(&[0x76, 0x01]));
+    script.extend(answer(&[0x77]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    // Aborted from the progress callback, once the first block is sent
+    let stop = client.transfer_stop_token();
+    let data: Vec<u8> = (1..=12).collect();
+    let result = client
+        .transfer_file(0x0800_0000, &data, 0x00, |done, _| {
+            if done > 0 {
+                stop.cancel();
+            }
+        })
+        .await;
+    assert!(matches!(result, Err(DiagError::TransferAborted)));
+    assert_eq!(sent.lock().unwrap().len(), 2 + 1);
+
+    client.abort_current_transfer(true).await.unwrap();
+    assert_eq!(sent.lock().unwrap()[3], vec![0x01, 0x37]);
+    // The next transfer starts with a fresh token
+    assert!(!client.transfer_stop_token().is_cancelled());
+}
+
+#[tokio::test]
+async fn reader_is_transferred_in_blocks() {
+    let mut script = Vec::new();
+    for counter in 1..=3 {
+        script.extend(answer(&[0x76, counter]));
+    }
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    // The first read stops in the middle of the first block
+    let data: Vec<u8> = (1..=10).collect();
+    let reader = (&data[..3]).chain(&data[3..]);
+    let mut progress = Vec::new();
+    let done = client
+        .transfer_data_stream(reader, 4, |done| progress.push(done))
+        .await
+        .unwrap();
+
+    assert_eq!(done, 10);
+    assert_eq!(progress, vec![4, 8, 10]);
+    assert_eq!(
+        *sent.lock().unwrap(),
+        vec![
+            vec![0x06, 0x36, 0x01, 1, 2, 3, 4],
+            vec![0x06, 0x36, 0x02, 5, 6, 7, 8],
+            vec![0x04, 0x36, 0x03, 9, 10],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn empty_reader_transfers_nothing() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let done = client
+        .transfer_data_stream(tokio::io::empty(), 4, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(done, 0);
+    assert!(sent.lock().unwrap().is_empty());
+    let result = client.transfer_data_stream(&[0xAA][..], 0, |_| {}).await;
+    assert!(matches!(result, Err(DiagError::ParameterInvalid)));
+}
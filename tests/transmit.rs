@@ -0,0 +1,158 @@
+//! Transmit of the frames: the frames displaced from the TX buffer are sent again, the frames
+//! are spaced by the minimum gap, and a TX buffer staying full is a TX timeout.
+
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use common::{MockChannel, MockError, MockFrame, answer};
+use embedded_can::{Frame, StandardId};
+use uds_client::{CanBusError, CanSocketTx, DiagError, ResponseSlot, UdsClient};
+
+/// The frames transmitted on the channel.
+type Sent = Arc<Mutex<Vec<Vec<u8>>>>;
+
+/// A channel whose first transmits each bump a lower priority frame out of its TX buffer.
+struct DisplacingChannel {
+    displaced: usize, // Number of transmits returning a displaced frame
+    sent: Sent,
+}
+
+impl CanSocketTx for DisplacingChannel {
+    type Frame = MockFrame;
+    type Error = MockError;
+
+    async fn transmit(&mut self, frame: &MockFrame) -> nb::Result<Option<MockFrame>, MockError> {
+        self.sent.lock().unwrap().push(frame.data().to_vec());
+        if self.displaced == 0 {
+            return Ok(None);
+        }
+        self.displaced -= 1;
+        let pending = MockFrame::new(StandardId::new(0x7E0).unwrap(), &[0x02, 0x3E, 0x80]);
+        Ok(pending)
+    }
+}
+
+fn client(slot: &Arc<ResponseSlot>, displaced: usize) -> (UdsClient<'_, DisplacingChannel>, Sent) {
+    let sent = Sent::default();
+    let channel = DisplacingChannel {
+        displaced,
+        sent: sent.clone(),
+    };
+    (UdsClient::new(channel, 0x7E0, slot), sent)
+}
+
+#[tokio::test]
+async fn displaced_frame_is_sent_again() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let (mut client, sent) = client(&slot, 1);
+
+    client.send_command(0x02, 0x10, &[0x03]).await.unwrap();
+    assert_eq!(
+        *sent.lock().unwrap(),
+        vec![vec![0x02, 0x10, 0x03], vec![0x02, 0x3E, 0x80]]
+    );
+}
+
+#[tokio::test]
+async fn channel_always_displacing_is_an_error() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let (mut client, sent) = client(&slot, usize::MAX);
+
+    let result = client.send_command(0x02, 0x10, &[0x03]).await;
+    assert!(matches!(result, Err(DiagError::ChannelError)));
+    // The request and 8 resends of the displaced frame
+    assert_eq!(sent.lock().unwrap().len(), 9);
+}
+
+#[tokio::test]
+async fn frames_are_spaced_by_the_minimum_gap() {
+    let mut script = vec![vec![vec![0x30, 0x00, 0x00]], vec![]];
+    script.extend(answer(&[0x6E, 0xF1, 0x90]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+    client.set_min_tx_gap(Duration::from_millis(20));
+
+    // A First Frame and two Consecutive Frames, without separation time
+    let mut request = vec![0x2E, 0xF1, 0x90];
+    request.extend([0x55; 17]);
+    let start = Instant::now();
+    client.raw_request(&request).await.unwrap();
+    assert_eq!(sent.lock().unwrap().len(), 3);
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
+
+/// A channel whose TX buffer is full for the first `busy` transmits.
+struct BusyChannel {
+    busy: usize, // Number of transmits returning `WouldBlock`
+    sent: Sent,
+}
+
+impl CanSocketTx for BusyChannel {
+    type Frame = MockFrame;
+    type Error = MockError;
+
+    async fn transmit(&mut self, frame: &MockFrame) -> nb::Result<Option<MockFrame>, MockError> {
+        if self.busy > 0 {
+            self.busy -= 1;
+            return Err(nb::Error::WouldBlock);
+        }
+        self.sent.lock().unwrap().push(frame.data().to_vec());
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn busy_tx_buffer_is_retried() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let sent = Sent::default();
+    let channel = BusyChannel {
+        busy: 3,
+        sent: sent.clone(),
+    };
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client.send_command(0x02, 0x10, &[0x03]).await.unwrap();
+    assert_eq!(*sent.lock().unwrap(), vec![vec![0x02, 0x10, 0x03]]);
+}
+
+#[tokio::test]
+async fn full_tx_buffer_is_a_tx_timeout() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = BusyChannel {
+        busy: usize::MAX,
+        sent: Sent::default(),
+    };
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client.send_command(0x02, 0x10, &[0x03]).await;
+    assert!(matches!(result, Err(DiagError::TxTimeout)));
+}
+
+#[tokio::test]
+async fn transmit_timeout_error_frame_fails_the_request() {
+    let slot = Arc::new(ResponseSlot::new(Some(500)));
+    let channel = MockChannel::new(slot.clone(), Vec::new());
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let reporter = slot.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        // Not related to the transmit of the request
+        reporter
+            .update_bus_error(CanBusError::ArbitrationLost(3))
+            .await;
+        reporter
+            .update_bus_error(CanBusError::TransmitTimeout)
+            .await;
+    });
+    let start = Instant::now();
+    let result = client.raw_request(&[0x22, 0xF1, 0x90]).await;
+    assert!(matches!(result, Err(DiagError::TxTimeout)));
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
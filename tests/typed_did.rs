@@ -0,0 +1,147 @@
+//! Typed reads of data identifiers.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{MockChannel, answer};
+use uds_client::{DID_VIN, DiagError, FromUdsBytes, IntoUdsBytes, ResponseSlot, UdsClient};
+
+#[derive(Debug, PartialEq)]
+struct Voltage {
+    millivolts: u16,
+    valid: bool,
+}
+
+impl FromUdsBytes for Voltage {
+    fn from_uds_bytes(data: &[u8]) -> Result<Self, DiagError> {
+        match data {
+            [high, low, flag] => Ok(Self {
+                millivolts: u16::from_be_bytes([*high, *low]),
+                valid: *flag == 0x01,
+            }),
+            _ => Err(DiagError::InvalidResponseLength),
+        }
+    }
+}
+
+#[test]
+fn integers_are_big_endian_and_sized() {
+    assert_eq!(
+        u32::from_uds_bytes(&[0x12, 0x34, 0x56, 0x78]).unwrap(),
+        0x12345678
+    );
+    assert_eq!(i16::from_uds_bytes(&[0xFF, 0xFE]).unwrap(), -2);
+    assert!(matches!(
+        u32::from_uds_bytes(&[0x12, 0x34]),
+        Err(DiagError::InvalidResponseLength)
+    ));
+    assert_eq!(
+        <[u8; 2]>::from_uds_bytes(&[0x01, 0x02]).unwrap(),
+        [0x01, 0x02]
+    );
+    assert!(<[u8; 2]>::from_uds_bytes(&[0x01]).is_err());
+}
+
+#[tokio::test]
+async fn read_did_as_integer() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(
+        slot.clone(),
+        answer(&[0x62, 0x01, 0x02, 0x00, 0x01, 0x86, 0xA0]),
+    );
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let value: u32 = client.read_did_as(0x0102).await.unwrap();
+    assert_eq!(value, 100_000);
+}
+
+#[tokio::test]
+async fn read_did_as_array_and_user_type() {
+    let mut vin = vec![0x62, 0xF1, 0x90];
+    vin.extend_from_slice(b"WVWZZZ1JZXW000001");
+    let mut script = answer(&vin);
+    script.extend(answer(&[0x62, 0x01, 0x10, 0x30, 0x39, 0x01]));
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(MockChannel::new(slot.clone(), script), 0x7E0, &slot);
+
+    let vin: [u8; 17] = client.read_did_as(DID_VIN).await.unwrap();
+    assert_eq!(&vin, b"WVWZZZ1JZXW000001");
+    let voltage: Voltage = client.read_did_as(0x0110).await.unwrap();
+    assert_eq!(
+        voltage,
+        Voltage {
+            millivolts: 12345,
+            valid: true
+        }
+    );
+}
+
+#[tokio::test]
+async fn read_did_as_rejects_a_record_of_another_size() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x62, 0x01, 0x02, 0x00, 0x01]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client.read_did_as::<u32>(0x0102).await;
+    assert!(matches!(result, Err(DiagError::InvalidResponseLength)));
+}
+
+#[test]
+fn values_are_serialized_big_endian() {
+    assert_eq!(0x12345678u32.into_uds_bytes(), vec![0x12, 0x34, 0x56, 0x78]);
+    assert_eq!((-2i16).into_uds_bytes(), vec![0xFF, 0xFE]);
+    assert_eq!([0x01u8, 0x02].into_uds_bytes(), vec![0x01, 0x02]);
+    assert_eq!("AB".into_uds_bytes(), vec![0x41, 0x42]);
+    assert_eq!(String::from("AB").into_uds_bytes(), vec![0x41, 0x42]);
+}
+
+#[tokio::test]
+async fn write_did_as_integer() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x6E, 0x01, 0x02]));
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client.write_did_as(0x0102, 100_000u32).await.unwrap();
+    assert_eq!(
+        *sent.lock().unwrap(),
+        vec![vec![0x07, 0x2E, 0x01, 0x02, 0x00, 0x01, 0x86, 0xA0]]
+    );
+}
+
+#[tokio::test]
+async fn write_did_as_string_is_segmented() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let script = vec![
+        vec![vec![0x30, 0x00, 0x00]],
+        vec![vec![0x03, 0x6E, 0xF1, 0x90]],
+    ];
+    let channel = MockChannel::new(slot.clone(), script);
+    let sent = channel.sent.clone();
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    client
+        .write_did_as(DID_VIN, "WVWZZZ1JZXW000001")
+        .await
+        .unwrap();
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent[0][..5], [0x10, 0x14, 0x2E, 0xF1, 0x90]);
+    assert_eq!(sent.len(), 3);
+}
+
+#[tokio::test]
+async fn write_did_as_checks_the_echoed_did() {
+    let slot = Arc::new(ResponseSlot::new(None));
+    let channel = MockChannel::new(slot.clone(), answer(&[0x6E, 0x01, 0x03]));
+    let mut client = UdsClient::new(channel, 0x7E0, &slot);
+
+    let result = client.write_did_as(0x0102, 0x01u8).await;
+    assert!(matches!(
+        result,
+        Err(DiagError::MismatchedIdentResponse {
+            want: 0x0102,
+            received: 0x0103
+        })
+    ));
+}
@@ -0,0 +1,195 @@
+//! UDS exchanges over a real SocketCAN socket, against an ECU stub on a virtual CAN bus.
+//!
+//! These tests need a `vcan0` interface, so they are ignored by default. The interface is created
+//! when missing, which requires the `CAP_NET_ADMIN` capability; otherwise set it up once with:
+//!
+//! ```sh
+//! sudo modprobe vcan
+//! sudo ip link add dev vcan0 type vcan
+//! sudo ip link set up vcan0
+//! ```
+//!
+//! and run them with `cargo test --test vcan -- --ignored`.
+#![cfg(target_os = "linux")]
+
+use std::{
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use socketcan::{
+    CanFilter, CanFrame, CanInterface, CanSocket, EmbeddedFrame, ExtendedId, Socket, SocketOptions,
+};
+use uds_client::{ResponseSlot, UdsClient, UdsFrame, UdsSocket};
+
+const VCAN: &str = "vcan0";
+const REQUEST_ID: u32 = 0x7E0;
+const RESPONSE_ID: u32 = 0x7E8;
+const VIN: &[u8; 17] = b"WVWZZZ1JZXW000001";
+
+/// Make sure `vcan0` exists and is up, creating it if needed.
+fn vcan() -> &'static str {
+    if CanInterface::open(VCAN).is_err() {
+        let iface = CanInterface::create_vcan(VCAN, None).unwrap_or_else(|e| {
+            panic!("{VCAN} is missing and can't be created ({e}), see the module documentation")
+        });
+        iface.bring_up().unwrap();
+    }
+    VCAN
+}
+
+/// A minimal ECU answering DiagnosticSessionControl (0x10), ECUReset (0x11) and
+/// ReadDataByIdentifier (0x22) of the VIN, any other request with serviceNotSupported.
+///
+/// The stub stops once no request was received for `idle`.
+struct EcuStub {
+    socket: CanSocket,
+}
+
+impl EcuStub {
+    fn spawn(idle: Duration) -> JoinHandle<()> {
+        let socket = CanSocket::open(vcan()).unwrap();
+        socket
+            .set_filters(&[CanFilter::new(REQUEST_ID, 0x1FFFFFFF)])
+            .unwrap();
+        let stub = Self { socket };
+        thread::spawn(move || {
+            while let Ok(frame) = stub.socket.read_frame_timeout(idle) {
+                stub.answer(frame.data());
+            }
+        })
+    }
+
+    fn send(&self, data: &[u8]) {
+        let id = ExtendedId::new(RESPONSE_ID).unwrap();
+        let frame = CanFrame::new(id, data).unwrap();
+        self.socket.write_frame(&frame).unwrap();
+    }
+
+    fn answer(&self, request: &[u8]) {
+        match request {
+            [0x02, 0x10, session, ..] => self.send(&[0x06, 0x50, *session, 0x00, 0x32, 0x01, 0xF4]),
+            [0x02, 0x11, kind, ..] => self.send(&[0x02, 0x51, *kind]),
+            [0x03, 0x22, 0xF1, 0x90, ..] => {
+                let mut payload = vec![0x62, 0xF1, 0x90];
+                payload.extend_from_slice(VIN);
+                self.send_segmented(&payload);
+            }
+            [size @ 0x01..=0x07, sid, ..] if request.len() > *size as usize => {
+                self.send(&[0x03, 0x7F, *sid, 0x11])
+            }
+            _ => {}
+        }
+    }
+
+    /// Send a payload over 7 bytes: First Frame, wait for our Flow Control, Consecutive Frames.
+    fn send_segmented(&self, payload: &[u8]) {
+        let mut first = vec![0x10 | (payload.len() >> 8) as u8, payload.len() as u8];
+        first.extend_from_slice(&payload[..6]);
+        self.send(&first);
+
+        let flow_control = self
+            .socket
+            .read_frame_timeout(Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(
+            flow_control.data()[0] & 0xF0,
+            0x30,
+            "expected a Flow Control frame"
+        );
+        for (idx, chunk) in payload[6..].chunks(7).enumerate() {
+            let mut frame = vec![0x20 | ((idx + 1) & 0x0F) as u8];
+            frame.extend_from_slice(chunk);
+            self.send(&frame);
+        }
+    }
+}
+
+/// Open the client side of the bus: the RX task feeds the returned slot.
+fn client_slot() -> (UdsSocket, Arc<ResponseSlot>) {
+    (
+        UdsSocket::new(vcan(), RESPONSE_ID),
+        Arc::new(ResponseSlot::new(None)),
+    )
+}
+
+#[tokio::test]
+#[ignore = "needs a vcan0 interface"]
+async fn exchange_over_vcan() {
+    let stub = EcuStub::spawn(Duration::from_millis(500));
+    let (socket, slot) = client_slot();
+    let (tx, mut rx) = socket.split();
+    let rx_slot = slot.clone();
+    let rx_task = tokio::spawn(async move {
+        while let Ok(frame) = rx.recv().await {
+            rx_slot.update_response(frame.data().to_vec()).await;
+        }
+    });
+    let mut client = UdsClient::new(tx, REQUEST_ID, &slot);
+
+    // DiagnosticSessionControl: extended session
+    let response = client.raw_request(&[0x10, 0x03]).await.unwrap();
+    assert_eq!(response, vec![0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]);
+
+    // ReadDataByIdentifier: multi-frame response
+    assert_eq!(client.read_vin().await.unwrap().as_bytes(), VIN);
+
+    // ECUReset: hard reset
+    client.uds_reset_ecu().await.unwrap();
+
+    // Unsupported service
+    assert!(client.raw_request(&[0x85, 0x01]).await.is_err());
+
+    rx_task.abort();
+    stub.join().unwrap();
+}
+
+#[test]
+#[ignore = "needs a vcan0 interface"]
+fn blocked_receive_does_not_stall_transmit() {
+    let (socket, _) = client_slot();
+    let (tx, mut rx) = socket.split();
+    let listener = CanSocket::open(vcan()).unwrap();
+    listener
+        .set_filters(&[CanFilter::new(REQUEST_ID, 0x1FFFFFFF)])
+        .unwrap();
+
+    // Nobody answers: the RX half stays blocked for the whole timeout
+    let receiver = thread::spawn(move || rx.receive_with_timeout(Duration::from_millis(500)));
+    thread::sleep(Duration::from_millis(50));
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let slot = Arc::new(ResponseSlot::new(None));
+    let mut client = UdsClient::new(tx, REQUEST_ID, &slot);
+    let start = std::time::Instant::now();
+    runtime
+        .block_on(client.send_frame(UdsFrame::from_vec(vec![0x02, 0x3E, 0x80]).unwrap()))
+        .unwrap();
+    assert!(start.elapsed() < Duration::from_millis(100));
+
+    let frame = listener
+        .read_frame_timeout(Duration::from_millis(100))
+        .unwrap();
+    assert_eq!(frame.data(), &[0x02, 0x3E, 0x80]);
+    assert!(receiver.join().unwrap().is_err());
+}
+
+#[test]
+#[ignore = "needs a vcan0 interface"]
+fn nonblocking_receive_returns_the_pending_frame() {
+    let (socket, _) = client_slot();
+    let (_tx, mut rx) = socket.split();
+    assert!(rx.try_receive().is_none());
+
+    let ecu = CanSocket::open(vcan()).unwrap();
+    let frame = CanFrame::new(ExtendedId::new(RESPONSE_ID).unwrap(), &[0x02, 0x7E, 0x00]).unwrap();
+    ecu.write_frame(&frame).unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    let received = rx.try_receive().unwrap();
+    assert_eq!(received.data(), &[0x02, 0x7E, 0x00]);
+    assert!(rx.try_receive().is_none());
+}